@@ -0,0 +1,86 @@
+//! Compares full and incremental ([`Text3dShapeCache`]) relayout of a large log-style text
+//! where a single [`FetchedTextSegment`] changes every frame.
+use bevy::{
+    app::App,
+    asset::{AssetApp, AssetPlugin},
+    ecs::entity::Entity,
+    image::Image,
+    pbr::StandardMaterial,
+    render::mesh::Mesh,
+    sprite::ColorMaterial,
+    MinimalPlugins,
+};
+use bevy_rich_text3d::{
+    FetchedTextSegment, SegmentStyle, Text3d, Text3dBounds, Text3dPlugin, Text3dSegment,
+    Text3dShapeCache, Text3dStyling, TextRenderer,
+};
+use cosmic_text::fontdb::Database;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const LINES: usize = 2000;
+
+fn app(incremental: bool) -> (App, Entity) {
+    let mut database = Database::new();
+    database.load_font_data(include_bytes!("../assets/Roboto-Regular.ttf").to_vec());
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Image>()
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<StandardMaterial>()
+        .insert_resource(TextRenderer::with_database("en-US", database))
+        .add_plugins(Text3dPlugin::default());
+    app.finish();
+    app.cleanup();
+
+    let fetched = app.world_mut().spawn(FetchedTextSegment::EMPTY).id();
+    let mut segments = Vec::new();
+    for line in 0..LINES {
+        let level = ["INFO", "WARN", "DEBUG"][line % 3];
+        segments.push((
+            Text3dSegment::String(format!(
+                "[{:02}:{:02}:{:02}] {level} server::session: handled request {line} in 12ms\n",
+                line / 3600 % 24,
+                line / 60 % 60,
+                line % 60
+            )),
+            SegmentStyle::default(),
+        ));
+    }
+    segments.push((Text3dSegment::Extract(fetched), SegmentStyle::default()));
+    let mut entity = app.world_mut().spawn((
+        Text3d { segments },
+        Text3dStyling {
+            font: "Roboto".into(),
+            ..Default::default()
+        },
+        Text3dBounds { width: 800.0 },
+    ));
+    if incremental {
+        entity.insert(Text3dShapeCache::default());
+    }
+    app.update();
+    (app, fetched)
+}
+
+fn relayout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("log_relayout");
+    for (name, incremental) in [("full", false), ("incremental", true)] {
+        let (mut app, fetched) = app(incremental);
+        let mut frame = 0u64;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                frame += 1;
+                app.world_mut()
+                    .get_mut::<FetchedTextSegment>(fetched)
+                    .unwrap()
+                    .0 = format!("{frame} requests pending");
+                app.update();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, relayout);
+criterion_main!(benches);
@@ -27,7 +27,7 @@ use bevy::{
 };
 use bevy_rich_text3d::{
     DrawStyle, LoadFonts, Text3d, Text3dPlugin, Text3dStyling, TextAtlas,
-    TextProgressReportCallback, TextRenderer,
+    TextProgressReportCallback, TextQuality, TextRenderer,
 };
 
 #[derive(Default)]
@@ -86,11 +86,13 @@ pub fn main() {
         .add_systems(
             Startup,
             move |settings: Res<Text3dPlugin>,
+                  quality: Res<TextQuality>,
                   text_renderer: Res<TextRenderer>,
                   mut atlases: ResMut<Assets<TextAtlas>>,
                   mut images: ResMut<Assets<Image>>| {
                 let task = text_renderer.prepare_images_cloned(
                     &settings,
+                    *quality,
                     [(
                         AssetId::default(),
                         (16..150).map(|x| {
@@ -15,7 +15,9 @@ use bevy::{
     sprite::{AlphaMode2d, ColorMaterial, MeshMaterial2d},
     DefaultPlugins,
 };
-use bevy_rich_text3d::{DrawStyle, Text3d, Text3dPlugin, Text3dStyling, TextAtlas, TextRenderer};
+use bevy_rich_text3d::{
+    DrawStyle, Text3d, Text3dPlugin, Text3dStyling, TextAtlas, TextQuality, TextRenderer,
+};
 
 pub fn main() {
     App::new()
@@ -34,11 +36,13 @@ pub fn main() {
         .add_systems(
             Startup,
             |settings: Res<Text3dPlugin>,
+             quality: Res<TextQuality>,
              text_renderer: Res<TextRenderer>,
              mut atlases: ResMut<Assets<TextAtlas>>,
              mut images: ResMut<Assets<Image>>| {
                 let task = text_renderer.prepare_images_cloned(
                     &settings,
+                    *quality,
                     [(
                         AssetId::default(),
                         [(
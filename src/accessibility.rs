@@ -0,0 +1,97 @@
+use accesskit::{Live, Node, Role};
+use bevy::{
+    a11y::AccessibilityNode,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        query::Changed,
+        system::{Commands, Query},
+        world::Ref,
+    },
+};
+
+use crate::{FetchedTextSegment, Text3d, Text3dSegment};
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+/// Publishes the plain text of a [`Text3d`] to screen readers as an [`AccessibilityNode`] with [`Role::Label`],
+/// see [`Text3d::resolve_plain`].
+///
+/// The node is updated when the text or any of its [`FetchedTextSegment`]s change.
+///
+/// Requires the `a11y` feature.
+#[derive(Debug, Clone, Copy, Component, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dAccessible {
+    /// If true, screen readers announce changes to the text as a polite live region,
+    /// for values like scores or status messages.
+    pub live: bool,
+}
+
+impl Text3dAccessible {
+    /// Announce changes to the text.
+    pub fn live() -> Self {
+        Text3dAccessible { live: true }
+    }
+}
+
+/// Updates [`AccessibilityNode`]s of [`Text3dAccessible`] text.
+pub fn accessibility_system(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        Ref<Text3d>,
+        Ref<Text3dAccessible>,
+        Option<&mut AccessibilityNode>,
+    )>,
+    segments: Query<&FetchedTextSegment>,
+    changed_segments: Query<(), Changed<FetchedTextSegment>>,
+) {
+    for (entity, text, accessible, node) in query.iter_mut() {
+        let fetched_changed = text.segments.iter().any(|(segment, _)| {
+            matches!(segment, Text3dSegment::Extract(entity) if changed_segments.contains(*entity))
+        });
+        let node_added = node.as_ref().is_some_and(|x| x.is_added());
+        if node.is_some()
+            && !node_added
+            && !text.is_changed()
+            && !accessible.is_changed()
+            && !fetched_changed
+        {
+            continue;
+        }
+        let value = text.resolve_plain(&segments);
+        let live = if accessible.live {
+            Live::Polite
+        } else {
+            Live::Off
+        };
+        match node {
+            Some(mut node) => {
+                if node.role() != Role::Label {
+                    node.set_role(Role::Label);
+                }
+                if node.value() != Some(value.as_str()) {
+                    node.set_value(value);
+                }
+                if node.live() != Some(live) {
+                    node.set_live(live);
+                }
+            }
+            None => {
+                let mut node = Node::new(Role::Label);
+                node.set_value(value);
+                node.set_live(live);
+                commands
+                    .entity(entity)
+                    .try_insert(AccessibilityNode::from(node));
+            }
+        }
+    }
+}
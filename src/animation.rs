@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use bevy::{
+    asset::Assets,
+    color::{ColorToComponents, LinearRgba, Srgba},
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
+        system::{Local, Query, Res, ResMut},
+        world::Ref,
+    },
+    math::{Vec2, Vec4},
+    render::mesh::{Mesh, Mesh2d, Mesh3d, VertexAttributeValues},
+    time::Time,
+};
+
+use crate::{mesh_util::SharedMeshes, GlyphLayoutInfo, Text3dLayoutInfo};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+/// Input of a [`Text3dGlyphAnimation`] function.
+#[derive(Debug, Clone, Copy)]
+pub struct GlyphAnimationInput<'t> {
+    /// Elapsed time in seconds.
+    pub time: f32,
+    /// Layout of the glyph, [`GlyphLayoutInfo::index`] is the glyph's index.
+    pub glyph: &'t GlyphLayoutInfo,
+    /// Number of glyphs in the text.
+    pub count: usize,
+}
+
+/// Output of a [`Text3dGlyphAnimation`] function, applied around the center of the glyph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphTransform {
+    /// Offset in local space.
+    pub offset: Vec2,
+    /// Scale around the center of the glyph.
+    pub scale: Vec2,
+    /// Counter-clockwise rotation in radians around the center of the glyph.
+    pub rotation: f32,
+    /// Multiplied with the colors of the glyph, including strokes and shadows.
+    pub tint: Srgba,
+}
+
+impl GlyphTransform {
+    pub const IDENTITY: Self = GlyphTransform {
+        offset: Vec2::ZERO,
+        scale: Vec2::ONE,
+        rotation: 0.0,
+        tint: Srgba::WHITE,
+    };
+
+    pub const fn from_offset(offset: Vec2) -> Self {
+        GlyphTransform {
+            offset,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub const fn from_tint(tint: Srgba) -> Self {
+        GlyphTransform {
+            tint,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub const fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub const fn with_scale(mut self, scale: Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub const fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub const fn with_tint(mut self, tint: Srgba) -> Self {
+        self.tint = tint;
+        self
+    }
+}
+
+impl Default for GlyphTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+type AnimateFn = dyn Fn(GlyphAnimationInput) -> GlyphTransform + Send + Sync;
+
+/// Animates each glyph of a [`Text3d`](crate::Text3d) on the CPU every frame,
+/// for effects like wave, shake or rainbow without writing a shader.
+///
+/// The text's mesh is modified in place, with `share_identical_meshes`
+/// the mesh is copied before being animated.
+/// The mesh is not modified if all glyphs stay at [`GlyphTransform::IDENTITY`].
+///
+/// # Example
+///
+/// ```
+/// # use bevy::math::Vec2;
+/// # use bevy_rich_text3d::{GlyphTransform, Text3dGlyphAnimation};
+/// // Wave.
+/// Text3dGlyphAnimation::new(|input| {
+///     let phase = input.time * 4.0 + input.glyph.index as f32 * 0.5;
+///     GlyphTransform::from_offset(Vec2::new(0.0, phase.sin() * 2.0))
+/// });
+/// ```
+#[derive(Component, Clone)]
+#[require(Text3dLayoutInfo)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct Text3dGlyphAnimation {
+    #[cfg_attr(feature = "reflect", reflect(ignore, default = "identity_animation"))]
+    animate: Arc<AnimateFn>,
+    /// If true, stops animating and keeps the current frame.
+    pub paused: bool,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    base: BaseVertices,
+}
+
+/// Animation used when [`Text3dGlyphAnimation`] is created from reflected data.
+#[cfg(feature = "reflect")]
+fn identity_animation() -> Arc<AnimateFn> {
+    Arc::new(|_| GlyphTransform::IDENTITY)
+}
+
+/// Vertices of the mesh before animation.
+#[derive(Debug, Clone, Default)]
+struct BaseVertices {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 4]>,
+    /// If false, the mesh is the same as `positions` and `colors`.
+    animated: bool,
+}
+
+impl Text3dGlyphAnimation {
+    pub fn new(
+        animate: impl Fn(GlyphAnimationInput) -> GlyphTransform + Send + Sync + 'static,
+    ) -> Self {
+        Text3dGlyphAnimation {
+            animate: Arc::new(animate),
+            paused: false,
+            base: BaseVertices::default(),
+        }
+    }
+}
+
+fn mesh_attributes(mesh: &mut Mesh) -> Option<(&mut Vec<[f32; 3]>, &mut Vec<[f32; 4]>)> {
+    let mut positions = None;
+    let mut colors = None;
+    for (attribute, values) in mesh.attributes_mut() {
+        match values {
+            VertexAttributeValues::Float32x3(values)
+                if attribute.id == Mesh::ATTRIBUTE_POSITION.id =>
+            {
+                positions = Some(values)
+            }
+            VertexAttributeValues::Float32x4(values)
+                if attribute.id == Mesh::ATTRIBUTE_COLOR.id =>
+            {
+                colors = Some(values)
+            }
+            _ => (),
+        }
+    }
+    Some((positions?, colors?))
+}
+
+/// Applies [`Text3dGlyphAnimation`] to text meshes after they are rendered.
+pub fn glyph_animation_system(
+    time: Res<Time>,
+    shared_meshes: Res<SharedMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut transforms: Local<Vec<GlyphTransform>>,
+    mut query: Query<(
+        &mut Text3dGlyphAnimation,
+        Ref<Text3dLayoutInfo>,
+        Option<&mut Mesh2d>,
+        Option<&mut Mesh3d>,
+    )>,
+) {
+    let now = time.elapsed_secs();
+    for (mut animation, layout, mut mesh2d, mut mesh3d) in query.iter_mut() {
+        let Some(mut handle) = mesh2d
+            .as_ref()
+            .map(|x| x.0.clone())
+            .or_else(|| mesh3d.as_ref().map(|x| x.0.clone()))
+        else {
+            continue;
+        };
+        let redrawn = layout.is_changed();
+        if animation.paused && !redrawn {
+            continue;
+        }
+        transforms.clear();
+        if !animation.paused {
+            let count = layout.glyphs.len();
+            transforms.extend(layout.glyphs.iter().map(|glyph| {
+                (animation.animate)(GlyphAnimationInput {
+                    time: now,
+                    glyph,
+                    count,
+                })
+            }));
+        }
+        let identity = transforms.iter().all(|x| *x == GlyphTransform::IDENTITY);
+        if identity && !redrawn && !animation.base.animated {
+            continue;
+        }
+        // Copy on write, since other entities may be using the same mesh.
+        if shared_meshes.is_shared(handle.id()) {
+            let Some(copy) = meshes.get(handle.id()).cloned() else {
+                continue;
+            };
+            handle = meshes.add(copy);
+            if let Some(mesh2d) = &mut mesh2d {
+                mesh2d.0 = handle.clone();
+            }
+            if let Some(mesh3d) = &mut mesh3d {
+                mesh3d.0 = handle.clone();
+            }
+        }
+        let Some(mesh) = meshes.get_mut(handle.id()) else {
+            continue;
+        };
+        let Some((positions, colors)) = mesh_attributes(mesh) else {
+            continue;
+        };
+        let animation = animation.bypass_change_detection();
+        let base = &mut animation.base;
+        // The mesh was redrawn, its vertices are not animated.
+        if redrawn || base.positions.len() != positions.len() {
+            base.positions.clone_from(positions);
+            base.colors.clone_from(colors);
+        } else {
+            positions.clone_from(&base.positions);
+            colors.clone_from(&base.colors);
+        }
+        base.animated = !identity;
+        if identity {
+            continue;
+        }
+        for (glyph, transform) in layout.glyphs.iter().zip(transforms.iter()) {
+            if glyph.dynamic {
+                continue;
+            }
+            let range = glyph.vertices.clone();
+            let Some(vertices) = positions.get_mut(range.clone()) else {
+                continue;
+            };
+            if vertices.is_empty() {
+                continue;
+            }
+            let center = vertices
+                .iter()
+                .map(|[x, y, _]| Vec2::new(*x, *y))
+                .sum::<Vec2>()
+                / vertices.len() as f32;
+            let rotation = Vec2::from_angle(transform.rotation);
+            for [x, y, _] in vertices {
+                let local = (Vec2::new(*x, *y) - center) * transform.scale;
+                let result = center + rotation.rotate(local) + transform.offset;
+                *x = result.x;
+                *y = result.y;
+            }
+            if transform.tint != Srgba::WHITE {
+                let tint = LinearRgba::from(transform.tint).to_vec4();
+                for color in colors.get_mut(range).into_iter().flatten() {
+                    *color = (Vec4::from_array(*color) * tint).to_array();
+                }
+            }
+        }
+    }
+}
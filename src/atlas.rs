@@ -1,11 +1,19 @@
 use bevy::{
-    asset::{Asset, Assets, Handle, RenderAssetUsages},
-    ecs::component::Component,
+    asset::{Asset, AssetId, Assets, Handle, RenderAssetUsages},
+    ecs::{
+        component::Component,
+        event::{Event, EventWriter},
+        resource::Resource,
+        system::{Local, Res},
+    },
     image::Image,
-    math::{IVec2, Rect, Vec2},
-    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    math::{IVec2, Rect, UVec2, Vec2},
+    render::render_resource::{
+        Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+    },
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::VecDeque;
 
 use crate::styling::GlyphEntry;
 
@@ -22,6 +30,8 @@ pub struct TextAtlas {
     pub(crate) glyphs: FxHashMap<GlyphEntry, (Rect, Vec2)>,
     pub(crate) pointer: IVec2,
     pub(crate) descent: usize,
+    /// If true, the image is a texture array that grows by adding layers.
+    pub(crate) array: bool,
 }
 
 const PADDING: usize = 2;
@@ -42,6 +52,27 @@ impl TextAtlas {
         }
     }
 
+    /// Create a new empty [`TextAtlas`] backed by a texture array,
+    /// when full a new layer is added instead of doubling the image's height.
+    ///
+    /// The integer part of `uv0.y` is the layer index and the fractional part is `v` in that layer,
+    /// so existing UVs stay valid when the atlas grows.
+    /// This requires a material that samples a texture array like [`TextAtlasArrayExtension`](crate::TextAtlasArrayExtension).
+    ///
+    /// The image can be created via [`TextAtlas::empty_array_image`].
+    pub fn new_array(image: Handle<Image>) -> Self {
+        Self {
+            image,
+            array: true,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if created via [`TextAtlas::new_array`].
+    pub fn is_array(&self) -> bool {
+        self.array
+    }
+
     /// Create an empty [`Image`] filled with transparent white `(255, 255, 255, 0)`.
     pub fn empty_image(width: usize, height: usize) -> Image {
         Image::new(
@@ -57,6 +88,16 @@ impl TextAtlas {
         )
     }
 
+    /// Create an empty texture array [`Image`] with one layer, filled with transparent white `(255, 255, 255, 0)`.
+    pub fn empty_array_image(width: usize, height: usize) -> Image {
+        let mut image = Self::empty_image(width, height);
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        image
+    }
+
     /// Cache a glyph.
     pub fn cache(
         &mut self,
@@ -83,8 +124,37 @@ impl TextAtlas {
                 image.data.as_mut().unwrap()[$($tt)*]
             };
         }
+        if self.array {
+            let page_height = image.height() as usize;
+            // Glyphs cannot span two layers.
+            if self.pointer.y as usize % page_height + self.descent.max(height) + PADDING
+                >= page_height
+            {
+                let page = self.pointer.y as usize / page_height + 1;
+                self.pointer = IVec2::new(0, (page * page_height) as i32);
+                self.descent = 0;
+            }
+            let page = self.pointer.y as usize / page_height;
+            let layers = image.texture_descriptor.size.depth_or_array_layers as usize;
+            if page >= layers {
+                let old_dim = image.width() as usize * page_height * layers;
+                image.resize(Extent3d {
+                    width: image.width(),
+                    height: image.height(),
+                    depth_or_array_layers: page as u32 + 1,
+                });
+                let new_dim = image.width() as usize * page_height * (page + 1);
+                for i in old_dim..new_dim {
+                    data![i * 4] = 255;
+                    data![i * 4 + 1] = 255;
+                    data![i * 4 + 2] = 255;
+                }
+            }
+        }
         self.descent = self.descent.max(height);
-        if self.pointer.y as usize + self.descent + PADDING >= image.height() as usize {
+        if !self.array
+            && self.pointer.y as usize + self.descent + PADDING >= image.height() as usize
+        {
             let old_dim = (image.width() * image.height()) as usize;
             image.resize(Extent3d {
                 width: image.width(),
@@ -129,6 +199,56 @@ impl TextAtlas {
     }
 }
 
+/// Atlases waiting to be cleared after a scale factor change, and atlases cleared since the last render.
+#[derive(Debug, Resource, Default)]
+pub(crate) struct AtlasClearQueue {
+    pub pending: VecDeque<AssetId<TextAtlas>>,
+    pub cleared: FxHashSet<AssetId<TextAtlas>>,
+}
+
+/// Event sent when the image of a [`TextAtlas`] is resized or cleared by this crate.
+///
+/// Systems that cache UVs or build custom meshes from an atlas can use this to update their data.
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub enum TextAtlasEvent {
+    /// The atlas grew, UVs normalized to the old dimension are no longer valid.
+    Resized {
+        atlas: AssetId<TextAtlas>,
+        old: UVec2,
+        new: UVec2,
+    },
+    /// All glyphs in the atlas are removed, this happens when the scale factor changes.
+    Cleared {
+        atlas: AssetId<TextAtlas>,
+        dimension: UVec2,
+    },
+}
+
+/// Sends [`TextAtlasEvent::Resized`] when an atlas' image changes size.
+pub fn atlas_event_system(
+    atlases: Res<Assets<TextAtlas>>,
+    images: Res<Assets<Image>>,
+    mut sizes: Local<FxHashMap<AssetId<TextAtlas>, UVec2>>,
+    mut events: EventWriter<TextAtlasEvent>,
+) {
+    sizes.retain(|id, _| atlases.contains(*id));
+    for (id, atlas) in atlases.iter() {
+        let Some(image) = images.get(atlas.image.id()) else {
+            continue;
+        };
+        let new = image.size();
+        if let Some(old) = sizes.insert(id, new) {
+            if old != new {
+                events.write(TextAtlasEvent::Resized {
+                    atlas: id,
+                    old,
+                    new,
+                });
+            }
+        }
+    }
+}
+
 /// [`Component`] of a [`Handle<TextAtlas>`](TextAtlas), if left as default,
 /// will use the shared [`TextAtlas::DEFAULT_IMAGE`] as
 /// the underlying image.
@@ -0,0 +1,141 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::{load_internal_asset, weak_handle, Asset, AssetId, Assets, Handle},
+    color::LinearRgba,
+    ecs::{
+        schedule::IntoScheduleConfigs,
+        system::{Local, Res, ResMut},
+    },
+    image::Image,
+    math::Vec2,
+    pbr::{ExtendedMaterial, MaterialExtension, MaterialPlugin, StandardMaterial},
+    reflect::Reflect,
+    render::render_resource::{AsBindGroup, Shader, ShaderRef},
+};
+
+use crate::change_detection::TouchMaterialSet;
+
+const ATLAS_EXTENSION_SHADER: Handle<Shader> = weak_handle!("5b0e6a8e-4f3c-4d51-9d4e-2f6c1a7e9b30");
+const ATLAS_ARRAY_EXTENSION_SHADER: Handle<Shader> =
+    weak_handle!("a3c7f1d2-8e64-4b0a-b5d9-61e2c4f87a13");
+const TWO_TONE_EXTENSION_SHADER: Handle<Shader> =
+    weak_handle!("d81f4b6e-27c9-4a35-9e0f-3b5a6c2d7e94");
+
+/// A [`StandardMaterial`] with a [`TextAtlasExtension`].
+pub type TextAtlasMaterial = ExtendedMaterial<StandardMaterial, TextAtlasExtension>;
+
+/// A [`MaterialExtension`] that converts `uv0` from pixels to normalized uv,
+/// use with [`Text3dPlugin::pixel_uvs`](crate::Text3dPlugin::pixel_uvs).
+///
+/// `atlas_size` is synchronized with the size of `base_color_texture` by [`TextAtlasExtensionPlugin`].
+#[derive(Debug, Clone, Default, Asset, AsBindGroup, Reflect)]
+pub struct TextAtlasExtension {
+    #[uniform(100)]
+    pub atlas_size: Vec2,
+}
+
+impl MaterialExtension for TextAtlasExtension {
+    fn fragment_shader() -> ShaderRef {
+        ATLAS_EXTENSION_SHADER.into()
+    }
+}
+
+/// A [`StandardMaterial`] with a [`TextAtlasArrayExtension`].
+pub type TextAtlasArrayMaterial = ExtendedMaterial<StandardMaterial, TextAtlasArrayExtension>;
+
+/// A [`MaterialExtension`] that samples a texture array atlas created by [`TextAtlas::new_array`](crate::TextAtlas::new_array),
+/// the integer part of `uv0.y` is the layer.
+///
+/// The sample is multiplied with the base color,
+/// `base_color_texture` should be left empty since it cannot be a texture array.
+#[derive(Debug, Clone, Default, Asset, AsBindGroup, Reflect)]
+pub struct TextAtlasArrayExtension {
+    #[texture(101, dimension = "2d_array")]
+    #[sampler(102)]
+    pub atlas: Handle<Image>,
+}
+
+impl MaterialExtension for TextAtlasArrayExtension {
+    fn fragment_shader() -> ShaderRef {
+        ATLAS_ARRAY_EXTENSION_SHADER.into()
+    }
+}
+
+/// A [`StandardMaterial`] with a [`TextTwoToneExtension`].
+pub type TextTwoToneMaterial = ExtendedMaterial<StandardMaterial, TextTwoToneExtension>;
+
+/// A [`MaterialExtension`] that renders text drawn with [`Text3dStyling::two_tone`](crate::Text3dStyling::two_tone),
+/// the fill uses the vertex color and the stroke uses `stroke_color`.
+///
+/// `base_color_texture` should be left empty, the atlas is sampled from `atlas` instead.
+#[derive(Debug, Clone, Default, Asset, AsBindGroup, Reflect)]
+pub struct TextTwoToneExtension {
+    #[uniform(103)]
+    pub stroke_color: LinearRgba,
+    #[texture(104)]
+    #[sampler(105)]
+    pub atlas: Handle<Image>,
+}
+
+impl MaterialExtension for TextTwoToneExtension {
+    fn fragment_shader() -> ShaderRef {
+        TWO_TONE_EXTENSION_SHADER.into()
+    }
+}
+
+/// Adds [`TextAtlasMaterial`], [`TextAtlasArrayMaterial`] and [`TextTwoToneMaterial`], and keeps its `atlas_size` up to date.
+#[derive(Debug, Default)]
+pub struct TextAtlasExtensionPlugin;
+
+impl Plugin for TextAtlasExtensionPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            ATLAS_EXTENSION_SHADER,
+            "atlas_extension.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            ATLAS_ARRAY_EXTENSION_SHADER,
+            "atlas_array_extension.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            TWO_TONE_EXTENSION_SHADER,
+            "two_tone_extension.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins(MaterialPlugin::<TextAtlasMaterial>::default());
+        app.add_plugins(MaterialPlugin::<TextTwoToneMaterial>::default());
+        app.add_plugins(MaterialPlugin::<TextAtlasArrayMaterial>::default());
+        app.add_systems(PostUpdate, synchronize_atlas_size.in_set(TouchMaterialSet));
+    }
+}
+
+fn synchronize_atlas_size(
+    images: Res<Assets<Image>>,
+    mut materials: ResMut<Assets<TextAtlasMaterial>>,
+    mut changed: Local<Vec<(AssetId<TextAtlasMaterial>, Vec2)>>,
+) {
+    for (id, material) in materials.iter() {
+        let Some(image) = material
+            .base
+            .base_color_texture
+            .as_ref()
+            .and_then(|x| images.get(x.id()))
+        else {
+            continue;
+        };
+        let size = image.size_f32();
+        if material.extension.atlas_size != size {
+            changed.push((id, size));
+        }
+    }
+    for (id, size) in changed.drain(..) {
+        if let Some(material) = materials.get_mut(id) {
+            material.extension.atlas_size = size;
+        }
+    }
+}
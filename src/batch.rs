@@ -0,0 +1,471 @@
+use std::ops::Range;
+
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::{AssetEvent, AssetId, Assets, Handle, RenderAssetUsages},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::{Changed, Or, With, Without},
+        removal_detection::RemovedComponents,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, ResMut},
+    },
+    math::{Affine3A, Vec3},
+    render::{
+        mesh::{Indices, Mesh, Mesh2d, Mesh3d, MeshAabb, PrimitiveTopology, VertexAttributeValues},
+        primitives::Aabb,
+    },
+    transform::{components::GlobalTransform, TransformSystem},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{mesh_util::TEXT_TANGENT, Text3d, ATTRIBUTE_GLYPH_META};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent, ReflectDefault};
+
+/// Merges the meshes of many small [`Text3d`] labels, like damage numbers or nametags,
+/// into a single mesh drawn with one material, see [`Text3dBatched`].
+///
+/// Add a [`Mesh2d`] or [`Mesh3d`] and a material using the labels' [`TextAtlas`](crate::TextAtlas),
+/// the mesh is created if it does not exist.
+/// When labels are moved or redrawn with the same number of glyphs, only their vertices are rewritten,
+/// the mesh is rebuilt when labels are added, removed or change size.
+///
+/// Requires [`Text3dBatchPlugin`].
+#[derive(Debug, Clone, Component, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dBatch;
+
+/// Adds a [`Text3d`] to a [`Text3dBatch`], with its [`GlobalTransform`] baked into the batch's mesh.
+///
+/// The label must have a [`Mesh2d`] or [`Mesh3d`] to render to, but no material,
+/// so it is not drawn individually.
+#[derive(Debug, Clone, Copy, Component)]
+#[require(Text3d)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct Text3dBatched {
+    /// The entity with [`Text3dBatch`].
+    pub batch: Entity,
+}
+
+fn mesh_id(mesh2d: Option<&Mesh2d>, mesh3d: Option<&Mesh3d>) -> Option<AssetId<Mesh>> {
+    mesh2d.map(|x| x.id()).or_else(|| mesh3d.map(|x| x.id()))
+}
+
+fn batch_mesh() -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
+}
+
+/// Vertices and indices of a label in its batch's mesh.
+#[derive(Debug, Clone)]
+pub(crate) struct BatchRange {
+    label: Entity,
+    vertices: Range<usize>,
+    indices: Range<usize>,
+}
+
+/// Vertex data of a batch being rebuilt.
+#[derive(Default)]
+pub(crate) struct BatchBuffer {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uv0: Vec<[f32; 2]>,
+    uv1: Vec<[f32; 2]>,
+    glyph_meta: Vec<[f32; 4]>,
+    tangents: Vec<[f32; 4]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl BatchBuffer {
+    fn clear(&mut self) {
+        self.positions.clear();
+        self.normals.clear();
+        self.uv0.clear();
+        self.uv1.clear();
+        self.glyph_meta.clear();
+        self.tangents.clear();
+        self.colors.clear();
+        self.indices.clear();
+    }
+
+    /// Append a text mesh transformed by `transform`, returns false if its attributes are missing.
+    fn append(&mut self, mesh: &Mesh, transform: Affine3A) -> bool {
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(VertexAttributeValues::Float32x2(uv0)),
+            Some(VertexAttributeValues::Float32x2(uv1)),
+            Some(VertexAttributeValues::Float32x4(colors)),
+            Some(indices),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+            mesh.attribute(Mesh::ATTRIBUTE_UV_1),
+            mesh.attribute(Mesh::ATTRIBUTE_COLOR),
+            mesh.indices(),
+        )
+        else {
+            return false;
+        };
+        let offset = self.positions.len() as u32;
+        self.positions.extend(
+            positions
+                .iter()
+                .map(|p| transform.transform_point3(Vec3::from_array(*p)).to_array()),
+        );
+        self.normals.extend(normals.iter().map(|n| {
+            transform
+                .transform_vector3(Vec3::from_array(*n))
+                .normalize_or_zero()
+                .to_array()
+        }));
+        self.uv0.extend_from_slice(uv0);
+        self.uv1.extend_from_slice(uv1);
+        if let Some(VertexAttributeValues::Float32x4(tangents)) =
+            mesh.attribute(Mesh::ATTRIBUTE_TANGENT)
+        {
+            self.tangents.resize(offset as usize, TEXT_TANGENT);
+            self.tangents.extend(tangents.iter().map(|[x, y, z, w]| {
+                let v = transform.transform_vector3(Vec3::new(*x, *y, *z));
+                let [x, y, z] = v.normalize_or_zero().to_array();
+                [x, y, z, *w]
+            }));
+        }
+        // Texts without metadata are zeroed if batched with texts that have it.
+        if let Some(VertexAttributeValues::Float32x4(glyph_meta)) =
+            mesh.attribute(ATTRIBUTE_GLYPH_META)
+        {
+            self.glyph_meta.resize(offset as usize, [0.0; 4]);
+            self.glyph_meta.extend_from_slice(glyph_meta);
+        }
+        self.colors.extend_from_slice(colors);
+        self.indices
+            .extend(indices.iter().map(|i| i as u32 + offset));
+        true
+    }
+
+    /// Overwrite the vertices and indices of a single label in a batch's mesh with the appended label,
+    /// returns false if the label's size or attributes changed and the batch must be rebuilt.
+    fn patch(&mut self, mesh: &mut Mesh, vertices: Range<usize>, indices: Range<usize>) -> bool {
+        let len = self.positions.len();
+        if len != vertices.len() || self.indices.len() != indices.len() {
+            return false;
+        }
+        // Attributes not in the batch's mesh cannot be added in place.
+        if !self.tangents.is_empty() && !mesh.contains_attribute(Mesh::ATTRIBUTE_TANGENT) {
+            return false;
+        }
+        if !self.glyph_meta.is_empty() && !mesh.contains_attribute(ATTRIBUTE_GLYPH_META) {
+            return false;
+        }
+        if mesh.contains_attribute(Mesh::ATTRIBUTE_TANGENT) {
+            self.tangents.resize(len, TEXT_TANGENT);
+        }
+        if mesh.contains_attribute(ATTRIBUTE_GLYPH_META) {
+            self.glyph_meta.resize(len, [0.0; 4]);
+        }
+        macro_rules! patch {
+            ($attribute: expr, $variant: ident, $values: expr) => {
+                if !$values.is_empty() {
+                    let Some(VertexAttributeValues::$variant(values)) =
+                        mesh.attribute_mut($attribute)
+                    else {
+                        return false;
+                    };
+                    let Some(values) = values.get_mut(vertices.clone()) else {
+                        return false;
+                    };
+                    values.copy_from_slice(&$values);
+                }
+            };
+        }
+        patch!(Mesh::ATTRIBUTE_POSITION, Float32x3, self.positions);
+        patch!(Mesh::ATTRIBUTE_NORMAL, Float32x3, self.normals);
+        patch!(Mesh::ATTRIBUTE_UV_0, Float32x2, self.uv0);
+        patch!(Mesh::ATTRIBUTE_UV_1, Float32x2, self.uv1);
+        patch!(Mesh::ATTRIBUTE_TANGENT, Float32x4, self.tangents);
+        patch!(ATTRIBUTE_GLYPH_META, Float32x4, self.glyph_meta);
+        patch!(Mesh::ATTRIBUTE_COLOR, Float32x4, self.colors);
+        let Some(Indices::U32(values)) = mesh.indices_mut() else {
+            return false;
+        };
+        let Some(values) = values.get_mut(indices) else {
+            return false;
+        };
+        for (value, index) in values.iter_mut().zip(&self.indices) {
+            *value = index + vertices.start as u32;
+        }
+        true
+    }
+
+    fn write(&mut self, mesh: &mut Mesh) {
+        if !self.glyph_meta.is_empty() {
+            self.glyph_meta.resize(self.positions.len(), [0.0; 4]);
+        }
+        if !self.tangents.is_empty() {
+            self.tangents.resize(self.positions.len(), TEXT_TANGENT);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, std::mem::take(&mut self.tangents));
+        } else {
+            mesh.remove_attribute(Mesh::ATTRIBUTE_TANGENT);
+        }
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            std::mem::take(&mut self.positions),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, std::mem::take(&mut self.normals));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, std::mem::take(&mut self.uv0));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, std::mem::take(&mut self.uv1));
+        if !self.glyph_meta.is_empty() {
+            mesh.insert_attribute(ATTRIBUTE_GLYPH_META, std::mem::take(&mut self.glyph_meta));
+        } else {
+            mesh.remove_attribute(ATTRIBUTE_GLYPH_META);
+        }
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, std::mem::take(&mut self.colors));
+        mesh.insert_indices(Indices::U32(std::mem::take(&mut self.indices)));
+    }
+}
+
+/// Merges the meshes of [`Text3dBatched`] labels into their [`Text3dBatch`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dBatchPlugin;
+
+impl Plugin for Text3dBatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            text_batch_system.after(TransformSystem::TransformPropagate),
+        );
+        #[cfg(feature = "reflect")]
+        app.register_type::<Text3dBatch>()
+            .register_type::<Text3dBatched>();
+    }
+}
+
+/// Rebuilds the meshes of [`Text3dBatch`]es whose labels changed.
+pub fn text_batch_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut removed: RemovedComponents<Text3dBatched>,
+    mut batches: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Option<&mut Mesh2d>,
+            Option<&mut Mesh3d>,
+        ),
+        (With<Text3dBatch>, Without<Text3dBatched>),
+    >,
+    labels: Query<(
+        Entity,
+        &Text3dBatched,
+        &GlobalTransform,
+        Option<&Mesh2d>,
+        Option<&Mesh3d>,
+    )>,
+    changed: Query<
+        (Entity, &Text3dBatched),
+        Or<(
+            Changed<Text3dBatched>,
+            Changed<GlobalTransform>,
+            Changed<Mesh2d>,
+            Changed<Mesh3d>,
+        )>,
+    >,
+    mut dirty: Local<FxHashSet<Entity>>,
+    mut dirty_labels: Local<FxHashSet<Entity>>,
+    mut members: Local<FxHashMap<Entity, Vec<Entity>>>,
+    mut layouts: Local<FxHashMap<Entity, Vec<BatchRange>>>,
+    mut buffer: Local<BatchBuffer>,
+) {
+    let modified: FxHashSet<AssetId<Mesh>> = mesh_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } | AssetEvent::Added { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    // Removed labels are not associated with a batch anymore, rebuild all batches.
+    let rebuild_all = removed.read().count() > 0;
+    for (entity, label) in changed.iter() {
+        dirty.insert(label.batch);
+        dirty_labels.insert(entity);
+    }
+    for (entity, label, _, mesh2d, mesh3d) in labels.iter() {
+        if mesh_id(mesh2d, mesh3d).is_some_and(|id| modified.contains(&id)) {
+            dirty.insert(label.batch);
+            dirty_labels.insert(entity);
+        }
+    }
+    if !rebuild_all && dirty.is_empty() {
+        return;
+    }
+    members.values_mut().for_each(Vec::clear);
+    for (entity, label, ..) in labels.iter() {
+        members.entry(label.batch).or_default().push(entity);
+    }
+    layouts.retain(|entity, _| batches.contains(*entity));
+    for (entity, transform, mut mesh2d, mut mesh3d) in batches.iter_mut() {
+        if !rebuild_all && !dirty.contains(&entity) {
+            continue;
+        }
+        let inverse = transform.affine().inverse();
+        let members = members.get(&entity).map(Vec::as_slice).unwrap_or_default();
+        let handle = match (&mesh2d, &mesh3d) {
+            (Some(mesh), _) if meshes.contains(mesh.id()) => mesh.0.clone(),
+            (_, Some(mesh)) if meshes.contains(mesh.id()) => mesh.0.clone(),
+            _ => {
+                let handle: Handle<Mesh> = meshes.add(batch_mesh());
+                if let Some(mesh2d) = &mut mesh2d {
+                    mesh2d.0 = handle.clone();
+                }
+                if let Some(mesh3d) = &mut mesh3d {
+                    mesh3d.0 = handle.clone();
+                }
+                layouts.remove(&entity);
+                handle
+            }
+        };
+        // Taken out of `meshes` so labels' meshes can be read while writing to it.
+        let Some(mut batch) = meshes
+            .get_mut(handle.id())
+            .map(|mesh| std::mem::replace(mesh, batch_mesh()))
+        else {
+            continue;
+        };
+        let layout = layouts.entry(entity).or_default();
+        // Rewrite changed labels in place if the batch has the same labels in the same order.
+        let patched = !rebuild_all
+            && layout.iter().map(|x| x.label).eq(members.iter().copied())
+            && layout
+                .iter()
+                .filter(|range| dirty_labels.contains(&range.label))
+                .all(|range| {
+                    let Ok((_, _, label_transform, mesh2d, mesh3d)) = labels.get(range.label)
+                    else {
+                        return false;
+                    };
+                    buffer.clear();
+                    if let Some(mesh) = mesh_id(mesh2d, mesh3d).and_then(|id| meshes.get(id)) {
+                        buffer.append(mesh, inverse * label_transform.affine());
+                    }
+                    buffer.patch(&mut batch, range.vertices.clone(), range.indices.clone())
+                });
+        if !patched {
+            buffer.clear();
+            layout.clear();
+            for label in members {
+                let (vertices, indices) = (buffer.positions.len(), buffer.indices.len());
+                if let Ok((_, _, label_transform, mesh2d, mesh3d)) = labels.get(*label) {
+                    if let Some(mesh) = mesh_id(mesh2d, mesh3d).and_then(|id| meshes.get(id)) {
+                        buffer.append(mesh, inverse * label_transform.affine());
+                    }
+                }
+                layout.push(BatchRange {
+                    label: *label,
+                    vertices: vertices..buffer.positions.len(),
+                    indices: indices..buffer.indices.len(),
+                });
+            }
+            buffer.write(&mut batch);
+        }
+        // Bevy does not update bounding boxes when a mesh is modified.
+        if let Some(aabb) = batch.compute_aabb() {
+            commands.entity(entity).try_insert(aabb);
+        } else {
+            commands.entity(entity).try_remove::<Aabb>();
+        }
+        if let Some(mesh) = meshes.get_mut(handle.id()) {
+            *mesh = batch;
+        }
+    }
+    dirty_labels.clear();
+    dirty.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::{App, Update},
+        asset::{AssetApp, AssetPlugin, Assets, RenderAssetUsages},
+        math::Vec3,
+        render::mesh::{Indices, Mesh, Mesh3d, PrimitiveTopology, VertexAttributeValues},
+        transform::components::{GlobalTransform, Transform},
+        MinimalPlugins,
+    };
+
+    use super::{text_batch_system, Text3dBatch, Text3dBatched};
+
+    fn label_mesh() -> Mesh {
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
+            .with_inserted_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+            )
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 3])
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; 3])
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_1, vec![[0.0, 0.0]; 3])
+            .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vec![[1.0; 4]; 3])
+            .with_inserted_indices(Indices::U32(vec![0, 1, 2]))
+    }
+
+    fn positions(app: &App, batch: bevy::ecs::entity::Entity) -> Vec<[f32; 3]> {
+        let handle = &app.world().get::<Mesh3d>(batch).unwrap().0;
+        let mesh = app.world().resource::<Assets<Mesh>>().get(handle).unwrap();
+        match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions.clone(),
+            _ => panic!("missing positions"),
+        }
+    }
+
+    #[test]
+    fn moved_label_patches_its_range() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .add_systems(Update, text_batch_system);
+        let batch = app
+            .world_mut()
+            .spawn((Text3dBatch, Mesh3d::default(), GlobalTransform::IDENTITY))
+            .id();
+        let mut labels = Vec::new();
+        for i in 0..3 {
+            let mesh = app
+                .world_mut()
+                .resource_mut::<Assets<Mesh>>()
+                .add(label_mesh());
+            let transform = Transform::from_xyz(i as f32 * 10.0, 0.0, 0.0);
+            labels.push(
+                app.world_mut()
+                    .spawn((
+                        Text3dBatched { batch },
+                        Mesh3d(mesh),
+                        transform,
+                        GlobalTransform::from(transform),
+                    ))
+                    .id(),
+            );
+        }
+        app.update();
+        let before = positions(&app, batch);
+        assert_eq!(before.len(), 9);
+        let handle = app.world().get::<Mesh3d>(batch).unwrap().0.clone();
+
+        app.world_mut()
+            .entity_mut(labels[1])
+            .insert(GlobalTransform::from_translation(Vec3::new(0.0, 5.0, 0.0)));
+        app.update();
+        let after = positions(&app, batch);
+        assert_eq!(app.world().get::<Mesh3d>(batch).unwrap().0, handle);
+        assert_eq!(before[..3], after[..3]);
+        assert_eq!(after[3], [0.0, 5.0, 0.0]);
+        assert_eq!(before[6..], after[6..]);
+    }
+}
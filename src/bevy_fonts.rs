@@ -0,0 +1,34 @@
+use bevy::{
+    asset::{AssetEvent, AssetId, Assets},
+    ecs::{
+        event::EventReader,
+        system::{Local, Res},
+    },
+    text::Font,
+};
+use cosmic_text::fontdb::{Source, ID};
+use rustc_hash::FxHashMap;
+
+use crate::{font_asset::sync_font_assets, TextRenderer};
+
+/// Loads [`Font`]s used by `bevy_text` into [`TextRenderer`].
+///
+/// `bevy_text` uses a different version of `cosmic_text` so the [`FontSystem`](cosmic_text::FontSystem)
+/// cannot be shared directly, instead font data is shared by reference, fonts are never copied.
+pub fn share_bevy_fonts_system(
+    mut events: EventReader<AssetEvent<Font>>,
+    fonts: Res<Assets<Font>>,
+    renderer: Res<TextRenderer>,
+    mut queue: Local<Vec<AssetEvent<Font>>>,
+    mut loaded: Local<FxHashMap<AssetId<Font>, Vec<ID>>>,
+    mut initialized: Local<bool>,
+) {
+    // Fonts loaded before the font system is ready.
+    if !*initialized {
+        queue.extend(fonts.ids().map(|id| AssetEvent::Added { id }));
+        *initialized = true;
+    }
+    sync_font_assets(&mut events, &renderer, &mut queue, &mut loaded, |id| {
+        Some(Source::Binary(fonts.get(id)?.data.clone()))
+    });
+}
@@ -0,0 +1,133 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{ParamSet, Query},
+    },
+    math::Quat,
+    render::camera::Camera,
+    transform::{components::Transform, helper::TransformHelper},
+};
+
+use crate::Text3dSet;
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+/// Rotates a [`Text3d`](crate::Text3d) entity to face a camera every frame,
+/// before transform propagation.
+///
+/// The entity's [`Transform::rotation`] is overwritten.
+///
+/// Requires [`Text3dBillboardPlugin`].
+#[derive(Debug, Component, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dBillboard {
+    /// Camera to face, if `None`, faces the first active [`Camera`].
+    pub camera: Option<Entity>,
+    /// How the text is rotated.
+    pub mode: BillboardMode,
+}
+
+impl Text3dBillboard {
+    /// Face the first active camera with the given mode.
+    pub const fn new(mode: BillboardMode) -> Self {
+        Text3dBillboard { camera: None, mode }
+    }
+
+    /// Face a specific camera.
+    pub const fn with_camera(mut self, camera: Entity) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+}
+
+/// Rotation mode of [`Text3dBillboard`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum BillboardMode {
+    /// Copy the camera's rotation, text is always parallel to the screen.
+    #[default]
+    Full,
+    /// Only rotate around the y axis to face the camera's position, text stays upright.
+    YAxis,
+}
+
+/// Rotates [`Text3dBillboard`]s to face their camera.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dBillboardPlugin;
+
+impl Plugin for Text3dBillboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, text_billboard_system.in_set(Text3dSet));
+        #[cfg(feature = "reflect")]
+        app.register_type::<Text3dBillboard>();
+    }
+}
+
+/// Rotates [`Text3dBillboard`] entities to face their camera.
+///
+/// Runs before transform propagation, so global transforms of the camera and the text
+/// are computed from this frame's [`Transform`]s instead of last frame's
+/// [`GlobalTransform`](bevy::transform::components::GlobalTransform).
+pub fn text_billboard_system(
+    cameras: Query<(Entity, &Camera)>,
+    query: Query<(Entity, &Text3dBillboard, Option<&ChildOf>)>,
+    mut transforms: ParamSet<(
+        TransformHelper,
+        Query<&mut Transform, With<Text3dBillboard>>,
+    )>,
+) {
+    let default_camera = cameras
+        .iter()
+        .find(|(_, camera)| camera.is_active)
+        .map(|(entity, _)| entity);
+    let mut rotations = Vec::new();
+    let helper = transforms.p0();
+    for (entity, billboard, parent) in query.iter() {
+        let Some(camera) = billboard
+            .camera
+            .or(default_camera)
+            .filter(|entity| cameras.contains(*entity))
+            .and_then(|entity| helper.compute_global_transform(entity).ok())
+        else {
+            continue;
+        };
+        let rotation = match billboard.mode {
+            BillboardMode::Full => camera.rotation(),
+            BillboardMode::YAxis => {
+                let Ok(global) = helper.compute_global_transform(entity) else {
+                    continue;
+                };
+                let dir = camera.translation() - global.translation();
+                if dir.x == 0.0 && dir.z == 0.0 {
+                    continue;
+                }
+                Quat::from_rotation_y(dir.x.atan2(dir.z))
+            }
+        };
+        // Convert to the parent's space.
+        let parent_rotation = parent
+            .and_then(|parent| helper.compute_global_transform(parent.parent()).ok())
+            .map(|x| x.rotation())
+            .unwrap_or(Quat::IDENTITY);
+        rotations.push((entity, parent_rotation.inverse() * rotation));
+    }
+    let mut query = transforms.p1();
+    for (entity, rotation) in rotations {
+        let Ok(mut transform) = query.get_mut(entity) else {
+            continue;
+        };
+        if !transform.rotation.abs_diff_eq(rotation, f32::EPSILON) {
+            transform.rotation = rotation;
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use bevy::{
+    color::Srgba,
+    ecs::{entity::Entity, world::World},
+};
+
+use crate::{styling::SegmentStyle, ComputedSegment, Style, Text3d, Text3dSegment, Weight};
+
+/// Builds a [`Text3d`] segment by segment, see [`Text3d::builder`].
+///
+/// Style methods apply to the last added segment.
+///
+/// # Example
+///
+/// ```
+/// # use bevy::{color::palettes::css::RED, prelude::*};
+/// # use bevy_rich_text3d::Text3d;
+/// # fn f(fetcher: Entity) {
+/// let text = Text3d::builder()
+///     .text("Deals ")
+///     .text("10")
+///     .style(|s| s.with_fill_color(RED))
+///     .text(" damage, ")
+///     .entity(fetcher)
+///     .underline()
+///     .build();
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Text3dBuilder {
+    segments: Vec<(Text3dSegment, SegmentStyle)>,
+}
+
+impl Text3dBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a segment with its style.
+    pub fn segment(mut self, segment: Text3dSegment, style: SegmentStyle) -> Self {
+        self.segments.push((segment, style));
+        self
+    }
+
+    /// Add a string without parsing.
+    pub fn text(self, text: impl ToString) -> Self {
+        self.segment(
+            Text3dSegment::String(text.to_string()),
+            SegmentStyle::default(),
+        )
+    }
+
+    /// Add a [`Text3dSegment::Interned`] string, see [`Text3dInterner`](crate::Text3dInterner).
+    pub fn interned(self, text: Arc<str>) -> Self {
+        self.segment(Text3dSegment::Interned(text), SegmentStyle::default())
+    }
+
+    /// Add a [`Text3dSegment::Extract`] that displays the entity's [`FetchedTextSegment`](crate::FetchedTextSegment).
+    pub fn entity(self, entity: Entity) -> Self {
+        self.segment(Text3dSegment::Extract(entity), SegmentStyle::default())
+    }
+
+    /// Add a [`Text3dSegment::Computed`] evaluated every frame.
+    pub fn computed(self, compute: impl Fn(&World) -> String + Send + Sync + 'static) -> Self {
+        self.segment(
+            Text3dSegment::Computed(ComputedSegment::new(compute)),
+            SegmentStyle::default(),
+        )
+    }
+
+    /// Add an icon from [`Text3dIcons`](crate::Text3dIcons).
+    pub fn image(self, name: impl ToString) -> Self {
+        self.segment(
+            Text3dSegment::Image(name.to_string()),
+            SegmentStyle::default(),
+        )
+    }
+
+    /// Modify the style of the last segment, does nothing if empty.
+    pub fn style(mut self, f: impl FnOnce(SegmentStyle) -> SegmentStyle) -> Self {
+        if let Some((_, style)) = self.segments.last_mut() {
+            *style = f(std::mem::take(style));
+        }
+        self
+    }
+
+    pub fn color(self, color: impl Into<Srgba>) -> Self {
+        self.style(|s| s.with_fill_color(color))
+    }
+
+    pub fn size(self, size: f32) -> Self {
+        self.style(|s| s.with_size(size))
+    }
+
+    pub fn font(self, font: &str) -> Self {
+        self.style(|s| s.with_font(font))
+    }
+
+    pub fn weight(self, weight: Weight) -> Self {
+        self.style(|s| s.with_weight(weight))
+    }
+
+    pub fn bold(self) -> Self {
+        self.weight(Weight::BOLD)
+    }
+
+    pub fn italic(self) -> Self {
+        self.style(|s| s.with_style(Style::Italic))
+    }
+
+    pub fn underline(self) -> Self {
+        self.style(SegmentStyle::with_underline)
+    }
+
+    pub fn strikethrough(self) -> Self {
+        self.style(SegmentStyle::with_strikethrough)
+    }
+
+    pub fn tag(self, tag: &str) -> Self {
+        self.style(|s| s.with_tag(tag))
+    }
+
+    /// Draw `ruby` above the current segment, i.e. furigana.
+    pub fn ruby(self, ruby: &str) -> Self {
+        self.style(|s| s.with_ruby(ruby))
+    }
+
+    pub fn build(self) -> Text3d {
+        Text3d {
+            segments: self.segments,
+        }
+    }
+}
+
+impl From<Text3dBuilder> for Text3d {
+    fn from(value: Text3dBuilder) -> Self {
+        value.build()
+    }
+}
+
+impl From<Text3d> for Text3dBuilder {
+    fn from(value: Text3d) -> Self {
+        Text3dBuilder {
+            segments: value.segments,
+        }
+    }
+}
+
+impl Extend<(Text3dSegment, SegmentStyle)> for Text3d {
+    fn extend<T: IntoIterator<Item = (Text3dSegment, SegmentStyle)>>(&mut self, iter: T) {
+        self.segments.extend(iter)
+    }
+}
+
+impl Text3d {
+    /// Build a [`Text3d`] segment by segment, see [`Text3dBuilder`].
+    pub fn builder() -> Text3dBuilder {
+        Text3dBuilder::new()
+    }
+
+    /// Add the segments of a [`Text3dBuilder`] at the end.
+    pub fn append(&mut self, builder: Text3dBuilder) {
+        self.segments.extend(builder.segments)
+    }
+}
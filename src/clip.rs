@@ -0,0 +1,426 @@
+use std::{fmt::Debug, sync::Arc};
+
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::Assets,
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Query, Res, ResMut},
+        world::Ref,
+    },
+    math::{IVec2, Rect, Vec2, Vec3, Vec4},
+    render::mesh::{Indices, Mesh, Mesh2d, Mesh3d, VertexAttributeValues},
+};
+
+use crate::{
+    animation::glyph_animation_system,
+    change_detection::TouchMaterialSet,
+    marquee::marquee_system,
+    mesh_util::{rescale_uvs, SharedMeshes, TEXT_TANGENT},
+    Text3dDimensionOut, Text3dLayoutInfo, Text3dPlugin, Text3dSet, ATTRIBUTE_GLYPH_META,
+};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+type SdfFn = dyn Fn(Vec2) -> f32 + Send + Sync;
+
+/// Shape of a [`Text3dClip`] in the text's local space, the same space as its mesh.
+#[derive(Clone)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum ClipShape {
+    /// Keeps text inside a circle.
+    Circle { center: Vec2, radius: f32 },
+    /// Keeps text inside a rectangle with rounded corners.
+    RoundedRect { rect: Rect, radius: f32 },
+    /// A signed distance function, negative inside the shape.
+    ///
+    /// Quads are split where the function crosses zero,
+    /// the function should be a distance or glyphs may be clipped inaccurately.
+    Sdf(#[cfg_attr(feature = "reflect", reflect(ignore))] ClipSdf),
+}
+
+/// A user provided signed distance function of a [`ClipShape::Sdf`].
+#[derive(Clone)]
+pub struct ClipSdf(Arc<SdfFn>);
+
+impl Default for ClipSdf {
+    fn default() -> Self {
+        ClipSdf(Arc::new(|_| -1.0))
+    }
+}
+
+impl Debug for ClipSdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ClipSdf")
+    }
+}
+
+impl Debug for ClipShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Circle { center, radius } => f
+                .debug_struct("Circle")
+                .field("center", center)
+                .field("radius", radius)
+                .finish(),
+            Self::RoundedRect { rect, radius } => f
+                .debug_struct("RoundedRect")
+                .field("rect", rect)
+                .field("radius", radius)
+                .finish(),
+            Self::Sdf(sdf) => f.debug_tuple("Sdf").field(sdf).finish(),
+        }
+    }
+}
+
+impl ClipShape {
+    pub const fn circle(center: Vec2, radius: f32) -> Self {
+        ClipShape::Circle { center, radius }
+    }
+
+    pub const fn rounded_rect(rect: Rect, radius: f32) -> Self {
+        ClipShape::RoundedRect { rect, radius }
+    }
+
+    pub fn sdf(f: impl Fn(Vec2) -> f32 + Send + Sync + 'static) -> Self {
+        ClipShape::Sdf(ClipSdf(Arc::new(f)))
+    }
+
+    /// Signed distance to the shape's border, negative inside the shape.
+    pub fn distance(&self, point: Vec2) -> f32 {
+        match self {
+            ClipShape::Circle { center, radius } => (point - *center).length() - radius,
+            ClipShape::RoundedRect { rect, radius } => {
+                let radius = radius.clamp(0.0, rect.half_size().min_element().max(0.0));
+                let q = (point - rect.center()).abs() - rect.half_size() + radius;
+                q.max(Vec2::ZERO).length() + q.max_element().min(0.0) - radius
+            }
+            ClipShape::Sdf(sdf) => (sdf.0)(point),
+        }
+    }
+}
+
+/// Confines a [`Text3d`](crate::Text3d) to a [`ClipShape`], like a circular gauge or an irregular panel,
+/// without a stencil or a custom material.
+///
+/// The shape is evaluated at each vertex, triangles crossing the border are subdivided
+/// and cut along it. The text's mesh is modified in place, with `share_identical_meshes`
+/// the mesh is copied before being clipped.
+///
+/// Vertex ranges in [`Text3dLayoutInfo`] do not match the clipped mesh.
+/// Cannot be combined with [`Text3dGlyphAnimation`](crate::Text3dGlyphAnimation)
+/// or [`Text3dMarquee`](crate::Text3dMarquee).
+///
+/// Requires [`Text3dClipPlugin`].
+#[derive(Debug, Clone, Component)]
+#[require(Text3dLayoutInfo)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct Text3dClip {
+    pub shape: ClipShape,
+    /// Number of times a triangle crossing the border is split in four, defaults to `4`.
+    ///
+    /// Higher values follow curved borders more closely.
+    pub subdivisions: u32,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    base: ClipBase,
+}
+
+/// The mesh before clipping.
+#[derive(Debug, Clone, Default)]
+struct ClipBase {
+    mesh: Option<Mesh>,
+    /// Size of the atlas uvs of `mesh` are normalized to.
+    atlas_dimension: IVec2,
+}
+
+impl Text3dClip {
+    pub fn new(shape: ClipShape) -> Self {
+        Text3dClip {
+            shape,
+            subdivisions: 4,
+            base: ClipBase::default(),
+        }
+    }
+
+    pub fn with_subdivisions(mut self, subdivisions: u32) -> Self {
+        self.subdivisions = subdivisions;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClipVertex {
+    position: Vec3,
+    normal: Vec3,
+    uv0: Vec2,
+    uv1: Vec2,
+    glyph_meta: Vec4,
+    color: Vec4,
+    distance: f32,
+}
+
+impl ClipVertex {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        ClipVertex {
+            position: self.position.lerp(other.position, t),
+            normal: self.normal.lerp(other.normal, t),
+            uv0: self.uv0.lerp(other.uv0, t),
+            uv1: self.uv1.lerp(other.uv1, t),
+            glyph_meta: self.glyph_meta.lerp(other.glyph_meta, t),
+            color: self.color.lerp(other.color, t),
+            distance: self.distance + (other.distance - self.distance) * t,
+        }
+    }
+}
+
+/// Vertex data of a clipped mesh.
+#[derive(Default)]
+pub(crate) struct ClipBuffer {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uv0: Vec<[f32; 2]>,
+    uv1: Vec<[f32; 2]>,
+    /// `None` if the base mesh has no [`ATTRIBUTE_GLYPH_META`].
+    glyph_meta: Option<Vec<[f32; 4]>>,
+    /// If true, the base mesh has [`Mesh::ATTRIBUTE_TANGENT`].
+    tangents: bool,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl ClipBuffer {
+    fn push(&mut self, vertex: &ClipVertex) {
+        self.indices.push(self.positions.len() as u32);
+        self.positions.push(vertex.position.to_array());
+        self.normals.push(vertex.normal.to_array());
+        self.uv0.push(vertex.uv0.to_array());
+        self.uv1.push(vertex.uv1.to_array());
+        if let Some(glyph_meta) = &mut self.glyph_meta {
+            glyph_meta.push(vertex.glyph_meta.to_array());
+        }
+        self.colors.push(vertex.color.to_array());
+    }
+
+    fn write(&mut self, mesh: &mut Mesh) {
+        if self.tangents {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_TANGENT,
+                vec![TEXT_TANGENT; self.positions.len()],
+            );
+        }
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            std::mem::take(&mut self.positions),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, std::mem::take(&mut self.normals));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, std::mem::take(&mut self.uv0));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, std::mem::take(&mut self.uv1));
+        if let Some(glyph_meta) = self.glyph_meta.take() {
+            mesh.insert_attribute(ATTRIBUTE_GLYPH_META, glyph_meta);
+        }
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, std::mem::take(&mut self.colors));
+        let indices = std::mem::take(&mut self.indices);
+        if indices.len() <= u16::MAX as usize {
+            mesh.insert_indices(Indices::U16(
+                indices.into_iter().map(|i| i as u16).collect(),
+            ));
+        } else {
+            mesh.insert_indices(Indices::U32(indices));
+        }
+    }
+
+    /// Emit the part of a triangle inside the shape.
+    fn clip_triangle(&mut self, shape: &ClipShape, [a, b, c]: [ClipVertex; 3], depth: u32) {
+        let size = [(a, b), (b, c), (c, a)]
+            .iter()
+            .map(|(p, q)| p.position.truncate().distance(q.position.truncate()))
+            .fold(0.0, f32::max);
+        let min = a.distance.min(b.distance).min(c.distance);
+        let max = a.distance.max(b.distance).max(c.distance);
+        // Every point of the triangle is within `size` of its corners.
+        if min >= size {
+            return;
+        }
+        if max <= -size {
+            [a, b, c].iter().for_each(|v| self.push(v));
+            return;
+        }
+        if depth > 0 && size > 0.0 {
+            let mid = |p: &ClipVertex, q: &ClipVertex| {
+                let mut v = p.lerp(q, 0.5);
+                v.distance = shape.distance(v.position.truncate());
+                v
+            };
+            let (ab, bc, ca) = (mid(&a, &b), mid(&b, &c), mid(&c, &a));
+            for triangle in [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]] {
+                self.clip_triangle(shape, triangle, depth - 1);
+            }
+            return;
+        }
+        // Cut along the linear interpolation of the distance.
+        let mut polygon = [a; 4];
+        let mut len = 0;
+        for (p, q) in [(a, b), (b, c), (c, a)] {
+            if p.distance <= 0.0 {
+                polygon[len] = p;
+                len += 1;
+            }
+            if (p.distance <= 0.0) != (q.distance <= 0.0) {
+                polygon[len] = p.lerp(&q, p.distance / (p.distance - q.distance));
+                len += 1;
+            }
+        }
+        for i in 2..len {
+            self.push(&polygon[0]);
+            self.push(&polygon[i - 1]);
+            self.push(&polygon[i]);
+        }
+    }
+}
+
+fn clip_mesh(base: &Mesh, clip: &Text3dClip, buffer: &mut ClipBuffer) -> bool {
+    let (
+        Some(VertexAttributeValues::Float32x3(positions)),
+        Some(VertexAttributeValues::Float32x3(normals)),
+        Some(VertexAttributeValues::Float32x2(uv0)),
+        Some(VertexAttributeValues::Float32x2(uv1)),
+        Some(VertexAttributeValues::Float32x4(colors)),
+        Some(indices),
+    ) = (
+        base.attribute(Mesh::ATTRIBUTE_POSITION),
+        base.attribute(Mesh::ATTRIBUTE_NORMAL),
+        base.attribute(Mesh::ATTRIBUTE_UV_0),
+        base.attribute(Mesh::ATTRIBUTE_UV_1),
+        base.attribute(Mesh::ATTRIBUTE_COLOR),
+        base.indices(),
+    )
+    else {
+        return false;
+    };
+    let glyph_meta = match base.attribute(ATTRIBUTE_GLYPH_META) {
+        Some(VertexAttributeValues::Float32x4(glyph_meta)) => Some(glyph_meta),
+        _ => None,
+    };
+    buffer.glyph_meta = glyph_meta.map(|_| Vec::new());
+    buffer.tangents = base.contains_attribute(Mesh::ATTRIBUTE_TANGENT);
+    let vertex = |i: usize| {
+        let position = Vec3::from_array(positions[i]);
+        ClipVertex {
+            position,
+            normal: Vec3::from_array(normals[i]),
+            uv0: Vec2::from_array(uv0[i]),
+            uv1: Vec2::from_array(uv1[i]),
+            glyph_meta: glyph_meta
+                .and_then(|x| x.get(i))
+                .map(|x| Vec4::from_array(*x))
+                .unwrap_or_default(),
+            color: Vec4::from_array(colors[i]),
+            distance: clip.shape.distance(position.truncate()),
+        }
+    };
+    let indices: Vec<usize> = indices.iter().collect();
+    for triangle in indices.chunks_exact(3) {
+        if triangle.iter().any(|i| *i >= positions.len()) {
+            continue;
+        }
+        buffer.clip_triangle(
+            &clip.shape,
+            [
+                vertex(triangle[0]),
+                vertex(triangle[1]),
+                vertex(triangle[2]),
+            ],
+            clip.subdivisions,
+        );
+    }
+    true
+}
+
+/// Clips the meshes of [`Text3dClip`]s, after [`Text3dMarqueePlugin`](crate::Text3dMarqueePlugin) if added.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dClipPlugin;
+
+impl Plugin for Text3dClipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            clip_system
+                .in_set(Text3dSet)
+                .after(glyph_animation_system)
+                .after(marquee_system)
+                .before(TouchMaterialSet),
+        );
+        #[cfg(feature = "reflect")]
+        app.register_type::<Text3dClip>();
+    }
+}
+
+/// Clips the meshes of [`Text3dClip`]s after text meshes are rendered.
+pub fn clip_system(
+    settings: Res<Text3dPlugin>,
+    shared_meshes: Res<SharedMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(
+        &mut Text3dClip,
+        Ref<Text3dLayoutInfo>,
+        &Text3dDimensionOut,
+        Option<&mut Mesh2d>,
+        Option<&mut Mesh3d>,
+    )>,
+    mut buffer: Local<ClipBuffer>,
+) {
+    for (mut clip, layout, output, mut mesh2d, mut mesh3d) in query.iter_mut() {
+        let redrawn = layout.is_changed();
+        if !redrawn && !clip.is_changed() {
+            continue;
+        }
+        let Some(mut handle) = mesh2d
+            .as_ref()
+            .map(|x| x.0.clone())
+            .or_else(|| mesh3d.as_ref().map(|x| x.0.clone()))
+        else {
+            continue;
+        };
+        let clip = clip.bypass_change_detection();
+        // The mesh was redrawn, it is not clipped.
+        let base = &mut clip.base;
+        if redrawn || base.mesh.is_none() {
+            base.mesh = meshes.get(handle.id()).cloned();
+            base.atlas_dimension = output.atlas_dimension;
+        } else if base.atlas_dimension != output.atlas_dimension {
+            // The atlas grew and uvs of the mesh were rescaled in place.
+            if let Some(VertexAttributeValues::Float32x2(uvs)) = base
+                .mesh
+                .as_mut()
+                .and_then(|mesh| mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0))
+                .filter(|_| !settings.pixel_uvs)
+            {
+                rescale_uvs(uvs, base.atlas_dimension, output.atlas_dimension);
+            }
+            base.atlas_dimension = output.atlas_dimension;
+        }
+        let Some(base) = &clip.base.mesh else {
+            continue;
+        };
+        // Copy on write, since other entities may be using the same mesh.
+        if shared_meshes.is_shared(handle.id()) {
+            handle = meshes.add(base.clone());
+            if let Some(mesh2d) = &mut mesh2d {
+                mesh2d.0 = handle.clone();
+            }
+            if let Some(mesh3d) = &mut mesh3d {
+                mesh3d.0 = handle.clone();
+            }
+        }
+        let Some(mesh) = meshes.get_mut(handle.id()) else {
+            continue;
+        };
+        if clip_mesh(base, clip, &mut buffer) {
+            buffer.write(mesh);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::ops::Range;
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::{Event, EventReader},
+        system::Query,
+    },
+    log::warn,
+};
+
+use crate::{FetchedTextSegment, Text3d, Text3dEditor};
+
+/// Part of a text copied by [`Text3dCopyToClipboard`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CopyRange {
+    /// The entire text with dynamic segments resolved, see [`Text3d::resolve_plain`].
+    #[default]
+    All,
+    /// A byte range of the resolved text.
+    Bytes(Range<usize>),
+    /// The selection of a [`Text3dEditor`], copies nothing if empty.
+    Selection,
+}
+
+/// Copies a [`Text3d`]'s plain text to the system clipboard when sent.
+///
+/// Requires the `clipboard` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct Text3dCopyToClipboard {
+    pub entity: Entity,
+    pub range: CopyRange,
+}
+
+impl Text3dCopyToClipboard {
+    pub fn all(entity: Entity) -> Self {
+        Self {
+            entity,
+            range: CopyRange::All,
+        }
+    }
+
+    pub fn selection(entity: Entity) -> Self {
+        Self {
+            entity,
+            range: CopyRange::Selection,
+        }
+    }
+}
+
+/// Handles [`Text3dCopyToClipboard`].
+pub fn clipboard_system(
+    mut events: EventReader<Text3dCopyToClipboard>,
+    texts: Query<(&Text3d, Option<&Text3dEditor>)>,
+    segments: Query<&FetchedTextSegment>,
+) {
+    for event in events.read() {
+        let Ok((text, editor)) = texts.get(event.entity) else {
+            continue;
+        };
+        let copied = match &event.range {
+            CopyRange::All => text.resolve_plain(&segments),
+            CopyRange::Bytes(range) => {
+                let Some(s) = text
+                    .resolve_plain(&segments)
+                    .get(range.clone())
+                    .map(str::to_owned)
+                else {
+                    warn!("Invalid copy range {range:?} for {}.", event.entity);
+                    continue;
+                };
+                s
+            }
+            CopyRange::Selection => match editor.and_then(Text3dEditor::selected_text) {
+                Some(s) => s.to_owned(),
+                None => continue,
+            },
+        };
+        // The clipboard is opened per copy since it is not `Send` on all platforms.
+        if let Err(e) = arboard::Clipboard::new().and_then(|mut x| x.set_text(copied)) {
+            warn!("Failed to copy text to clipboard: {e}.");
+        }
+    }
+}
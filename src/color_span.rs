@@ -0,0 +1,130 @@
+use std::ops::Range;
+
+use bevy::{
+    asset::Assets,
+    color::{ColorToComponents, LinearRgba, Srgba},
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
+        system::{Query, Res, ResMut},
+        world::Ref,
+    },
+    math::Vec4,
+    render::mesh::{Mesh, Mesh2d, Mesh3d, VertexAttributeValues},
+};
+
+use crate::{mesh_util::SharedMeshes, Text3dLayoutInfo};
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+/// Colors byte ranges of a [`Text3d`](crate::Text3d) by modifying the vertex colors of its mesh,
+/// without layout or rasterization, for fast changing overlays like syntax or search highlighting.
+///
+/// Byte offsets match [`Text3dLayoutInfo::text`], each glyph whose cluster starts in a span
+/// has its colors multiplied by the span's color, including strokes and shadows.
+/// Use a white fill color for exact colors. If spans overlap, the last span applies.
+///
+/// The text's mesh is modified in place, with `share_identical_meshes`
+/// the mesh is copied before being colored.
+/// Cannot be combined with [`Text3dGlyphAnimation`](crate::Text3dGlyphAnimation).
+#[derive(Debug, Clone, Component, Default)]
+#[require(Text3dLayoutInfo)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dColorSpans {
+    pub spans: Vec<(Range<usize>, Srgba)>,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    base: Vec<[f32; 4]>,
+}
+
+impl Text3dColorSpans {
+    pub fn new(spans: impl IntoIterator<Item = (Range<usize>, Srgba)>) -> Self {
+        Text3dColorSpans {
+            spans: spans.into_iter().collect(),
+            base: Vec::new(),
+        }
+    }
+
+    /// Add a span, drawn over existing spans.
+    pub fn push(&mut self, range: Range<usize>, color: Srgba) {
+        self.spans.push((range, color));
+    }
+
+    pub fn clear(&mut self) {
+        self.spans.clear();
+    }
+}
+
+fn mesh_colors(mesh: &mut Mesh) -> Option<&mut Vec<[f32; 4]>> {
+    match mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)? {
+        VertexAttributeValues::Float32x4(colors) => Some(colors),
+        _ => None,
+    }
+}
+
+/// Applies [`Text3dColorSpans`] to text meshes after they are rendered.
+pub fn color_span_system(
+    shared_meshes: Res<SharedMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(
+        &mut Text3dColorSpans,
+        Ref<Text3dLayoutInfo>,
+        Option<&mut Mesh2d>,
+        Option<&mut Mesh3d>,
+    )>,
+) {
+    for (mut spans, layout, mut mesh2d, mut mesh3d) in query.iter_mut() {
+        let redrawn = layout.is_changed();
+        if !redrawn && !spans.is_changed() {
+            continue;
+        }
+        let Some(mut handle) = mesh2d
+            .as_ref()
+            .map(|x| x.0.clone())
+            .or_else(|| mesh3d.as_ref().map(|x| x.0.clone()))
+        else {
+            continue;
+        };
+        // Copy on write, since other entities may be using the same mesh.
+        if shared_meshes.is_shared(handle.id()) {
+            let Some(copy) = meshes.get(handle.id()).cloned() else {
+                continue;
+            };
+            handle = meshes.add(copy);
+            if let Some(mesh2d) = &mut mesh2d {
+                mesh2d.0 = handle.clone();
+            }
+            if let Some(mesh3d) = &mut mesh3d {
+                mesh3d.0 = handle.clone();
+            }
+        }
+        let Some(colors) = meshes.get_mut(handle.id()).and_then(mesh_colors) else {
+            continue;
+        };
+        let spans = spans.bypass_change_detection();
+        // The mesh was redrawn, its vertices are not colored.
+        if redrawn || spans.base.len() != colors.len() {
+            spans.base.clone_from(colors);
+        } else {
+            colors.clone_from(&spans.base);
+        }
+        for glyph in layout.glyphs.iter().filter(|glyph| !glyph.dynamic) {
+            let Some((_, color)) = spans
+                .spans
+                .iter()
+                .rev()
+                .find(|(range, _)| range.contains(&glyph.cluster.start))
+            else {
+                continue;
+            };
+            let tint = LinearRgba::from(*color).to_vec4();
+            for vertex in colors.get_mut(glyph.vertices.clone()).into_iter().flatten() {
+                *vertex = (Vec4::from_array(*vertex) * tint).to_array();
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{Changed, Without},
+        system::Query,
+    },
+    input::{
+        keyboard::{Key, KeyCode, KeyboardInput},
+        ButtonState,
+    },
+};
+
+use crate::{Text3d, Text3dEditor, Text3dSegment};
+
+type KeyHook = dyn Fn(&KeyboardInput, &mut Text3dEditor) -> bool + Send + Sync;
+
+/// An in-game console made of a scrollable log [`Text3d`] on this entity
+/// and an input line [`Text3dEditor`] on another entity.
+///
+/// While `focused`, keyboard input is typed into the input line,
+/// `Enter` submits it as a [`Text3dConsoleSubmit`] event and adds it to the history,
+/// `ArrowUp` and `ArrowDown` browse the history and `PageUp` and `PageDown` scroll the log.
+///
+/// The log is written into [`Text3d`] as a single [`Text3dSegment::String`]
+/// that inherits the style of the original first segment.
+///
+/// Requires the `console` feature.
+#[derive(Component, Clone)]
+#[require(Text3d)]
+pub struct Text3dConsole {
+    /// Entity with the input line's [`Text3dEditor`].
+    pub input: Entity,
+    /// If true, receives keyboard input.
+    pub focused: bool,
+    /// Oldest lines are removed past this number of lines, by default `1000`.
+    pub max_lines: usize,
+    /// Number of lines displayed, by default `20`.
+    pub visible_lines: usize,
+    /// Number of lines scrolled up from the bottom of the log.
+    pub scroll: usize,
+    /// Added in front of submitted lines when echoed to the log.
+    pub prompt: String,
+    /// If true, submitted lines are added to the log.
+    pub echo: bool,
+    lines: VecDeque<String>,
+    history: Vec<String>,
+    /// Position in `history` while browsing it.
+    history_index: Option<usize>,
+    key_hook: Option<Arc<KeyHook>>,
+}
+
+/// Sent when a line is submitted in a [`Text3dConsole`].
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct Text3dConsoleSubmit {
+    /// Entity with the [`Text3dConsole`].
+    pub console: Entity,
+    pub line: String,
+}
+
+impl Text3dConsole {
+    pub fn new(input: Entity) -> Self {
+        Text3dConsole {
+            input,
+            focused: true,
+            max_lines: 1000,
+            visible_lines: 20,
+            scroll: 0,
+            prompt: "> ".into(),
+            echo: true,
+            lines: VecDeque::new(),
+            history: Vec::new(),
+            history_index: None,
+            key_hook: None,
+        }
+    }
+
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    pub fn with_visible_lines(mut self, visible_lines: usize) -> Self {
+        self.visible_lines = visible_lines;
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Handle keys before the console, the key is not handled by the console if the hook returns true.
+    ///
+    /// Useful for autocompletion or custom shortcuts.
+    pub fn with_key_hook(
+        mut self,
+        hook: impl Fn(&KeyboardInput, &mut Text3dEditor) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.key_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Add lines to the log, text with `\n` is split into multiple lines.
+    pub fn log(&mut self, text: impl AsRef<str>) {
+        for line in text.as_ref().split('\n') {
+            self.lines.push_back(line.into());
+            // Stay at the same lines if scrolled up.
+            if self.scroll > 0 {
+                self.scroll += 1;
+            }
+        }
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+        }
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+
+    /// Remove all lines from the log.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.scroll = 0;
+    }
+
+    /// Lines of the log from oldest to newest.
+    pub fn lines(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+
+    /// Submitted lines from oldest to newest.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Scroll up by `lines`, or down if negative.
+    pub fn scroll_by(&mut self, lines: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(lines)
+            .min(self.max_scroll());
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.visible_lines)
+    }
+
+    /// The displayed lines joined by `\n`.
+    fn visible_text(&self) -> String {
+        let end = self.lines.len() - self.scroll.min(self.lines.len());
+        let start = end.saturating_sub(self.visible_lines);
+        let mut result = String::new();
+        for (i, line) in self.lines.range(start..end).enumerate() {
+            if i > 0 {
+                result.push('\n');
+            }
+            result.push_str(line);
+        }
+        result
+    }
+
+    /// Replace the input line with an entry of the history, or clear it past the newest entry.
+    fn browse_history(&mut self, editor: &mut Text3dEditor, up: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match (self.history_index, up) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => return,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+        self.history_index = index;
+        editor.set_text(index.map(|i| self.history[i].as_str()).unwrap_or_default());
+    }
+}
+
+/// Handles keyboard input of focused [`Text3dConsole`]s.
+pub fn console_input_system(
+    mut keys: EventReader<KeyboardInput>,
+    mut submit: EventWriter<Text3dConsoleSubmit>,
+    mut consoles: Query<(Entity, &mut Text3dConsole)>,
+    mut editors: Query<&mut Text3dEditor, Without<Text3dConsole>>,
+) {
+    let keys: Vec<_> = keys
+        .read()
+        .filter(|key| key.state == ButtonState::Pressed)
+        .collect();
+    if keys.is_empty() {
+        return;
+    }
+    for (entity, mut console) in consoles.iter_mut() {
+        if !console.focused {
+            continue;
+        }
+        let Ok(mut editor) = editors.get_mut(console.input) else {
+            continue;
+        };
+        for key in keys.iter().copied() {
+            if console
+                .key_hook
+                .as_ref()
+                .is_some_and(|hook| hook(key, &mut editor))
+            {
+                continue;
+            }
+            let page = console.visible_lines.max(1) as isize;
+            match key.key_code {
+                KeyCode::Enter | KeyCode::NumpadEnter => {
+                    let line = editor.text().to_owned();
+                    editor.set_text("");
+                    console.history_index = None;
+                    if console.history.last() != Some(&line) && !line.is_empty() {
+                        console.history.push(line.clone());
+                    }
+                    if console.echo {
+                        let echo = format!("{}{}", console.prompt, line);
+                        console.log(echo);
+                    }
+                    console.scroll = 0;
+                    submit.write(Text3dConsoleSubmit {
+                        console: entity,
+                        line,
+                    });
+                }
+                KeyCode::Backspace => editor.backspace(),
+                KeyCode::Delete => editor.delete(),
+                KeyCode::ArrowLeft => editor.move_left(false),
+                KeyCode::ArrowRight => editor.move_right(false),
+                KeyCode::Home => editor.move_home(false),
+                KeyCode::End => editor.move_end(false),
+                KeyCode::ArrowUp => console.browse_history(&mut editor, true),
+                KeyCode::ArrowDown => console.browse_history(&mut editor, false),
+                KeyCode::PageUp => console.scroll_by(page),
+                KeyCode::PageDown => console.scroll_by(-page),
+                _ => {
+                    if let Key::Character(text) = &key.logical_key {
+                        if !text.chars().any(char::is_control) {
+                            editor.insert(text);
+                        }
+                    } else if key.logical_key == Key::Space {
+                        editor.insert(" ");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Writes the visible lines of [`Text3dConsole`] into [`Text3d`].
+pub fn console_log_system(mut query: Query<(&Text3dConsole, &mut Text3d), Changed<Text3dConsole>>) {
+    for (console, mut text) in query.iter_mut() {
+        let visible = console.visible_text();
+        if text.get_single() == Some(visible.as_str()) {
+            continue;
+        }
+        let style = text
+            .segments
+            .first()
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default();
+        text.segments = vec![(Text3dSegment::String(visible), style)];
+    }
+}
@@ -0,0 +1,234 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::Handle,
+    color::{palettes::css, Color},
+    ecs::{
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res},
+    },
+    gizmos::gizmos::Gizmos,
+    math::{Rect, Vec2, Vec3},
+    render::primitives::Aabb,
+    transform::{components::GlobalTransform, TransformSystem},
+};
+
+use crate::{Text3d, Text3dLayoutInfo, TextAtlas};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectDefault, ReflectResource};
+
+/// Settings of [`Text3dDebugPlugin`], can be changed at runtime.
+#[derive(Debug, Clone, Resource)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource, Default))]
+pub struct Text3dDebugSettings {
+    /// If false, nothing is drawn, by default `true`.
+    pub enabled: bool,
+    /// Draw the `Aabb` of each text.
+    pub aabbs: bool,
+    /// Draw the rectangle of each line, requires [`Text3dLayoutInfo`].
+    pub lines: bool,
+    /// Draw the fill quad of each glyph, requires [`Text3dLayoutInfo`].
+    pub glyphs: bool,
+    /// Draw a cross at the local origin of each text, which is placed at its anchor.
+    pub anchors: bool,
+    /// If set, shows this atlas's texture in the bottom right corner of the screen
+    /// with its unused rows darkened.
+    ///
+    /// Requires the `ui` feature, texture array atlases are not shown.
+    pub atlas: Option<Handle<TextAtlas>>,
+}
+
+impl Default for Text3dDebugSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            aabbs: true,
+            lines: true,
+            glyphs: false,
+            anchors: true,
+            atlas: None,
+        }
+    }
+}
+
+/// Draws gizmos for the layout of [`Text3d`]s and optionally a view of a [`TextAtlas`],
+/// configured by [`Text3dDebugSettings`].
+///
+/// See [`Text3dDebugOverlay`](crate::Text3dDebugOverlay) for statistics.
+///
+/// Requires the `debug` feature.
+#[derive(Debug, Clone, Default)]
+pub struct Text3dDebugPlugin {
+    /// Initial settings.
+    pub settings: Text3dDebugSettings,
+}
+
+impl Plugin for Text3dDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone());
+        app.add_systems(
+            PostUpdate,
+            debug_gizmo_system.after(TransformSystem::TransformPropagate),
+        );
+        #[cfg(feature = "ui")]
+        app.add_systems(PostUpdate, atlas_view::debug_atlas_system);
+        #[cfg(feature = "reflect")]
+        app.register_type::<Text3dDebugSettings>();
+    }
+}
+
+fn draw_rect(gizmos: &mut Gizmos, transform: &GlobalTransform, rect: Rect, color: Color) {
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+        rect.min,
+    ];
+    gizmos.linestrip(
+        corners.map(|x| transform.transform_point(x.extend(0.0))),
+        color,
+    );
+}
+
+/// Draws gizmos configured by [`Text3dDebugSettings`].
+pub fn debug_gizmo_system(
+    settings: Res<Text3dDebugSettings>,
+    mut gizmos: Gizmos,
+    query: Query<(&GlobalTransform, Option<&Aabb>, Option<&Text3dLayoutInfo>), With<Text3d>>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for (transform, aabb, layout) in &query {
+        if let Some(aabb) = aabb.filter(|_| settings.aabbs) {
+            let center = Vec3::from(aabb.center).truncate();
+            let half = Vec3::from(aabb.half_extents).truncate();
+            draw_rect(
+                &mut gizmos,
+                transform,
+                Rect::from_center_half_size(center, half),
+                css::LIME.into(),
+            );
+        }
+        if let Some(layout) = layout {
+            if settings.lines {
+                for line in &layout.lines {
+                    draw_rect(&mut gizmos, transform, line.rect, css::DEEP_SKY_BLUE.into());
+                }
+            }
+            if settings.glyphs {
+                for quad in layout.glyphs.iter().filter_map(|x| x.quad) {
+                    draw_rect(&mut gizmos, transform, quad, css::ORANGE.into());
+                }
+            }
+        }
+        if settings.anchors {
+            let size = layout
+                .and_then(|x| x.lines.first())
+                .map(|x| x.rect.height() * 0.25)
+                .unwrap_or(1.0);
+            for (a, b) in [(Vec3::X, -Vec3::X), (Vec3::Y, -Vec3::Y)] {
+                gizmos.line(
+                    transform.transform_point(a * size),
+                    transform.transform_point(b * size),
+                    css::RED,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ui")]
+mod atlas_view {
+    use bevy::{
+        asset::Assets,
+        color::{Color, LinearRgba},
+        ecs::{
+            entity::Entity,
+            system::{Commands, Local, Query, Res},
+        },
+        image::Image,
+        ui::{widget::ImageNode, BackgroundColor, Node, PositionType, Val},
+    };
+
+    use super::Text3dDebugSettings;
+    use crate::TextAtlas;
+
+    /// Shows [`Text3dDebugSettings::atlas`] in a corner of the screen.
+    pub fn debug_atlas_system(
+        mut commands: Commands,
+        settings: Res<Text3dDebugSettings>,
+        atlases: Res<Assets<TextAtlas>>,
+        images: Res<Assets<Image>>,
+        mut view: Local<Option<(Entity, Entity)>>,
+        mut nodes: Query<&mut Node>,
+        mut image_nodes: Query<&mut ImageNode>,
+    ) {
+        let atlas = settings
+            .atlas
+            .as_ref()
+            .filter(|_| settings.enabled)
+            .and_then(|x| atlases.get(x.id()))
+            .filter(|x| !x.array);
+        let Some((atlas, image)) =
+            atlas.and_then(|atlas| Some((atlas, images.get(atlas.image.id())?)))
+        else {
+            if let Some((root, _)) = view.take() {
+                commands.entity(root).try_despawn();
+            }
+            return;
+        };
+        let (width, height) = (image.width() as f32, image.height() as f32);
+        let used = ((atlas.pointer.y as usize + atlas.descent) as f32 / height.max(1.0)).min(1.0);
+        let (root, unused) = *view.get_or_insert_with(|| {
+            let unused = commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        right: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                        ..Default::default()
+                    },
+                    BackgroundColor(Color::from(LinearRgba::new(0.0, 0.0, 0.0, 0.6))),
+                ))
+                .id();
+            let root = commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(0.0),
+                        bottom: Val::Px(0.0),
+                        width: Val::Px(256.0),
+                        ..Default::default()
+                    },
+                    ImageNode::new(atlas.image.clone()),
+                    BackgroundColor(Color::BLACK),
+                ))
+                .add_child(unused)
+                .id();
+            (root, unused)
+        });
+        if let Ok(mut node) = nodes.get_mut(root) {
+            let aspect_ratio = Some(width / height.max(1.0));
+            if node.aspect_ratio != aspect_ratio {
+                node.aspect_ratio = aspect_ratio;
+            }
+        }
+        if let Ok(mut node) = nodes.get_mut(unused) {
+            let top = Val::Percent(used * 100.0);
+            if node.top != top {
+                node.top = top;
+            }
+        }
+        if let Ok(mut image_node) = image_nodes.get_mut(root) {
+            if image_node.image != atlas.image {
+                image_node.image = atlas.image.clone();
+            }
+        }
+    }
+}
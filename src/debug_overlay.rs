@@ -0,0 +1,162 @@
+use std::{fmt::Write, sync::TryLockError};
+
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::Assets,
+    ecs::{
+        bundle::Bundle,
+        component::Component,
+        event::EventReader,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res},
+    },
+    image::Image,
+    time::Time,
+};
+
+use crate::{
+    render::text_render, Text3d, Text3dSet, Text3dStyling, TextAtlas, TextRemeshed, TextRenderer,
+};
+
+/// Displays live statistics of text rendering as its own [`Text3d`], for tuning text heavy scenes.
+///
+/// Shows the number of texts remeshed and glyphs rasterized per second,
+/// atlas occupancy and how often the font system was locked by another thread.
+///
+/// Spawn with [`Text3dDebugOverlay::bundle`] and a material.
+///
+/// Requires [`Text3dDebugOverlayPlugin`].
+#[derive(Debug, Clone, Component)]
+#[require(Text3d)]
+pub struct Text3dDebugOverlay {
+    /// Seconds between updates of the displayed text, by default `0.5`.
+    pub refresh: f32,
+    elapsed: f32,
+    frames: usize,
+    remeshed: usize,
+    glyphs: usize,
+    last_glyphs: Option<usize>,
+    contended: usize,
+}
+
+impl Default for Text3dDebugOverlay {
+    fn default() -> Self {
+        Text3dDebugOverlay {
+            refresh: 0.5,
+            elapsed: 0.0,
+            frames: 0,
+            remeshed: 0,
+            glyphs: 0,
+            last_glyphs: None,
+            contended: 0,
+        }
+    }
+}
+
+impl Text3dDebugOverlay {
+    /// Components of an overlay, add a material and a transform to display it.
+    pub fn bundle(styling: Text3dStyling) -> impl Bundle {
+        (Text3dDebugOverlay::default(), Text3d::new(""), styling)
+    }
+}
+
+/// Returns the number of cached glyphs, and the used and total rows of all atlases.
+pub(crate) fn atlas_usage(
+    atlases: &Assets<TextAtlas>,
+    images: &Assets<Image>,
+) -> (usize, usize, usize) {
+    let mut glyph_count = 0;
+    let mut used = 0;
+    let mut capacity = 0;
+    for (_, atlas) in atlases.iter() {
+        glyph_count += atlas.glyphs.len();
+        if let Some(image) = images.get(atlas.image.id()) {
+            let height =
+                image.height() as usize * image.texture_descriptor.array_layer_count() as usize;
+            used += (atlas.pointer.y as usize + atlas.descent).min(height);
+            capacity += height;
+        }
+    }
+    (glyph_count, used, capacity)
+}
+
+/// Updates [`Text3dDebugOverlay`]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dDebugOverlayPlugin;
+
+impl Plugin for Text3dDebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            debug_overlay_system.in_set(Text3dSet).after(text_render),
+        );
+    }
+}
+
+/// Collects statistics and writes them into [`Text3dDebugOverlay`]s.
+pub fn debug_overlay_system(
+    time: Res<Time>,
+    renderer: Option<Res<TextRenderer>>,
+    atlases: Res<Assets<TextAtlas>>,
+    images: Res<Assets<Image>>,
+    mut remeshed: EventReader<TextRemeshed>,
+    mut query: Query<(&mut Text3dDebugOverlay, &mut Text3d)>,
+) {
+    if query.is_empty() {
+        remeshed.clear();
+        return;
+    }
+    let remeshed_count = remeshed
+        .read()
+        .filter(|event| !query.contains(event.entity))
+        .count();
+    let contended = renderer
+        .is_some_and(|renderer| matches!(renderer.0.try_lock(), Err(TryLockError::WouldBlock)));
+    let (glyph_count, used, capacity) = atlas_usage(&atlases, &images);
+    for (mut overlay, mut text) in query.iter_mut() {
+        let overlay = &mut *overlay;
+        overlay.elapsed += time.delta_secs();
+        overlay.frames += 1;
+        overlay.remeshed += remeshed_count;
+        overlay.contended += contended as usize;
+        // Glyphs removed by clearing atlases are not counted.
+        overlay.glyphs += glyph_count.saturating_sub(overlay.last_glyphs.unwrap_or(glyph_count));
+        overlay.last_glyphs = Some(glyph_count);
+        if overlay.elapsed < overlay.refresh {
+            continue;
+        }
+        let seconds = overlay.elapsed.max(f32::EPSILON);
+        let mut stats = String::new();
+        let _ = writeln!(
+            stats,
+            "remeshed: {:.0}/s",
+            overlay.remeshed as f32 / seconds
+        );
+        let _ = writeln!(
+            stats,
+            "rasterized: {:.0} glyphs/s",
+            overlay.glyphs as f32 / seconds
+        );
+        let _ = writeln!(
+            stats,
+            "atlases: {}, {} glyphs, {:.0}% used",
+            atlases.len(),
+            glyph_count,
+            used as f32 / capacity.max(1) as f32 * 100.0
+        );
+        let _ = write!(
+            stats,
+            "lock contention: {:.0}% of frames",
+            overlay.contended as f32 / overlay.frames.max(1) as f32 * 100.0
+        );
+        overlay.elapsed = 0.0;
+        overlay.frames = 0;
+        overlay.remeshed = 0;
+        overlay.glyphs = 0;
+        overlay.contended = 0;
+        match text.get_single_mut() {
+            Some(s) => *s = stats,
+            None => *text = Text3d::new(stats),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::Assets,
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    ecs::{
+        event::EventReader,
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Local, Query, Res},
+    },
+    image::Image,
+    render::mesh::{Mesh, Mesh2d, Mesh3d},
+};
+
+use crate::{debug_overlay::atlas_usage, render::text_render, Text3d, TextAtlas, TextRemeshed};
+
+/// Reports text rendering statistics to [`bevy::diagnostic`],
+/// for spotting text that is redrawn every frame in a diagnostics overlay or log.
+///
+/// See [`Text3dDiagnosticsPlugin::GLYPHS_CACHED`], [`Text3dDiagnosticsPlugin::ATLAS_USAGE`],
+/// [`Text3dDiagnosticsPlugin::REMESHED`] and [`Text3dDiagnosticsPlugin::VERTICES`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dDiagnosticsPlugin;
+
+impl Text3dDiagnosticsPlugin {
+    /// Number of glyphs added to atlases this frame.
+    pub const GLYPHS_CACHED: DiagnosticPath = DiagnosticPath::const_new("text3d/glyphs_cached");
+    /// Percentage of rows used in all atlases.
+    pub const ATLAS_USAGE: DiagnosticPath = DiagnosticPath::const_new("text3d/atlas_usage");
+    /// Number of texts remeshed this frame.
+    pub const REMESHED: DiagnosticPath = DiagnosticPath::const_new("text3d/remeshed");
+    /// Number of vertices in all text meshes.
+    pub const VERTICES: DiagnosticPath = DiagnosticPath::const_new("text3d/vertices");
+}
+
+impl Plugin for Text3dDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::GLYPHS_CACHED))
+            .register_diagnostic(Diagnostic::new(Self::ATLAS_USAGE).with_suffix("%"))
+            .register_diagnostic(Diagnostic::new(Self::REMESHED))
+            .register_diagnostic(Diagnostic::new(Self::VERTICES));
+        app.add_systems(PostUpdate, diagnostics_system.after(text_render));
+    }
+}
+
+/// Measures diagnostics of [`Text3dDiagnosticsPlugin`].
+pub fn diagnostics_system(
+    mut diagnostics: Diagnostics,
+    atlases: Res<Assets<TextAtlas>>,
+    images: Res<Assets<Image>>,
+    meshes: Res<Assets<Mesh>>,
+    mut remeshed: EventReader<TextRemeshed>,
+    mut last_glyphs: Local<Option<usize>>,
+    texts: Query<(Option<&Mesh2d>, Option<&Mesh3d>), With<Text3d>>,
+) {
+    let (glyph_count, used, capacity) = atlas_usage(&atlases, &images);
+    // Glyphs removed by clearing atlases are not counted.
+    let cached = glyph_count.saturating_sub(last_glyphs.unwrap_or(glyph_count));
+    *last_glyphs = Some(glyph_count);
+    diagnostics.add_measurement(&Text3dDiagnosticsPlugin::GLYPHS_CACHED, || cached as f64);
+    diagnostics.add_measurement(&Text3dDiagnosticsPlugin::ATLAS_USAGE, || {
+        used as f64 / capacity.max(1) as f64 * 100.0
+    });
+    let remeshed = remeshed.read().count();
+    diagnostics.add_measurement(&Text3dDiagnosticsPlugin::REMESHED, || remeshed as f64);
+    diagnostics.add_measurement(&Text3dDiagnosticsPlugin::VERTICES, || {
+        texts
+            .iter()
+            .filter_map(|(mesh2d, mesh3d)| {
+                let handle = mesh2d.map(|x| &x.0).or(mesh3d.map(|x| &x.0))?;
+                meshes.get(handle.id())
+            })
+            .map(Mesh::count_vertices)
+            .sum::<usize>() as f64
+    });
+}
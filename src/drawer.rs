@@ -0,0 +1,137 @@
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use bevy::{
+    color::Srgba,
+    ecs::resource::Resource,
+    image::Image,
+    math::{FloatOrd, Rect},
+};
+use cosmic_text::fontdb::ID;
+use rustc_hash::{FxHashMap, FxHasher};
+
+use crate::{
+    icon::cache_pixels,
+    styling::{GlyphEntry, GlyphTextureOf},
+    Layer, SegmentStyle, StrokeJoin, TextAtlas, Weight,
+};
+
+/// Input of a [`Text3dDrawer`] for a glyph.
+#[derive(Debug, Clone, Copy)]
+pub struct DrawerInput<'t> {
+    /// Style of the glyph's segment, not merged with [`Text3dStyling`](crate::Text3dStyling).
+    pub style: &'t SegmentStyle,
+    /// Font size of the glyph.
+    pub font_size: f32,
+    /// Advance of the glyph relative to its origin on the baseline, spans the line height.
+    pub rect: Rect,
+    /// Fill color of the glyph.
+    pub color: Srgba,
+    /// Scale factor of the atlas, textures should be this many pixels per unit.
+    pub scale_factor: f32,
+}
+
+/// A texture created by [`Text3dDrawer::texture`].
+#[derive(Debug, Clone, Default)]
+pub struct DrawerTexture {
+    pub width: usize,
+    pub height: usize,
+    /// Linear RGBA pixels in row major order, `width * height` in length.
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// Draws a custom quad for each glyph, like backgrounds or glows,
+/// enabled by name in [`Text3dStyling::drawers`](crate::Text3dStyling::drawers)
+/// or [`SegmentStyle::drawers`].
+///
+/// Textures are copied into the [`TextAtlas`] and cached by [`Text3dDrawer::texture_key`].
+pub trait Text3dDrawer: Send + Sync + 'static {
+    /// Sort key of the quad, by default [`Layer::Background`].
+    fn layer(&self) -> Layer {
+        Layer::Background
+    }
+
+    /// Identifies the texture of a glyph, glyphs with the same key share a texture.
+    fn texture_key(&self, input: &DrawerInput) -> u64;
+
+    /// Create the texture of a glyph, called once per [`Text3dDrawer::texture_key`] per atlas.
+    ///
+    /// Must be smaller than the atlas' width.
+    fn texture(&self, input: &DrawerInput) -> Option<DrawerTexture>;
+
+    /// Rectangle of the quad relative to the glyph's origin on the baseline, `None` skips the glyph.
+    fn quad(&self, input: &DrawerInput) -> Option<Rect>;
+
+    /// Color multiplied with the texture, by default the fill color of the glyph.
+    fn color(&self, input: &DrawerInput) -> Srgba {
+        input.color
+    }
+}
+
+/// Named [`Text3dDrawer`]s that can be referenced by [`Text3dStyling::drawers`](crate::Text3dStyling::drawers)
+/// and [`SegmentStyle::drawers`].
+///
+/// Text is redrawn when this resource changes.
+#[derive(Clone, Default, Resource)]
+pub struct Text3dDrawers {
+    pub drawers: FxHashMap<String, Arc<dyn Text3dDrawer>>,
+}
+
+impl Text3dDrawers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a named drawer.
+    pub fn with_drawer(mut self, name: impl Into<String>, drawer: impl Text3dDrawer) -> Self {
+        self.drawers.insert(name.into(), Arc::new(drawer));
+        self
+    }
+
+    /// Add or replace a named drawer.
+    pub fn insert(&mut self, name: impl Into<String>, drawer: impl Text3dDrawer) {
+        self.drawers.insert(name.into(), Arc::new(drawer));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn Text3dDrawer>> {
+        self.drawers.get(name)
+    }
+}
+
+/// Obtain the atlas rectangle of a drawer's texture, creating it if needed.
+pub(crate) fn drawer_texture(
+    atlas: &mut TextAtlas,
+    image: &mut Image,
+    drawer: &Arc<dyn Text3dDrawer>,
+    input: &DrawerInput,
+) -> Option<Rect> {
+    let mut hasher = FxHasher::default();
+    (Arc::as_ptr(drawer) as *const () as usize).hash(&mut hasher);
+    drawer.texture_key(input).hash(&mut hasher);
+    let entry = GlyphEntry {
+        font: ID::dummy(),
+        glyph_id: GlyphTextureOf::Custom(hasher.finish()),
+        join: StrokeJoin::default(),
+        size: FloatOrd(0.0),
+        weight: Weight::NORMAL,
+        stroke: None,
+        padding: 0,
+        scale_factor: FloatOrd(input.scale_factor),
+        variations: 0,
+        outline: 0,
+    };
+    if let Some((rect, _)) = atlas.glyphs.get(&entry) {
+        return Some(*rect);
+    }
+    let texture = drawer.texture(input)?;
+    Some(cache_pixels(
+        atlas,
+        image,
+        entry,
+        texture.width,
+        texture.height,
+        &texture.pixels,
+    ))
+}
@@ -0,0 +1,59 @@
+use std::ops::Range;
+
+use bevy::{
+    asset::AssetId,
+    ecs::{component::Component, entity::Entity},
+    math::Rect,
+    render::mesh::Mesh,
+};
+
+#[cfg(feature = "reflect")]
+use bevy::{ecs::reflect::ReflectComponent, reflect::Reflect};
+
+/// Emits glyphs of [`Text3dSegment::Extract`](crate::Text3dSegment::Extract) segments
+/// into the mesh of another entity, so a counter in a long paragraph does not rewrite the paragraph's mesh.
+///
+/// `entity` should have a [`Mesh2d`](bevy::render::mesh::Mesh2d) or [`Mesh3d`](bevy::render::mesh::Mesh3d)
+/// and the same material as the text, usually as a child. Its mesh is created if missing.
+///
+/// If only fetched values changed and the static glyphs did not move, only the dynamic mesh is rebuilt,
+/// otherwise the text's own mesh is rebuilt but only written if its contents changed.
+/// Glyphs in [`Text3dLayoutInfo`](crate::Text3dLayoutInfo) drawn into the dynamic mesh are marked
+/// [`GlyphLayoutInfo::dynamic`](crate::GlyphLayoutInfo::dynamic).
+///
+/// Per-glyph effects such as [`Text3dGlyphAnimation`](crate::Text3dGlyphAnimation),
+/// [`Text3dColorSpans`](crate::Text3dColorSpans) and [`Text3dMarquee`](crate::Text3dMarquee)
+/// only apply to the static glyphs.
+#[derive(Debug, Clone, Component)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct Text3dDynamicMesh {
+    pub entity: Entity,
+    /// Id and hash of the last written static mesh.
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    pub(crate) static_mesh: Option<(AssetId<Mesh>, u64)>,
+    /// Static glyphs of the last rebuilt static mesh.
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    pub(crate) static_layout: Option<StaticLayout>,
+}
+
+impl Text3dDynamicMesh {
+    pub fn new(entity: Entity) -> Self {
+        Text3dDynamicMesh {
+            entity,
+            static_mesh: None,
+            static_layout: None,
+        }
+    }
+}
+
+/// Static glyphs of a [`Text3dDynamicMesh`], reused when only fetched segments change.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StaticLayout {
+    /// Hash of the layout static glyphs are drawn from.
+    pub key: u64,
+    /// Horizontal extent of static glyphs on each line.
+    pub extents: Vec<(f32, f32)>,
+    /// Fill quad, atlas rectangle and vertices of each static glyph, if layout info is recorded.
+    pub glyphs: Option<Vec<(Option<Rect>, Option<Rect>, Range<usize>)>>,
+}
@@ -0,0 +1,263 @@
+use std::ops::Range;
+
+use bevy::{
+    ecs::{component::Component, query::Changed, system::Query},
+    math::{Rect, Vec2},
+};
+use cosmic_text::{Affinity, LayoutRun, LineIter};
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+use crate::{render::BufferOffsets, Text3d, Text3dSegment};
+
+/// An editable single segment [`Text3d`] with a cursor and a selection.
+///
+/// Changes are written into [`Text3d`] as a single [`Text3dSegment::String`]
+/// that inherits the style of the original first segment.
+///
+/// The caret and selection rectangles are updated in local space after the text is rendered,
+/// and can be used to render a caret or a selection highlight.
+#[derive(Debug, Component, Default, Clone)]
+#[require(Text3d)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dEditor {
+    text: String,
+    cursor: usize,
+    anchor: Option<usize>,
+    pub(crate) caret_rect: Option<Rect>,
+    pub(crate) selection_rects: Vec<Rect>,
+}
+
+impl Text3dEditor {
+    /// Create an editor with the cursor at the end of the text.
+    pub fn new(text: impl ToString) -> Self {
+        let text = text.to_string();
+        Self {
+            cursor: text.len(),
+            text,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the text being edited.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, moves the cursor to the end and clears the selection.
+    pub fn set_text(&mut self, text: impl ToString) {
+        self.text = text.to_string();
+        self.cursor = self.text.len();
+        self.anchor = None;
+    }
+
+    /// Byte index of the cursor.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Set the cursor's byte index, clamped to the nearest char boundary before `index`.
+    pub fn set_cursor(&mut self, index: usize) {
+        self.cursor = self.floor_char_boundary(index);
+        self.anchor = None;
+    }
+
+    /// Returns the selected byte range, if not empty.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        match anchor.cmp(&self.cursor) {
+            std::cmp::Ordering::Less => Some(anchor..self.cursor),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(self.cursor..anchor),
+        }
+    }
+
+    /// Returns the selected text, if not empty.
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection().and_then(|range| self.text.get(range))
+    }
+
+    /// Select a byte range, the cursor is placed at the end of the range.
+    pub fn select(&mut self, range: Range<usize>) {
+        self.anchor = Some(self.floor_char_boundary(range.start));
+        self.cursor = self.floor_char_boundary(range.end);
+    }
+
+    /// Select the entire text.
+    pub fn select_all(&mut self) {
+        self.select(0..self.text.len());
+    }
+
+    /// Clear the selection without moving the cursor.
+    pub fn clear_selection(&mut self) {
+        self.anchor = None;
+    }
+
+    /// Insert a string at the cursor, replaces the selection if present.
+    pub fn insert(&mut self, s: &str) {
+        self.delete_selection();
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    /// Delete the selection or the char before the cursor.
+    pub fn backspace(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        if let Some((index, _)) = self.text[..self.cursor].char_indices().next_back() {
+            self.text.replace_range(index..self.cursor, "");
+            self.cursor = index;
+        }
+    }
+
+    /// Delete the selection or the char after the cursor.
+    pub fn delete(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.text
+                .replace_range(self.cursor..self.cursor + c.len_utf8(), "");
+        }
+    }
+
+    /// Delete the selection, returns true if the selection is not empty.
+    pub fn delete_selection(&mut self) -> bool {
+        let Some(range) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        self.text.replace_range(range.clone(), "");
+        self.cursor = range.start;
+        self.anchor = None;
+        true
+    }
+
+    /// Move the cursor one char to the left, if `select`, extend the selection.
+    pub fn move_left(&mut self, select: bool) {
+        let index = self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        self.move_to(index, select);
+    }
+
+    /// Move the cursor one char to the right, if `select`, extend the selection.
+    pub fn move_right(&mut self, select: bool) {
+        let index = self.text[self.cursor..]
+            .chars()
+            .next()
+            .map(|c| self.cursor + c.len_utf8())
+            .unwrap_or(self.cursor);
+        self.move_to(index, select);
+    }
+
+    /// Move the cursor to the start of the current line, if `select`, extend the selection.
+    pub fn move_home(&mut self, select: bool) {
+        self.move_to(self.current_line().start, select);
+    }
+
+    /// Move the cursor to the end of the current line, if `select`, extend the selection.
+    pub fn move_end(&mut self, select: bool) {
+        self.move_to(self.current_line().end, select);
+    }
+
+    /// Byte range of the line containing the cursor without its line ending,
+    /// split the same way as [`BufferLine`](cosmic_text::BufferLine)s.
+    fn current_line(&self) -> Range<usize> {
+        LineIter::new(&self.text)
+            .map(|(range, _)| range)
+            .find(|range| range.end >= self.cursor)
+            .unwrap_or(self.text.len()..self.text.len())
+    }
+
+    /// Move the cursor to a byte index, if `select`, extend the selection.
+    pub fn move_to(&mut self, index: usize, select: bool) {
+        let index = self.floor_char_boundary(index);
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = index;
+    }
+
+    /// Rectangle of the caret in local space, with a width of `0`.
+    ///
+    /// Updated after the text is rendered.
+    pub fn caret_rect(&self) -> Option<Rect> {
+        self.caret_rect
+    }
+
+    /// Rectangles of the selection in local space, one per line.
+    ///
+    /// Updated after the text is rendered.
+    pub fn selection_rects(&self) -> &[Rect] {
+        &self.selection_rects
+    }
+
+    fn floor_char_boundary(&self, index: usize) -> usize {
+        let mut index = index.min(self.text.len());
+        while !self.text.is_char_boundary(index) {
+            index -= 1;
+        }
+        index
+    }
+
+    /// Collect caret and selection rectangles of a laid out line, before anchor offset is applied.
+    pub(crate) fn collect_rects(
+        &self,
+        run: &LayoutRun,
+        dx: f32,
+        offsets: &BufferOffsets,
+        caret: &mut Option<Rect>,
+        selection: &mut Vec<Rect>,
+    ) {
+        let top = -run.line_top;
+        let bottom = -run.line_top - run.line_height;
+        let rect = |x: f32, w: f32| Rect {
+            min: Vec2::new(x + dx, bottom),
+            max: Vec2::new(x + dx + w, top),
+        };
+        if caret.is_none() {
+            let before = offsets.to_cursor(self.cursor, Affinity::Before);
+            let after = offsets.to_cursor(self.cursor, Affinity::After);
+            if let Some((x, _)) = run.highlight(before, after) {
+                *caret = Some(rect(x, 0.0));
+            } else if run.glyphs.is_empty() && run.line_i == before.line {
+                *caret = Some(rect(0.0, 0.0));
+            }
+        }
+        if let Some(range) = self.selection() {
+            let start = offsets.to_cursor(range.start, Affinity::After);
+            let end = offsets.to_cursor(range.end, Affinity::Before);
+            if let Some((x, w)) = run.highlight(start, end) {
+                if w > 0.0 {
+                    selection.push(rect(x, w));
+                }
+            }
+        }
+    }
+}
+
+/// Writes the content of [`Text3dEditor`] into [`Text3d`].
+pub fn text_editor_system(mut query: Query<(&Text3dEditor, &mut Text3d), Changed<Text3dEditor>>) {
+    for (editor, mut text) in query.iter_mut() {
+        if text.get_single() == Some(editor.text()) {
+            continue;
+        }
+        let style = text
+            .segments
+            .first()
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default();
+        text.segments = vec![(Text3dSegment::String(editor.text.clone()), style)];
+    }
+}
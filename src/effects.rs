@@ -0,0 +1,140 @@
+// `ShaderType` generates unused size checks for each field of `Text3dEffects`.
+#![allow(dead_code)]
+
+use bevy::{
+    app::{App, Plugin},
+    asset::{load_internal_asset, weak_handle, Asset, Handle},
+    color::LinearRgba,
+    pbr::{ExtendedMaterial, MaterialExtension, MaterialPlugin, StandardMaterial},
+    reflect::Reflect,
+    render::render_resource::{AsBindGroup, Shader, ShaderRef, ShaderType},
+};
+
+use crate::TouchTextMaterial3dPlugin;
+
+const EFFECTS_EXTENSION_SHADER: Handle<Shader> =
+    weak_handle!("7c2e9a41-5d83-4f6b-a0e7-94b1c3d8f256");
+
+/// A [`StandardMaterial`] with a [`Text3dEffectsExtension`].
+pub type Text3dStandardMaterial = ExtendedMaterial<StandardMaterial, Text3dEffectsExtension>;
+
+/// A [`MaterialExtension`] that applies common text effects from [`Text3dEffects`].
+///
+/// Effects are driven by `uv1.x`, which should increase along the text,
+/// i.e. [`GlyphMeta::Index`](crate::GlyphMeta::Index) or
+/// [`GlyphMeta::PerGlyphAdvance`](crate::GlyphMeta::PerGlyphAdvance) in
+/// [`Text3dStyling::uv1`](crate::Text3dStyling::uv1).
+///
+/// Requires the `effects` feature.
+#[derive(Debug, Clone, Default, Asset, AsBindGroup, Reflect)]
+pub struct Text3dEffectsExtension {
+    #[uniform(100)]
+    pub effects: Text3dEffects,
+}
+
+impl MaterialExtension for Text3dEffectsExtension {
+    fn vertex_shader() -> ShaderRef {
+        EFFECTS_EXTENSION_SHADER.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        EFFECTS_EXTENSION_SHADER.into()
+    }
+}
+
+/// Parameters of [`Text3dEffectsExtension`], every effect is disabled by default.
+///
+/// Times are in seconds of [`Time`](bevy::time::Time), as seen by the shader's `globals.time`.
+#[derive(Debug, Clone, Copy, PartialEq, ShaderType, Reflect)]
+pub struct Text3dEffects {
+    /// Time when the typewriter reveal started.
+    pub typewriter_start: f32,
+    /// Units of `uv1.x` revealed per second, `0.0` disables the effect.
+    pub typewriter_speed: f32,
+    /// Cycles of the rainbow per second.
+    pub rainbow_speed: f32,
+    /// Hue change in radians per unit of `uv1.x`, `0.0` disables the effect.
+    pub rainbow_scale: f32,
+    /// Vertical wobble in radians per second.
+    pub wobble_frequency: f32,
+    /// Height of the wobble in local units, `0.0` disables the effect.
+    pub wobble_amplitude: f32,
+    /// Time when the fade started.
+    pub fade_start: f32,
+    /// Length of a fade in, or a fade out if negative, `0.0` disables the effect.
+    pub fade_duration: f32,
+    /// Color mixed into the anti-aliased edges of glyphs, by its alpha.
+    pub outline_tint: LinearRgba,
+    /// Coverage below which a pixel is considered an edge, `0.0` disables the effect.
+    pub outline_width: f32,
+}
+
+impl Default for Text3dEffects {
+    fn default() -> Self {
+        Text3dEffects {
+            typewriter_start: 0.0,
+            typewriter_speed: 0.0,
+            rainbow_speed: 0.0,
+            rainbow_scale: 0.0,
+            wobble_frequency: 0.0,
+            wobble_amplitude: 0.0,
+            fade_start: 0.0,
+            fade_duration: 0.0,
+            outline_tint: LinearRgba::NONE,
+            outline_width: 0.0,
+        }
+    }
+}
+
+impl Text3dEffects {
+    /// Reveal `speed` units of `uv1.x` per second starting at `start`.
+    pub fn with_typewriter(mut self, start: f32, speed: f32) -> Self {
+        self.typewriter_start = start;
+        self.typewriter_speed = speed;
+        self
+    }
+
+    pub fn with_rainbow(mut self, speed: f32, scale: f32) -> Self {
+        self.rainbow_speed = speed;
+        self.rainbow_scale = scale;
+        self
+    }
+
+    pub fn with_wobble(mut self, frequency: f32, amplitude: f32) -> Self {
+        self.wobble_frequency = frequency;
+        self.wobble_amplitude = amplitude;
+        self
+    }
+
+    /// Fade in over `duration` starting at `start`, or fade out if `duration` is negative.
+    pub fn with_fade(mut self, start: f32, duration: f32) -> Self {
+        self.fade_start = start;
+        self.fade_duration = duration;
+        self
+    }
+
+    pub fn with_outline_tint(mut self, tint: impl Into<LinearRgba>, width: f32) -> Self {
+        self.outline_tint = tint.into();
+        self.outline_width = width;
+        self
+    }
+}
+
+/// Adds [`Text3dStandardMaterial`] and its [`TouchTextMaterial3dPlugin`].
+///
+/// Requires the `effects` feature.
+#[derive(Debug, Default)]
+pub struct Text3dEffectsPlugin;
+
+impl Plugin for Text3dEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            EFFECTS_EXTENSION_SHADER,
+            "effects_extension.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins(MaterialPlugin::<Text3dStandardMaterial>::default());
+        app.add_plugins(TouchTextMaterial3dPlugin::<Text3dStandardMaterial>::default());
+    }
+}
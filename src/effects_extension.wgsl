@@ -0,0 +1,111 @@
+#import bevy_pbr::{
+    mesh_functions,
+    forward_io::{Vertex, VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    pbr_types::STANDARD_MATERIAL_FLAGS_UNLIT_BIT,
+    mesh_view_bindings::globals,
+    view_transformations::position_world_to_clip,
+}
+#import bevy_render::color_operations::hsv_to_rgb
+
+struct Text3dEffects {
+    typewriter_start: f32,
+    typewriter_speed: f32,
+    rainbow_speed: f32,
+    rainbow_scale: f32,
+    wobble_frequency: f32,
+    wobble_amplitude: f32,
+    fade_start: f32,
+    fade_duration: f32,
+    outline_tint: vec4<f32>,
+    outline_width: f32,
+}
+
+@group(2) @binding(100) var<uniform> effects: Text3dEffects;
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    var world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+
+#ifdef VERTEX_NORMALS
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        vertex.normal,
+        vertex.instance_index
+    );
+#endif
+
+#ifdef VERTEX_POSITIONS
+    var position = vertex.position;
+#ifdef VERTEX_UVS_B
+    position.y += sin(globals.time * effects.wobble_frequency + vertex.uv_b.x) * effects.wobble_amplitude;
+#endif
+    out.world_position = mesh_functions::mesh_position_local_to_world(world_from_local, vec4<f32>(position, 1.0));
+    out.position = position_world_to_clip(out.world_position.xyz);
+#endif
+
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+#ifdef VERTEX_UVS_B
+    out.uv_b = vertex.uv_b;
+#endif
+
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+
+#ifdef VISIBILITY_RANGE_DITHER
+    out.visibility_range_dither = mesh_functions::get_visibility_range_dither_level(
+        vertex.instance_index, world_from_local[3]);
+#endif
+
+    return out;
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    var color = pbr_input.material.base_color;
+#ifdef VERTEX_UVS_B
+    let phase = in.uv_b.x;
+#else
+    let phase = 0.0;
+#endif
+
+    if effects.outline_width > 0.0 {
+        // Anti-aliased edges of glyphs have partial coverage.
+        let edge = 1.0 - smoothstep(0.0, effects.outline_width, color.a);
+        color = vec4(mix(color.rgb, effects.outline_tint.rgb, edge * effects.outline_tint.a), color.a);
+    }
+    if effects.rainbow_scale != 0.0 {
+        let hue = phase * effects.rainbow_scale + globals.time * effects.rainbow_speed * 6.2831853;
+        color *= vec4(hsv_to_rgb(vec3(hue, 1.0, 1.0)), 1.0);
+    }
+    if effects.typewriter_speed > 0.0 {
+        color.a *= clamp((globals.time - effects.typewriter_start) * effects.typewriter_speed - phase, 0.0, 1.0);
+    }
+    if effects.fade_duration != 0.0 {
+        let t = clamp((globals.time - effects.fade_start) / abs(effects.fade_duration), 0.0, 1.0);
+        color.a *= select(t, 1.0 - t, effects.fade_duration < 0.0);
+    }
+
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, color);
+    var out: FragmentOutput;
+    if (pbr_input.material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u {
+        out.color = apply_pbr_lighting(pbr_input);
+    } else {
+        out.color = pbr_input.material.base_color;
+    }
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
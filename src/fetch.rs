@@ -1,18 +1,34 @@
-use std::str::FromStr;
-
-use bevy::ecs::{
-    change_detection::DetectChanges,
-    component::Component,
-    entity::Entity,
-    query::Without,
-    system::Query,
-    world::{EntityRef, Mut},
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
 };
+
+use bevy::{
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::{Component, Tick},
+        entity::{Entities, Entity},
+        event::{Event, EventCursor, Events},
+        query::Without,
+        resource::Resource,
+        system::{Commands, Local, Query, SystemState},
+        world::{EntityRef, Mut, World},
+    },
+    time::Time,
+};
+
+use crate::{Text3d, Text3dSegment, TextSegmentUpdated};
 #[cfg(feature = "reflect")]
-use bevy::prelude::{Reflect, ReflectComponent, ReflectDefault};
+use bevy::{
+    ecs::reflect::AppTypeRegistry,
+    prelude::{Reflect, ReflectComponent, ReflectDefault},
+    reflect::{PartialReflect, ReflectPath, ReflectRef},
+};
 
 /// Prevent [`Text3d`](crate::Text3d) from despawning a [`FetchedTextSegment`] on remove.
-#[derive(Debug, Component, Default)]
+///
+/// Also prevents a [`TextFetch`] from being despawned when its target entity is despawned.
+#[derive(Debug, Component, Default, Clone, Copy)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
 pub struct SharedTextSegment;
@@ -21,7 +37,7 @@ pub struct SharedTextSegment;
 ///
 /// By default [`Text3d`](crate::Text3d) removes all [`FetchedTextSegment`] on remove,
 /// add [`SharedTextSegment`] to prevent this behavior.
-#[derive(Debug, Component, Default)]
+#[derive(Debug, Component, Default, Clone)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
 pub struct FetchedTextSegment(pub String);
@@ -51,14 +67,209 @@ impl FetchedTextSegment {
     }
 }
 
+/// Formats numbers fetched by [`TextFetch::fetch_component_formatted`] and [`TextFetch::fetch_resource_formatted`].
+///
+/// # Example
+///
+/// ```
+/// # use bevy_rich_text3d::NumberFormat;
+/// let format = NumberFormat::new(2).with_thousands_separator(',');
+/// assert_eq!(format.format(1234567.891), "1,234,567.89");
+/// assert_eq!(NumberFormat::new(1).percent().format(0.125), "12.5%");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Default))]
+pub struct NumberFormat {
+    /// Number of digits after the decimal point.
+    pub precision: usize,
+    /// Inserted between groups of 3 digits.
+    pub thousands_separator: Option<char>,
+    pub decimal_point: char,
+    /// Minimum number of characters, padded on the left with `pad`.
+    pub width: usize,
+    pub pad: char,
+    /// If true, multiplies by 100 and appends `%`.
+    pub percent: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            precision: 0,
+            thousands_separator: None,
+            decimal_point: '.',
+            width: 0,
+            pad: ' ',
+            percent: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Format with a fixed number of digits after the decimal point.
+    pub fn new(precision: usize) -> Self {
+        Self {
+            precision,
+            ..Default::default()
+        }
+    }
+
+    /// Use the decimal point and thousands separator of a locale like `de-DE`,
+    /// only the language is considered.
+    pub fn with_locale(mut self, locale: &str) -> Self {
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        let (decimal_point, thousands_separator) = match language {
+            "de" | "nl" | "id" | "tr" | "da" | "it" | "es" | "pt" | "el" => (',', '.'),
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "fi" | "uk" | "hu" | "bg" => {
+                (',', '\u{a0}')
+            }
+            _ => ('.', ','),
+        };
+        self.decimal_point = decimal_point;
+        self.thousands_separator = Some(thousands_separator);
+        self
+    }
+
+    pub fn with_thousands_separator(mut self, separator: char) -> Self {
+        self.thousands_separator = Some(separator);
+        self
+    }
+
+    pub fn with_decimal_point(mut self, decimal_point: char) -> Self {
+        self.decimal_point = decimal_point;
+        self
+    }
+
+    /// Pad on the left to at least `width` characters, zeros are inserted after the sign.
+    pub fn with_width(mut self, width: usize, pad: char) -> Self {
+        self.width = width;
+        self.pad = pad;
+        self
+    }
+
+    /// Multiply by 100 and append `%`.
+    pub fn percent(mut self) -> Self {
+        self.percent = true;
+        self
+    }
+
+    /// Format a number.
+    pub fn format(&self, value: f64) -> String {
+        let value = if self.percent { value * 100.0 } else { value };
+        let digits = format!("{:.*}", self.precision, value.abs());
+        let (int, fract) = match digits.split_once('.') {
+            Some((int, fract)) => (int, Some(fract)),
+            None => (digits.as_str(), None),
+        };
+        let mut result = String::new();
+        // Avoid `-0`.
+        if value.is_sign_negative() && digits.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+            result.push('-');
+        }
+        let sign = result.len();
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    result.push(separator);
+                }
+            }
+            result.push(c);
+        }
+        if let Some(fract) = fract {
+            result.push(self.decimal_point);
+            result.push_str(fract);
+        }
+        if self.percent {
+            result.push('%');
+        }
+        let len = result.chars().count();
+        if len < self.width {
+            let at = if self.pad == '0' { sign } else { 0 };
+            let padding: String = std::iter::repeat_n(self.pad, self.width - len).collect();
+            result.insert_str(at, &padding);
+        }
+        result
+    }
+}
+
 /// A component that fetches data as a string from the world.
-#[derive(Component)]
+///
+/// Clones of a [`TextFetch`] share the same fetch function and its state,
+/// but each clone keeps its own [`TextFetch::every_seconds`] or [`TextFetch::on_event`] state.
+///
+/// By default the fetch function runs every frame,
+/// see [`TextFetch::every_seconds`] and [`TextFetch::on_event`] to run it less often.
+/// Change detection in the fetch function is relative to its last run,
+/// so changes made in skipped frames are still fetched.
+#[derive(Component, Clone)]
 #[require(FetchedTextSegment)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
-#[cfg_attr(feature = "reflect", reflect(Component))]
+// The fetch function cannot be created from reflected data.
+#[cfg_attr(feature = "reflect", reflect(Component, from_reflect = false))]
 pub struct TextFetch {
-    entity: Entity,
-    fetch: Box<dyn FnMut(EntityRef) -> Option<String> + Send + Sync>,
+    entity: Option<Entity>,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    fetch: Arc<Mutex<FetchFn>>,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    policy: FetchPolicy,
+    /// Change tick of the last run of the fetch function.
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    last_fetch: Option<Tick>,
+}
+
+/// When the fetch function of a [`TextFetch`] runs.
+#[derive(Clone)]
+enum FetchPolicy {
+    EveryFrame,
+    /// Runs when the elapsed [`Time`] reaches `next`.
+    Interval {
+        seconds: f64,
+        next: f64,
+    },
+    Trigger(Box<dyn FetchTrigger>),
+}
+
+/// A cloneable trigger of [`FetchPolicy::Trigger`].
+trait FetchTrigger: FnMut(&World, Option<Entity>) -> bool + Send + Sync + 'static {
+    fn clone_box(&self) -> Box<dyn FetchTrigger>;
+}
+
+impl<T: FnMut(&World, Option<Entity>) -> bool + Clone + Send + Sync + 'static> FetchTrigger for T {
+    fn clone_box(&self) -> Box<dyn FetchTrigger> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn FetchTrigger> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl FetchPolicy {
+    fn should_fetch(&mut self, world: &World, entity: Option<Entity>) -> bool {
+        match self {
+            FetchPolicy::EveryFrame => true,
+            FetchPolicy::Interval { seconds, next } => {
+                let Some(time) = world.get_resource::<Time>() else {
+                    return true;
+                };
+                let now = time.elapsed_secs_f64();
+                if now < *next {
+                    return false;
+                }
+                *next = now + *seconds;
+                true
+            }
+            FetchPolicy::Trigger(trigger) => trigger(world, entity),
+        }
+    }
+}
+
+enum FetchFn {
+    Entity(Box<dyn FnMut(EntityRef) -> Option<String> + Send + Sync>),
+    World(Box<dyn FnMut(&World) -> Option<String> + Send + Sync>),
 }
 
 impl TextFetch {
@@ -67,17 +278,26 @@ impl TextFetch {
         entity: Entity,
         mut fetch: impl (FnMut(&C) -> String) + Send + Sync + 'static,
     ) -> Self {
-        TextFetch {
-            entity,
-            fetch: Box::new(move |entity: EntityRef| {
-                if let Some(component) = entity.get_ref::<C>() {
-                    if component.is_changed() {
-                        return Some(fetch(&component));
-                    }
+        Self::fetch_entity_ref(entity, move |entity: EntityRef| {
+            if let Some(component) = entity.get_ref::<C>() {
+                if component.is_changed() {
+                    return Some(fetch(&component));
                 }
-                None
-            }),
-        }
+            }
+            None
+        })
+    }
+
+    /// Create a text fetcher that fetches a number from a single component if the component changes,
+    /// and formats it with a [`NumberFormat`].
+    pub fn fetch_component_formatted<C: Component, T: Into<f64>>(
+        entity: Entity,
+        format: NumberFormat,
+        mut fetch: impl (FnMut(&C) -> T) + Send + Sync + 'static,
+    ) -> Self {
+        Self::fetch_component::<C>(entity, move |component| {
+            format.format(fetch(component).into())
+        })
     }
 
     /// Create a text fetcher that fetches from an [`EntityRef`].
@@ -86,22 +306,447 @@ impl TextFetch {
         fetch: impl (FnMut(EntityRef) -> Option<String>) + Send + Sync + 'static,
     ) -> Self {
         TextFetch {
-            entity,
-            fetch: Box::new(fetch),
+            entity: Some(entity),
+            fetch: Arc::new(Mutex::new(FetchFn::Entity(Box::new(fetch)))),
+            policy: FetchPolicy::EveryFrame,
+            last_fetch: None,
+        }
+    }
+
+    /// Create a text fetcher that fetches a string from a [`Resource`] if the resource changes,
+    /// for global values like score or time of day without a proxy entity.
+    ///
+    /// The segment is not updated while the resource does not exist.
+    pub fn fetch_resource<R: Resource>(
+        mut fetch: impl (FnMut(&R) -> String) + Send + Sync + 'static,
+    ) -> Self {
+        Self::fetch_world(move |world: &World| {
+            let resource = world.get_resource_ref::<R>()?;
+            resource.is_changed().then(|| fetch(&resource))
+        })
+    }
+
+    /// Create a text fetcher that fetches from the [`World`], returns `None` to keep the current value.
+    ///
+    /// Runs every frame, use change detection to avoid formatting unchanged values.
+    pub fn fetch_world(
+        fetch: impl (FnMut(&World) -> Option<String>) + Send + Sync + 'static,
+    ) -> Self {
+        TextFetch {
+            entity: None,
+            fetch: Arc::new(Mutex::new(FetchFn::World(Box::new(fetch)))),
+            policy: FetchPolicy::EveryFrame,
+            last_fetch: None,
+        }
+    }
+
+    /// Create a text fetcher that fetches a number from a [`Resource`] if the resource changes,
+    /// and formats it with a [`NumberFormat`].
+    pub fn fetch_resource_formatted<R: Resource, T: Into<f64>>(
+        format: NumberFormat,
+        mut fetch: impl (FnMut(&R) -> T) + Send + Sync + 'static,
+    ) -> Self {
+        Self::fetch_resource::<R>(move |resource| format.format(fetch(resource).into()))
+    }
+
+    /// Create a text fetcher that fetches a value by its reflection path if the component changes,
+    /// so bindings can be declared in data files instead of closures.
+    ///
+    /// The path starts with the component's short or full type path, followed by
+    /// a [`bevy_reflect` path](bevy::reflect::GetPath), like `Health.current` or `Inventory.items[0].name`.
+    /// Map entries can be accessed by key with `[key]` if the key is a string or a unit enum variant,
+    /// like `StatMap.0[Strength]`.
+    ///
+    /// The component must be registered with [`ReflectComponent`] in the [`AppTypeRegistry`].
+    #[cfg(feature = "reflect")]
+    pub fn fetch_reflect_path(entity: Entity, path: impl Into<String>) -> Self {
+        let path: String = path.into();
+        TextFetch {
+            entity: Some(entity),
+            fetch: Arc::new(Mutex::new(FetchFn::World(Box::new(
+                move |world: &World| reflect_path(world, entity, &path),
+            )))),
+            policy: FetchPolicy::EveryFrame,
+            last_fetch: None,
+        }
+    }
+
+    /// Only run the fetch function every `seconds` of [`Time`], instead of every frame.
+    ///
+    /// Reduces overhead when many texts track slow changing data.
+    pub fn every_seconds(mut self, seconds: f32) -> Self {
+        self.policy = FetchPolicy::Interval {
+            seconds: seconds as f64,
+            next: 0.0,
+        };
+        self
+    }
+
+    /// Only run the fetch function in frames where an event `E` is sent.
+    pub fn on_event<E: Event>(mut self) -> Self {
+        let mut cursor = EventCursor::<E>::default();
+        self.policy = FetchPolicy::Trigger(Box::new(move |world: &World, _| {
+            world
+                .get_resource::<Events<E>>()
+                .is_some_and(|events| cursor.read(events).count() > 0)
+        }));
+        self
+    }
+
+    /// Only run the fetch function when component `C` on the target entity changes.
+    pub fn on_changed<C: Component>(mut self) -> Self {
+        self.policy = FetchPolicy::Trigger(Box::new(|world: &World, entity: Option<Entity>| {
+            entity
+                .and_then(|entity| world.get_entity(entity).ok())
+                .and_then(|entity| entity.get_ref::<C>())
+                .is_some_and(|component| component.is_changed())
+        }));
+        self
+    }
+
+    /// Only run the fetch function when resource `R` changes.
+    pub fn on_resource_changed<R: Resource>(mut self) -> Self {
+        self.policy = FetchPolicy::Trigger(Box::new(|world: &World, _| {
+            world
+                .get_resource_ref::<R>()
+                .is_some_and(|x| x.is_changed())
+        }));
+        self
+    }
+
+    /// Returns the entity fetched from, `None` if fetching from a [`Resource`].
+    pub fn entity(&self) -> Option<Entity> {
+        self.entity
+    }
+}
+
+#[cfg(feature = "reflect")]
+fn reflect_path(world: &World, entity: Entity, path: &str) -> Option<String> {
+    let (component, mut path) = path.split_at(path.find(['.', '[']).unwrap_or(path.len()));
+    let registry = world.get_resource::<AppTypeRegistry>()?.read();
+    let registration = registry
+        .get_with_short_type_path(component)
+        .or_else(|| registry.get_with_type_path(component))?;
+    let entity = world.get_entity(entity).ok()?;
+    let id = world.components().get_id(registration.type_id())?;
+    if !entity
+        .get_change_ticks_by_id(id)?
+        .is_changed(world.last_change_tick(), world.read_change_tick())
+    {
+        return None;
+    }
+    let mut value = registration
+        .data::<ReflectComponent>()?
+        .reflect(entity)?
+        .as_partial_reflect();
+    // `bevy_reflect` paths do not support map keys, split the path at each `[key]`.
+    while !path.is_empty() {
+        let key = path
+            .match_indices('[')
+            .map(|(i, _)| i)
+            .find(|i| !path[i + 1..].starts_with(|c: char| c.is_ascii_digit()));
+        let (reflect, rest) = path.split_at(key.unwrap_or(path.len()));
+        if !reflect.is_empty() {
+            value = reflect.reflect_element(value).ok()?;
+        }
+        path = rest;
+        if let Some(rest) = path.strip_prefix('[') {
+            let (key, rest) = rest.split_once(']')?;
+            let ReflectRef::Map(map) = value.reflect_ref() else {
+                return None;
+            };
+            value = map
+                .iter()
+                .find(|(k, _)| match k.reflect_ref() {
+                    ReflectRef::Enum(e) => e.variant_name() == key,
+                    _ => k.try_downcast_ref::<String>().is_some_and(|k| k == key),
+                })?
+                .1;
+            path = rest;
         }
     }
+    Some(reflect_to_string(value))
 }
 
-/// Triggers the [`TextFetch`] component.
+#[cfg(feature = "reflect")]
+fn reflect_to_string(value: &dyn PartialReflect) -> String {
+    macro_rules! to_string {
+        ($($ty: ty),*) => {
+            $(if let Some(value) = value.try_downcast_ref::<$ty>() {
+                return value.to_string();
+            })*
+        };
+    }
+    to_string!(
+        String,
+        &'static str,
+        bool,
+        char,
+        f32,
+        f64,
+        i8,
+        i16,
+        i32,
+        i64,
+        isize,
+        u8,
+        u16,
+        u32,
+        u64,
+        usize
+    );
+    match value.reflect_ref() {
+        ReflectRef::Enum(e) if e.field_len() == 0 => e.variant_name().to_owned(),
+        _ => format!("{value:?}"),
+    }
+}
+
+/// Triggers the [`TextFetch`] component, sends [`TextSegmentUpdated`] for updated segments.
 pub fn text_fetch_system(
-    mut channels: Query<(&mut TextFetch, &mut FetchedTextSegment)>,
-    other: Query<EntityRef, Without<TextFetch>>,
+    world: &mut World,
+    state: &mut SystemState<(Query<(Entity, &TextFetch)>, Query<(Entity, &Text3d)>)>,
+    mut fetchers: Local<Vec<Entity>>,
+    mut updated: Local<Vec<(Entity, String)>>,
+    mut computed: Local<Vec<(Entity, usize, String)>>,
+    mut events: Local<Vec<TextSegmentUpdated>>,
 ) {
-    for (mut channel, mut text) in channels.iter_mut() {
-        if let Ok(entity_ref) = other.get(channel.entity) {
-            if let Some(output) = (channel.fetch)(entity_ref) {
-                text.0 = output;
+    let (_, texts) = state.get(world);
+    for (entity, text) in texts.iter() {
+        for (segment_index, (segment, _)) in text.segments.iter().enumerate() {
+            if let Text3dSegment::Computed(segment) = segment {
+                let value = segment.compute(world);
+                if value != segment.value() {
+                    computed.push((entity, segment_index, value));
+                }
             }
         }
     }
+    for (entity, segment_index, value) in computed.drain(..) {
+        let Some(mut text) = world.get_mut::<Text3d>(entity) else {
+            continue;
+        };
+        if let Some((Text3dSegment::Computed(segment), style)) =
+            text.segments.get_mut(segment_index)
+        {
+            segment.value = value;
+            events.push(TextSegmentUpdated {
+                entity,
+                segment_index,
+                tag: style.tag.clone(),
+            });
+        }
+    }
+    let (channels, _) = state.get(world);
+    fetchers.extend(channels.iter().map(|(entity, _)| entity));
+    for entity in fetchers.drain(..) {
+        let Some(mut channel) = world.get_mut::<TextFetch>(entity) else {
+            continue;
+        };
+        let channel = channel.bypass_change_detection();
+        let target = channel.entity;
+        let fetch = channel.fetch.clone();
+        let mut policy = std::mem::replace(&mut channel.policy, FetchPolicy::EveryFrame);
+        let last_fetch = channel.last_fetch.unwrap_or(world.last_change_tick());
+        // Detect changes since the last fetch instead of the last frame.
+        let output = world.last_change_tick_scope(last_fetch, |world| {
+            if !policy.should_fetch(world, target) {
+                return None;
+            }
+            let output = match (&mut *fetch.lock().ok()?, target) {
+                (FetchFn::Entity(fetch), Some(target)) => match world.get_entity(target) {
+                    Ok(entity_ref) => fetch(entity_ref),
+                    Err(_) => None,
+                },
+                (FetchFn::World(fetch), _) => fetch(world),
+                _ => None,
+            };
+            Some(output)
+        });
+        let this_run = world.read_change_tick();
+        let Some(mut channel) = world.get_mut::<TextFetch>(entity) else {
+            continue;
+        };
+        let channel = channel.bypass_change_detection();
+        channel.policy = policy;
+        if let Some(output) = output {
+            channel.last_fetch = Some(this_run);
+            if let Some(output) = output {
+                updated.push((entity, output));
+            }
+        }
+    }
+    if updated.is_empty() {
+        world.send_event_batch(events.drain(..));
+        return;
+    }
+    for (entity, output) in updated.iter_mut() {
+        if let Some(mut text) = world.get_mut::<FetchedTextSegment>(*entity) {
+            text.0 = std::mem::take(output);
+        }
+    }
+    let (_, texts) = state.get(world);
+    for (entity, text) in texts.iter() {
+        for (segment_index, (segment, style)) in text.segments.iter().enumerate() {
+            if let Text3dSegment::Extract(fetcher) = segment {
+                if updated.iter().any(|(x, _)| x == fetcher) {
+                    events.push(TextSegmentUpdated {
+                        entity,
+                        segment_index,
+                        tag: style.tag.clone(),
+                    });
+                }
+            }
+        }
+    }
+    updated.clear();
+    world.send_event_batch(events.drain(..));
+}
+
+/// Despawns [`TextFetch`] entities whose target entity no longer exists,
+/// unless marked as [`SharedTextSegment`].
+pub fn orphan_fetch_system(
+    mut commands: Commands,
+    entities: &Entities,
+    channels: Query<(Entity, &TextFetch), Without<SharedTextSegment>>,
+) {
+    for (entity, channel) in channels.iter() {
+        if channel
+            .entity
+            .is_some_and(|target| !entities.contains(target))
+        {
+            commands.entity(entity).try_despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        ecs::{component::Component, event::Events, schedule::Schedule, world::World},
+        time::Time,
+    };
+
+    use super::{text_fetch_system, FetchedTextSegment, TextFetch};
+    use crate::{SegmentStyle, Text3d, Text3dSegment, TextSegmentUpdated};
+
+    #[derive(Component)]
+    struct Score(u32);
+
+    #[test]
+    fn interval_fetches_changes_from_skipped_frames() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<TextSegmentUpdated>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(text_fetch_system);
+        let score = world.spawn(Score(0)).id();
+        let fetch = TextFetch::fetch_component::<Score>(score, |score| score.0.to_string())
+            .every_seconds(1.0);
+        let fetchers = [world.spawn(fetch.clone()).id(), world.spawn(fetch).id()];
+        let text = world
+            .spawn(Text3d {
+                segments: vec![(
+                    Text3dSegment::Extract(fetchers[0]),
+                    SegmentStyle {
+                        tag: Some("score".into()),
+                        ..Default::default()
+                    },
+                )],
+            })
+            .id();
+        let fetched = |world: &World| {
+            fetchers.map(|entity| world.get::<FetchedTextSegment>(entity).unwrap().0.clone())
+        };
+        schedule.run(&mut world);
+        assert_eq!(fetched(&world), ["0", "0"]);
+
+        // Changed in a frame skipped by the interval.
+        world.get_mut::<Score>(score).unwrap().0 = 1;
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.5));
+        schedule.run(&mut world);
+        assert_eq!(fetched(&world), ["0", "0"]);
+
+        world.resource_mut::<Events<TextSegmentUpdated>>().clear();
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.6));
+        schedule.run(&mut world);
+        assert_eq!(fetched(&world), ["1", "1"]);
+        let events: Vec<_> = world
+            .resource_mut::<Events<TextSegmentUpdated>>()
+            .drain()
+            .collect();
+        assert_eq!(
+            events,
+            [TextSegmentUpdated {
+                entity: text,
+                segment_index: 0,
+                tag: Some("score".into()),
+            }]
+        );
+    }
+
+    #[cfg(feature = "reflect")]
+    mod reflect {
+        use bevy::{
+            ecs::{component::Component, reflect::AppTypeRegistry, world::World},
+            platform::collections::HashMap,
+            prelude::{Reflect, ReflectComponent},
+        };
+
+        use super::super::reflect_path;
+
+        #[derive(Component, Reflect)]
+        #[reflect(Component)]
+        struct Inventory {
+            items: Vec<Item>,
+            counts: HashMap<String, u32>,
+        }
+
+        #[derive(Reflect)]
+        struct Item {
+            name: String,
+        }
+
+        #[test]
+        fn reflect_path_reads_fields_and_map_keys() {
+            let mut world = World::new();
+            world.init_resource::<AppTypeRegistry>();
+            world
+                .resource::<AppTypeRegistry>()
+                .write()
+                .register::<Inventory>();
+            let entity = world
+                .spawn(Inventory {
+                    items: vec![Item {
+                        name: "Sword".into(),
+                    }],
+                    counts: HashMap::from_iter([("gold".to_owned(), 12)]),
+                })
+                .id();
+            assert_eq!(
+                reflect_path(&world, entity, "Inventory.items[0].name").as_deref(),
+                Some("Sword")
+            );
+            assert_eq!(
+                reflect_path(&world, entity, "Inventory.counts[gold]").as_deref(),
+                Some("12")
+            );
+            assert_eq!(
+                reflect_path(&world, entity, "Inventory.counts[silver]"),
+                None
+            );
+            assert_eq!(reflect_path(&world, entity, "Inventory.missing"), None);
+            // Unchanged components are not fetched again.
+            world.clear_trackers();
+            assert_eq!(
+                reflect_path(&world, entity, "Inventory.items[0].name"),
+                None
+            );
+        }
+    }
 }
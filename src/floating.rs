@@ -0,0 +1,261 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::Handle,
+    color::{Alpha, Srgba},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::Added,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::Vec3,
+    render::mesh::{Mesh, Mesh2d, Mesh3d},
+    time::Time,
+    transform::components::Transform,
+};
+
+use crate::{
+    fetch::text_fetch_system, mesh_util::SharedMeshes, GlyphTransform, Text3d, Text3dBatched,
+    Text3dGlyphAnimation, Text3dSet, Text3dStyling,
+};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+/// Spawn-and-forget text that moves, fades out and despawns itself, like damage numbers.
+///
+/// The [`Text3d`] and [`Text3dStyling`] are inserted when spawned,
+/// fading is done with a [`Text3dGlyphAnimation`] so the material must support transparency.
+/// Meshes of despawned texts are reused by new ones, see [`FloatingTextPool`].
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_rich_text3d::{FloatingText, Text3dStyling};
+/// # fn system(mut commands: Commands, material: Handle<StandardMaterial>, style: Text3dStyling) {
+/// commands.spawn((
+///     FloatingText::new("128!", style).rising(1.5).fade_out(0.8),
+///     MeshMaterial3d(material),
+///     Transform::from_xyz(0.0, 2.0, 0.0),
+/// ));
+/// # }
+/// ```
+///
+/// Requires [`Text3dFloatingPlugin`].
+#[derive(Debug, Clone, Component)]
+#[require(Transform)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct FloatingText {
+    /// Velocity in parent space, in units per second.
+    pub velocity: Vec3,
+    /// Despawns after this many seconds.
+    pub lifetime: f32,
+    /// Fades out over the last this many seconds of `lifetime`.
+    pub fade: f32,
+    /// Seconds since spawned.
+    pub age: f32,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    init: Option<FloatingTextInit>,
+}
+
+/// Components inserted when a [`FloatingText`] is spawned.
+#[derive(Debug, Clone, Default)]
+struct FloatingTextInit {
+    text: String,
+    styling: Text3dStyling,
+    batch: Option<Entity>,
+}
+
+impl FloatingText {
+    /// Text that stays in place for `1` second.
+    pub fn new(text: impl ToString, styling: Text3dStyling) -> Self {
+        FloatingText {
+            velocity: Vec3::ZERO,
+            lifetime: 1.0,
+            fade: 0.0,
+            age: 0.0,
+            init: Some(FloatingTextInit {
+                text: text.to_string(),
+                styling,
+                batch: None,
+            }),
+        }
+    }
+
+    /// Move up at `speed` units per second.
+    pub fn rising(mut self, speed: f32) -> Self {
+        self.velocity = Vec3::Y * speed;
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec3) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    /// Despawn after `seconds`.
+    pub fn with_lifetime(mut self, seconds: f32) -> Self {
+        self.lifetime = seconds;
+        self
+    }
+
+    /// Fade out over `seconds`, extends `lifetime` if shorter.
+    pub fn fade_out(mut self, seconds: f32) -> Self {
+        self.fade = seconds;
+        self.lifetime = self.lifetime.max(seconds);
+        self
+    }
+
+    /// Draw as part of a [`Text3dBatch`](crate::Text3dBatch) instead of with its own material.
+    pub fn in_batch(mut self, batch: Entity) -> Self {
+        if let Some(init) = &mut self.init {
+            init.batch = Some(batch);
+        }
+        self
+    }
+}
+
+/// Meshes of despawned [`FloatingText`]s, reused by newly spawned ones.
+#[derive(Debug, Resource)]
+pub struct FloatingTextPool {
+    meshes: Vec<Handle<Mesh>>,
+    /// Maximum number of meshes kept, defaults to `64`.
+    pub capacity: usize,
+}
+
+impl Default for FloatingTextPool {
+    fn default() -> Self {
+        FloatingTextPool {
+            meshes: Vec::new(),
+            capacity: 64,
+        }
+    }
+}
+
+impl FloatingTextPool {
+    /// Number of meshes available for reuse.
+    pub fn len(&self) -> usize {
+        self.meshes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.meshes.is_empty()
+    }
+
+    /// Drop all pooled meshes.
+    pub fn clear(&mut self) {
+        self.meshes.clear();
+    }
+}
+
+/// Spawns, moves and despawns [`FloatingText`]s.
+///
+/// Add [`Text3dBatchPlugin`](crate::Text3dBatchPlugin) as well to use [`FloatingText::in_batch`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dFloatingPlugin;
+
+impl Plugin for Text3dFloatingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FloatingTextPool>();
+        app.add_systems(
+            PostUpdate,
+            (floating_text_spawn_system, floating_text_system)
+                .chain()
+                .in_set(Text3dSet)
+                .before(text_fetch_system),
+        );
+        #[cfg(feature = "reflect")]
+        app.register_type::<FloatingText>();
+    }
+}
+
+/// Inserts the text and components of newly spawned [`FloatingText`]s.
+pub fn floating_text_spawn_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<FloatingTextPool>,
+    mut query: Query<
+        (
+            Entity,
+            &mut FloatingText,
+            Option<&mut Mesh2d>,
+            Option<&mut Mesh3d>,
+        ),
+        Added<FloatingText>,
+    >,
+) {
+    let now = time.elapsed_secs();
+    for (entity, mut floating, mesh2d, mesh3d) in query.iter_mut() {
+        let Some(init) = floating.init.take() else {
+            continue;
+        };
+        let start = now - floating.age;
+        let (lifetime, fade) = (floating.lifetime, floating.fade);
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((
+            Text3d::new(init.text),
+            init.styling,
+            Text3dGlyphAnimation::new(move |input| {
+                let remaining = start + lifetime - input.time;
+                if fade <= 0.0 || remaining >= fade {
+                    return GlyphTransform::IDENTITY;
+                }
+                GlyphTransform::from_tint(Srgba::WHITE.with_alpha((remaining / fade).max(0.0)))
+            }),
+        ));
+        if let Some(batch) = init.batch {
+            entity_commands.insert(Text3dBatched { batch });
+        }
+        let mesh = pool.meshes.pop();
+        match (mesh2d, mesh3d, mesh) {
+            (Some(mut mesh2d), _, Some(mesh)) => mesh2d.0 = mesh,
+            (_, Some(mut mesh3d), Some(mesh)) => mesh3d.0 = mesh,
+            (Some(_), _, None) | (_, Some(_), None) => (),
+            // A default handle is replaced by a new mesh when the text is drawn.
+            (None, None, mesh) => {
+                entity_commands.insert(Mesh3d(mesh.unwrap_or_default()));
+            }
+        }
+    }
+}
+
+/// Moves [`FloatingText`]s and despawns them at the end of their lifetime.
+pub fn floating_text_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    shared_meshes: Res<SharedMeshes>,
+    mut pool: ResMut<FloatingTextPool>,
+    mut query: Query<(
+        Entity,
+        &mut FloatingText,
+        &mut Transform,
+        Option<&Mesh2d>,
+        Option<&Mesh3d>,
+    )>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut floating, mut transform, mesh2d, mesh3d) in query.iter_mut() {
+        floating.age += dt;
+        if floating.velocity != Vec3::ZERO {
+            transform.translation += floating.velocity * dt;
+        }
+        if floating.age < floating.lifetime {
+            continue;
+        }
+        let mesh = mesh2d.map(|x| &x.0).or_else(|| mesh3d.map(|x| &x.0));
+        if let Some(mesh) = mesh {
+            // Shared meshes are owned by `SharedMeshes`.
+            if pool.meshes.len() < pool.capacity
+                && *mesh != Handle::default()
+                && !shared_meshes.is_shared(mesh.id())
+            {
+                pool.meshes.push(mesh.clone());
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+}
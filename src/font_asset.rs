@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use bevy::{
+    asset::{io::Reader, Asset, AssetEvent, AssetId, AssetLoader, Assets, LoadContext},
+    ecs::{
+        change_detection::DetectChangesMut,
+        event::{Event, EventReader, EventWriter},
+        system::{Local, Res, ResMut},
+    },
+    reflect::TypePath,
+};
+use cosmic_text::fontdb::{Database, Source, ID};
+use rustc_hash::FxHashMap;
+
+use crate::{TextAtlas, TextRenderer};
+
+/// A font file loaded via the [`AssetServer`](bevy::asset::AssetServer),
+/// fonts are added to the [`TextRenderer`] when loaded and removed when dropped.
+///
+/// Text is redrawn when a font is loaded, so text drawn before a font is ready
+/// will be rendered with that font once it is available.
+///
+/// Fonts are selected by family name, not by handle, keep the handle alive to keep the font loaded.
+#[derive(Debug, Clone, Asset, TypePath)]
+pub struct Font3d {
+    /// Content of the font file.
+    pub data: Arc<Vec<u8>>,
+    /// Family names of faces in the font file, can be used as [`Text3dStyling::font`](crate::Text3dStyling::font).
+    pub families: Vec<String>,
+}
+
+impl Font3d {
+    /// Parse a font file, returns `None` if the file contains no valid faces.
+    pub fn new(data: Vec<u8>) -> Option<Self> {
+        let data = Arc::new(data);
+        let mut db = Database::new();
+        db.load_font_source(Source::Binary(data.clone()));
+        let mut families = Vec::new();
+        for face in db.faces() {
+            if let Some((family, _)) = face.families.first() {
+                if !families.contains(family) {
+                    families.push(family.clone());
+                }
+            }
+        }
+        if db.is_empty() {
+            return None;
+        }
+        Some(Font3d { data, families })
+    }
+}
+
+/// Error when loading a [`Font3d`].
+#[derive(Debug, thiserror::Error)]
+pub enum Font3dLoadError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("No valid font faces found.")]
+    InvalidFont,
+}
+
+/// [`AssetLoader`] of [`Font3d`] for `.ttf`, `.otf`, `.ttc` and `.otc` files.
+#[derive(Debug, Default, TypePath)]
+pub struct Font3dLoader;
+
+impl AssetLoader for Font3dLoader {
+    type Asset = Font3d;
+    type Settings = ();
+    type Error = Font3dLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &(),
+        _: &mut LoadContext<'_>,
+    ) -> Result<Font3d, Font3dLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Font3d::new(bytes).ok_or(Font3dLoadError::InvalidFont)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ttf", "otf", "ttc", "otc"]
+    }
+}
+
+/// Apply font asset events to the [`TextRenderer`]'s font database,
+/// events are queued if the font system is used by a background task.
+///
+/// Text is redrawn by [`font_event_system`].
+pub(crate) fn sync_font_assets<A: Asset>(
+    events: &mut EventReader<AssetEvent<A>>,
+    renderer: &TextRenderer,
+    queue: &mut Vec<AssetEvent<A>>,
+    loaded: &mut FxHashMap<AssetId<A>, Vec<ID>>,
+    source: impl Fn(AssetId<A>) -> Option<Source>,
+) {
+    queue.extend(events.read().cloned());
+    if queue.is_empty() {
+        return;
+    }
+    // Retry next frame if the font system is used by a background task.
+    let Ok(mut lock) = renderer.0.try_lock() else {
+        return;
+    };
+    for event in queue.drain(..) {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                lock.remove_fonts(loaded.remove(&id).unwrap_or_default());
+                let Some(source) = source(id) else {
+                    continue;
+                };
+                let faces = lock.add_font(source);
+                loaded.insert(id, faces);
+            }
+            AssetEvent::Removed { id } => {
+                lock.remove_fonts(loaded.remove(&id).unwrap_or_default());
+            }
+            _ => continue,
+        }
+    }
+}
+
+/// Sent when fonts are added or removed from [`TextRenderer`] after startup,
+/// including [`Font3d`] assets being loaded or hot reloaded.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub enum Text3dFontEvent {
+    Added {
+        faces: Vec<ID>,
+        /// Family names of the added faces.
+        families: Vec<String>,
+    },
+    Removed {
+        faces: Vec<ID>,
+    },
+}
+
+/// Sends [`Text3dFontEvent`]s, removes glyphs of removed fonts from atlases and redraws all text.
+pub fn font_event_system(
+    mut renderer: ResMut<TextRenderer>,
+    mut atlases: ResMut<Assets<TextAtlas>>,
+    mut events: EventWriter<Text3dFontEvent>,
+) {
+    let Ok(mut lock) = renderer.0.try_lock() else {
+        return;
+    };
+    if lock.font_events.is_empty() {
+        return;
+    }
+    let font_events = std::mem::take(&mut lock.font_events);
+    drop(lock);
+    for event in &font_events {
+        if let Text3dFontEvent::Removed { faces } = event {
+            for (_, atlas) in atlases.iter_mut() {
+                atlas.glyphs.retain(|entry, _| !faces.contains(&entry.font));
+            }
+        }
+    }
+    events.write_batch(font_events);
+    renderer.set_changed();
+}
+
+/// Loads [`Font3d`]s into [`TextRenderer`].
+pub fn load_font3d_system(
+    mut events: EventReader<AssetEvent<Font3d>>,
+    fonts: Res<Assets<Font3d>>,
+    renderer: Res<TextRenderer>,
+    mut queue: Local<Vec<AssetEvent<Font3d>>>,
+    mut loaded: Local<FxHashMap<AssetId<Font3d>, Vec<ID>>>,
+    mut initialized: Local<bool>,
+) {
+    // Fonts loaded before the font system is ready.
+    if !*initialized {
+        queue.extend(fonts.ids().map(|id| AssetEvent::Added { id }));
+        *initialized = true;
+    }
+    sync_font_assets(&mut events, &renderer, &mut queue, &mut loaded, |id| {
+        Some(Source::Binary(fonts.get(id)?.data.clone()))
+    });
+}
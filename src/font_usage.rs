@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use bevy::ecs::component::Component;
+use cosmic_text::{
+    fontdb::{Source, ID},
+    FontSystem,
+};
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+/// Debug information of fonts that actually rendered each segment of a [`Text3d`](crate::Text3d).
+///
+/// Add this component to a [`Text3d`](crate::Text3d) entity to opt-in,
+/// it is updated every time the text is rendered.
+///
+/// Useful for detecting strings that fall back to an undesired font.
+#[derive(Debug, Component, Default, Clone)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dFontUsage {
+    /// Fonts used by each rendered segment, sorted by segment index.
+    pub segments: Vec<SegmentFontUsage>,
+}
+
+/// Fonts used by a [`Text3dSegment`](crate::Text3dSegment).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct SegmentFontUsage {
+    /// Index of the segment in [`Text3d::segments`](crate::Text3d::segments).
+    pub segment: usize,
+    /// Fonts used by glyphs in this segment, in order of first appearance.
+    pub fonts: Vec<FontUsage>,
+}
+
+/// A font face used for rendering.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct FontUsage {
+    /// Family name of the font.
+    pub family: String,
+    /// PostScript name of the font.
+    pub post_script_name: String,
+    /// Path of the font file, if loaded from a file.
+    pub path: Option<PathBuf>,
+    /// Number of glyphs rendered with this font.
+    pub glyphs: usize,
+}
+
+impl Text3dFontUsage {
+    /// Returns true if any glyph in any segment is rendered with a font not in `families`.
+    pub fn has_fallback(&self, families: &[&str]) -> bool {
+        self.segments
+            .iter()
+            .flat_map(|x| &x.fonts)
+            .any(|x| !families.contains(&x.family.as_str()))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    pub(crate) fn record(&mut self, font_system: &FontSystem, segment: usize, font: ID) {
+        let index = match self.segments.binary_search_by_key(&segment, |x| x.segment) {
+            Ok(index) => index,
+            Err(index) => {
+                self.segments.insert(
+                    index,
+                    SegmentFontUsage {
+                        segment,
+                        fonts: Vec::new(),
+                    },
+                );
+                index
+            }
+        };
+        let Some(face) = font_system.db().face(font) else {
+            return;
+        };
+        let fonts = &mut self.segments[index].fonts;
+        if let Some(usage) = fonts
+            .iter_mut()
+            .find(|x| x.post_script_name == face.post_script_name)
+        {
+            usage.glyphs += 1;
+            return;
+        }
+        fonts.push(FontUsage {
+            family: face
+                .families
+                .first()
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+            post_script_name: face.post_script_name.clone(),
+            path: match &face.source {
+                Source::File(path) => Some(path.clone()),
+                Source::SharedFile(path, _) => Some(path.clone()),
+                _ => None,
+            },
+            glyphs: 1,
+        });
+    }
+}
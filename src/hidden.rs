@@ -0,0 +1,153 @@
+use bevy::{
+    asset::Handle,
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::{Component, HookContext},
+        entity::Entity,
+        system::{Commands, Query, Res},
+        world::{DeferredWorld, Ref},
+    },
+    math::IVec2,
+    render::{
+        mesh::{Mesh2d, Mesh3d},
+        view::{InheritedVisibility, ViewVisibility},
+    },
+};
+
+use crate::{
+    FetchedTextSegment, Text3d, Text3dBounds, Text3dDimensionOut, Text3dPath, Text3dSegment,
+    Text3dStyling, TextRenderer,
+};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent, ReflectDefault};
+
+/// Skips layout and mesh updates of a [`Text3d`] while present,
+/// use with [`Visibility::Hidden`](bevy::render::view::Visibility::Hidden) to toggle text cheaply.
+///
+/// Also inserted automatically on hidden or culled text,
+/// see [`Text3dStyling::update_when_hidden`].
+///
+/// When removed, the text is only redrawn if it changed while hidden.
+#[derive(Debug, Clone, Copy, Component, Default)]
+#[component(on_remove = text_3d_hidden_on_remove)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dHidden {
+    /// If true, removes the mesh while hidden to save memory, the text is redrawn when shown.
+    pub clear_mesh: bool,
+    /// Changed while hidden.
+    dirty: bool,
+    /// Inserted by [`visibility_defer_system`].
+    automatic: bool,
+}
+
+impl Text3dHidden {
+    /// Hide and keep the mesh.
+    pub const fn new() -> Self {
+        Text3dHidden {
+            clear_mesh: false,
+            dirty: false,
+            automatic: false,
+        }
+    }
+
+    /// Hide and remove the mesh.
+    pub const fn clear_mesh() -> Self {
+        Text3dHidden {
+            clear_mesh: true,
+            dirty: false,
+            automatic: false,
+        }
+    }
+}
+
+fn text_3d_hidden_on_remove(mut world: DeferredWorld, cx: HookContext) {
+    if !world
+        .get::<Text3dHidden>(cx.entity)
+        .is_some_and(|hidden| hidden.dirty)
+    {
+        return;
+    }
+    if let Some(mut text) = world.get_mut::<Text3d>(cx.entity) {
+        text.set_changed();
+    }
+}
+
+/// Clears meshes of newly hidden text and records changes made while hidden.
+pub fn hidden_text_system(
+    renderer: Option<Res<TextRenderer>>,
+    mut query: Query<(
+        &mut Text3dHidden,
+        Option<&mut Mesh2d>,
+        Option<&mut Mesh3d>,
+        Ref<Text3d>,
+        Ref<Text3dBounds>,
+        Ref<Text3dStyling>,
+        Option<Ref<Text3dPath>>,
+    )>,
+    segments: Query<Ref<FetchedTextSegment>>,
+) {
+    let redraw = renderer.is_some_and(|x| x.is_changed());
+    for (mut hidden, mesh2d, mesh3d, text, bounds, styling, path) in query.iter_mut() {
+        if hidden.dirty {
+            continue;
+        }
+        if hidden.is_added() && hidden.clear_mesh {
+            if let Some(mut mesh2d) = mesh2d {
+                mesh2d.0 = Handle::default();
+            }
+            if let Some(mut mesh3d) = mesh3d {
+                mesh3d.0 = Handle::default();
+            }
+            hidden.dirty = true;
+            continue;
+        }
+        let changed = redraw
+            || text.is_changed()
+            || bounds.is_changed()
+            || styling.is_changed()
+            || path.is_some_and(|x| x.is_changed())
+            || text.segments.iter().any(|(segment, _)| match segment {
+                Text3dSegment::Extract(entity) => {
+                    segments.get(*entity).is_ok_and(|x| x.is_changed())
+                }
+                _ => false,
+            });
+        if changed {
+            hidden.dirty = true;
+        }
+    }
+}
+
+/// Defers layout of text that is hidden or was culled last frame
+/// by inserting and removing [`Text3dHidden`].
+pub fn visibility_defer_system(
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        &InheritedVisibility,
+        &ViewVisibility,
+        &Text3dStyling,
+        &Text3dDimensionOut,
+        Option<&Text3dHidden>,
+    )>,
+) {
+    for (entity, inherited, view, styling, output, hidden) in query.iter() {
+        // Text that has never been drawn has no bounding box and may be considered culled.
+        let drawn = output.atlas_dimension != IVec2::ZERO;
+        let visible = styling.update_when_hidden || (inherited.get() && (view.get() || !drawn));
+        match hidden {
+            None if !visible => {
+                commands.entity(entity).insert(Text3dHidden {
+                    automatic: true,
+                    ..Text3dHidden::new()
+                });
+            }
+            Some(hidden) if visible && hidden.automatic => {
+                commands.entity(entity).remove::<Text3dHidden>();
+            }
+            _ => (),
+        }
+    }
+}
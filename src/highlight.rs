@@ -0,0 +1,83 @@
+use bevy::{
+    asset::{Assets, Handle},
+    color::Srgba,
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
+        system::{Query, ResMut},
+        world::Ref,
+    },
+    math::{Rect, Vec2},
+    render::mesh::Mesh,
+};
+
+use crate::{Text3dLayoutInfo, Text3dMeshBuilder, Text3dWord};
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+/// Finds the word under a point in local space and generates a quad [`Mesh`] covering it,
+/// a building block for dictionary popups and link hover states.
+///
+/// The mesh is in the text's local space, spawn it as a child of the text entity with a material of your choice.
+#[derive(Debug, Clone, Component, Default)]
+#[require(Text3dLayoutInfo)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dWordHighlight {
+    /// Point in local space to test, usually the cursor projected onto the text's plane.
+    pub position: Option<Vec2>,
+    /// The word under [`Text3dWordHighlight::position`], updated after text is rendered.
+    pub word: Option<Text3dWord>,
+    /// Quad covering [`Text3dWordHighlight::word`], created if left as default.
+    pub mesh: Handle<Mesh>,
+}
+
+impl Text3dWordHighlight {
+    /// Create with an existing mesh handle, the mesh will be overwritten.
+    pub fn with_mesh(mesh: Handle<Mesh>) -> Self {
+        Self {
+            mesh,
+            ..Default::default()
+        }
+    }
+}
+
+/// Updates [`Text3dWordHighlight`] from [`Text3dLayoutInfo`].
+pub fn word_highlight_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&mut Text3dWordHighlight, Ref<Text3dLayoutInfo>)>,
+) {
+    for (mut highlight, layout) in query.iter_mut() {
+        if !highlight.is_changed() && !layout.is_changed() {
+            continue;
+        }
+        let word = highlight
+            .position
+            .and_then(|position| layout.word_at(position));
+        let created = highlight.mesh == Handle::default();
+        if !created && highlight.word == word && meshes.contains(highlight.mesh.id()) {
+            continue;
+        }
+        let mut builder = Text3dMeshBuilder::new();
+        if let Some(word) = &word {
+            builder.quad(
+                word.rect,
+                Rect::new(0.0, 0.0, 1.0, 1.0),
+                Srgba::WHITE,
+                0,
+                [Vec2::ZERO; 4],
+            );
+        }
+        let mesh = builder.build(Vec2::ONE);
+        if created {
+            highlight.mesh = meshes.add(mesh);
+        } else {
+            meshes.insert(highlight.mesh.id(), mesh);
+        }
+        highlight.bypass_change_detection().word = word;
+    }
+}
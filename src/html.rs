@@ -0,0 +1,232 @@
+use std::str::FromStr;
+
+use crate::{
+    color_table::parse_color,
+    misc::{Style, Weight},
+    SegmentStyle, Text3d, Text3dSegment,
+};
+
+/// Error emitted when parsing html, see [`Text3d::parse_html`].
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{kind} (at byte {position})")]
+pub struct HtmlParseError {
+    /// Byte offset of the tag or entity that caused the error.
+    pub position: usize,
+    pub kind: HtmlErrorKind,
+}
+
+/// Kind of [`HtmlParseError`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum HtmlErrorKind {
+    #[error("Tag is not closed by `>`.")]
+    UnterminatedTag,
+    #[error("Tag <{0}> is not supported.")]
+    UnsupportedTag(String),
+    #[error("Tag <{0}> is never closed.")]
+    UnclosedTag(String),
+    #[error("Closing tag </{0}> does not match any open tag.")]
+    MismatchedTag(String),
+    #[error("Bad attribute: {0}")]
+    BadAttribute(String),
+    #[error("Bad entity: &{0};")]
+    BadEntity(String),
+}
+
+impl Text3d {
+    /// Parse a safe subset of html, for porting rich text written for the web or other engines.
+    ///
+    /// # Supported tags
+    ///
+    /// * `<b>`, `<strong>`: bold.
+    /// * `<i>`, `<em>`: italic.
+    /// * `<u>`: underline.
+    /// * `<s>`, `<del>`: strikethrough.
+    /// * `<br>`: line break.
+    /// * `<sub>`, `<sup>`: drawn at `0.7` times the current size, on the same baseline
+    ///   since segments cannot be offset vertically.
+    /// * `<span style="..">` with the css properties `color`, `font-family`, `font-size` in `px`,
+    ///   `font-weight`, `font-style` and `text-decoration`.
+    ///
+    /// Entities `&lt;`, `&gt;`, `&amp;`, `&quot;`, `&apos;`, `&nbsp;` and numeric entities are decoded.
+    /// Other tags and attributes are errors, nested styles are joined like [`Text3d::parse`].
+    ///
+    /// `size` should match [`Text3dStyling::size`](crate::Text3dStyling::size), as the base of `<sub>` and `<sup>`.
+    pub fn parse_html(text: &str, size: f32) -> Result<Self, HtmlParseError> {
+        let mut segments = Vec::new();
+        let mut buffer = String::new();
+        // Open tags with their position and joined style.
+        let mut stack: Vec<(String, usize, SegmentStyle)> = Vec::new();
+        let base = SegmentStyle::default();
+        let mut i = 0;
+        while let Some(c) = text[i..].chars().next() {
+            let error = move |kind| HtmlParseError { position: i, kind };
+            let current = stack.last().map(|(_, _, style)| style).unwrap_or(&base);
+            match c {
+                '<' => {
+                    let end = text[i..]
+                        .find('>')
+                        .ok_or_else(|| error(HtmlErrorKind::UnterminatedTag))?;
+                    let tag = text[i + 1..i + end].trim();
+                    if let Some(name) = tag.strip_prefix('/') {
+                        let name = name.trim().to_ascii_lowercase();
+                        match stack.last() {
+                            Some((open, _, _)) if *open == name => (),
+                            _ => return Err(error(HtmlErrorKind::MismatchedTag(name))),
+                        }
+                        push_html_segment(&mut buffer, &mut segments, current);
+                        stack.pop();
+                    } else {
+                        let tag = tag.trim_end_matches('/').trim_end();
+                        let (name, attributes) =
+                            tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+                        let name = name.to_ascii_lowercase();
+                        if name == "br" {
+                            buffer.push('\n');
+                        } else {
+                            let style = html_style(&name, attributes.trim(), current, size)
+                                .map_err(error)?;
+                            push_html_segment(&mut buffer, &mut segments, current);
+                            let style = current.join(style);
+                            stack.push((name, i, style));
+                        }
+                    }
+                    i += end + 1;
+                }
+                '&' => {
+                    let end = text[i..]
+                        .find(';')
+                        .filter(|end| *end <= 10)
+                        .ok_or_else(|| error(HtmlErrorKind::BadEntity(String::new())))?;
+                    let entity = &text[i + 1..i + end];
+                    let decoded = decode_entity(entity)
+                        .ok_or_else(|| error(HtmlErrorKind::BadEntity(entity.into())))?;
+                    buffer.push(decoded);
+                    i += end + 1;
+                }
+                c => {
+                    buffer.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+        if let Some((name, position, _)) = stack.pop() {
+            return Err(HtmlParseError {
+                position,
+                kind: HtmlErrorKind::UnclosedTag(name),
+            });
+        }
+        push_html_segment(&mut buffer, &mut segments, &base);
+        Ok(Text3d { segments })
+    }
+}
+
+fn push_html_segment(
+    buffer: &mut String,
+    segments: &mut Vec<(Text3dSegment, SegmentStyle)>,
+    style: &SegmentStyle,
+) {
+    if !buffer.is_empty() {
+        segments.push((Text3dSegment::String(std::mem::take(buffer)), style.clone()));
+    }
+}
+
+/// Style of an opening tag, joined with the style of its parent.
+fn html_style(
+    name: &str,
+    attributes: &str,
+    parent: &SegmentStyle,
+    size: f32,
+) -> Result<SegmentStyle, HtmlErrorKind> {
+    if !attributes.is_empty() && name != "span" {
+        return Err(HtmlErrorKind::BadAttribute(attributes.into()));
+    }
+    let mut style = SegmentStyle::default();
+    match name {
+        "b" | "strong" => style.weight = Some(Weight::BOLD),
+        "i" | "em" => style.style = Some(Style::Italic),
+        "u" => style.underline = Some(true),
+        "s" | "del" => style.strikethrough = Some(true),
+        "sub" | "sup" => style.size = Some(parent.size.unwrap_or(size) * 0.7),
+        "span" => {
+            if attributes.is_empty() {
+                return Ok(style);
+            }
+            let css = attributes
+                .strip_prefix("style")
+                .map(str::trim_start)
+                .and_then(|x| x.strip_prefix('='))
+                .map(str::trim)
+                .and_then(|x| {
+                    x.strip_prefix('"')
+                        .and_then(|x| x.strip_suffix('"'))
+                        .or_else(|| x.strip_prefix('\'').and_then(|x| x.strip_suffix('\'')))
+                })
+                .ok_or_else(|| HtmlErrorKind::BadAttribute(attributes.into()))?;
+            for declaration in css.split(';').map(str::trim).filter(|x| !x.is_empty()) {
+                let bad = || HtmlErrorKind::BadAttribute(declaration.into());
+                let (property, value) = declaration.split_once(':').ok_or_else(bad)?;
+                let value = value.trim();
+                match property.trim() {
+                    "color" => style.fill_color = Some(parse_color(value).ok_or_else(bad)?),
+                    "font-family" => {
+                        style.font = Some(value.trim_matches(['"', '\'']).into());
+                    }
+                    "font-size" => {
+                        let px = value.strip_suffix("px").unwrap_or(value);
+                        style.size = Some(f32::from_str(px.trim()).map_err(|_| bad())?);
+                    }
+                    "font-weight" => {
+                        style.weight = Some(match value {
+                            "normal" => Weight::NORMAL,
+                            "bold" => Weight::BOLD,
+                            _ => Weight(u16::from_str(value).map_err(|_| bad())?),
+                        })
+                    }
+                    "font-style" => {
+                        style.style = Some(match value {
+                            "normal" => Style::Normal,
+                            "italic" => Style::Italic,
+                            "oblique" => Style::Oblique,
+                            _ => return Err(bad()),
+                        })
+                    }
+                    "text-decoration" => {
+                        for decoration in value.split_whitespace() {
+                            match decoration {
+                                "underline" => style.underline = Some(true),
+                                "line-through" => style.strikethrough = Some(true),
+                                "none" => {
+                                    style.underline = Some(false);
+                                    style.strikethrough = Some(false);
+                                }
+                                _ => return Err(bad()),
+                            }
+                        }
+                    }
+                    _ => return Err(bad()),
+                }
+            }
+        }
+        _ => return Err(HtmlErrorKind::UnsupportedTag(name.into())),
+    }
+    Ok(style)
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    Some(match entity {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => u32::from_str(number).ok()?,
+            };
+            char::from_u32(code)?
+        }
+    })
+}
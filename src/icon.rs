@@ -0,0 +1,152 @@
+use bevy::{
+    asset::{AssetId, Assets, Handle},
+    color::ColorToPacked,
+    ecs::resource::Resource,
+    image::Image,
+    math::{FloatOrd, IVec2, Rect, Vec2},
+    platform::collections::HashMap,
+};
+use cosmic_text::fontdb::ID;
+
+use crate::{
+    styling::{GlyphEntry, GlyphTextureOf},
+    StrokeJoin, TextAtlas, Weight,
+};
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectResource,
+    prelude::{Reflect, ReflectDefault},
+};
+
+/// Text inserted in place of an inline image, shaped as a zero width glyph.
+pub(crate) const ICON_PLACEHOLDER: &str = "\u{2060}";
+
+/// An image drawn inline with text via `{image:name}`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct Text3dIcon {
+    /// Image of the icon, copied into the [`TextAtlas`] at its original resolution.
+    ///
+    /// Must be smaller than the atlas' width and readable on the CPU.
+    pub image: Handle<Image>,
+    /// Size of the icon in `em`, i.e. multiples of the segment's font size.
+    ///
+    /// Icons taller than the line height may overlap other lines.
+    pub size: Vec2,
+    /// Offset from the baseline to the bottom of the icon in `em`.
+    pub baseline: f32,
+}
+
+impl Text3dIcon {
+    /// Create an icon sitting on the baseline.
+    pub fn new(image: Handle<Image>, size: Vec2) -> Self {
+        Self {
+            image,
+            size,
+            baseline: 0.0,
+        }
+    }
+
+    /// Set the offset from the baseline, negative values move the icon down.
+    pub fn with_baseline(mut self, baseline: f32) -> Self {
+        self.baseline = baseline;
+        self
+    }
+}
+
+/// Named icons that can be referenced by [`Text3dSegment::Image`](crate::Text3dSegment::Image).
+///
+/// Text is redrawn when this resource changes or when an icon's image is loaded.
+#[derive(Debug, Clone, Default, Resource)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource, Default))]
+pub struct Text3dIcons {
+    pub icons: HashMap<String, Text3dIcon>,
+}
+
+impl Text3dIcons {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a named icon.
+    pub fn with_icon(mut self, name: impl Into<String>, icon: Text3dIcon) -> Self {
+        self.icons.insert(name.into(), icon);
+        self
+    }
+
+    /// Add or replace a named icon.
+    pub fn insert(&mut self, name: impl Into<String>, icon: Text3dIcon) {
+        self.icons.insert(name.into(), icon);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Text3dIcon> {
+        self.icons.get(name)
+    }
+}
+
+pub(crate) fn icon_entry(image: AssetId<Image>) -> GlyphEntry {
+    GlyphEntry {
+        font: ID::dummy(),
+        glyph_id: GlyphTextureOf::Icon(image),
+        join: StrokeJoin::default(),
+        size: FloatOrd(0.0),
+        weight: Weight::NORMAL,
+        stroke: None,
+        padding: 0,
+        scale_factor: FloatOrd(1.0),
+        variations: 0,
+        outline: 0,
+    }
+}
+
+/// Copy an icon's image into the atlas if not already cached.
+pub(crate) fn cache_icon(atlas: &mut TextAtlas, images: &mut Assets<Image>, icon: AssetId<Image>) {
+    let entry = icon_entry(icon);
+    if atlas.glyphs.contains_key(&entry) {
+        return;
+    }
+    let Some(source) = images.get(icon) else {
+        return;
+    };
+    let (width, height) = (source.width() as usize, source.height() as usize);
+    // The atlas is linear, convert from the source format.
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            pixels.push(
+                source
+                    .get_color_at(x as u32, y as u32)
+                    .map(|color| color.to_linear().to_u8_array())
+                    .unwrap_or([255, 255, 255, 0]),
+            );
+        }
+    }
+    let Some(image) = images.get_mut(atlas.image.id()) else {
+        return;
+    };
+    cache_pixels(atlas, image, entry, width, height, &pixels);
+}
+
+/// Copy linear RGBA pixels in row major order into the atlas.
+pub(crate) fn cache_pixels(
+    atlas: &mut TextAtlas,
+    image: &mut Image,
+    entry: GlyphEntry,
+    width: usize,
+    height: usize,
+    pixels: &[[u8; 4]],
+) -> Rect {
+    atlas.cache(image, entry, Vec2::ZERO, width, height, |data, stride| {
+        for (y, row) in pixels.chunks(width).take(height).enumerate() {
+            data[y * stride..y * stride + row.len() * 4].copy_from_slice(row.as_flattened());
+        }
+        IVec2::new(width as i32, height as i32)
+    })
+}
+
+/// Remove a modified icon from the atlas so it is copied again.
+pub(crate) fn invalidate_icon(atlas: &mut TextAtlas, icon: AssetId<Image>) {
+    atlas.glyphs.remove(&icon_entry(icon));
+}
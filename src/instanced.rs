@@ -0,0 +1,511 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::{load_internal_asset, weak_handle, AssetId, Assets, Handle, RenderAssetUsages},
+    color::{ColorToComponents, LinearRgba},
+    core_pipeline::core_3d::Transparent3d,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        query::{QueryItem, ROQueryItem, With},
+        removal_detection::RemovedComponents,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{
+            lifetimeless::{Read, SRes},
+            Commands, Local, Query, Res, ResMut, SystemParamItem,
+        },
+        world::{FromWorld, Ref, World},
+    },
+    image::Image,
+    math::{Affine3A, Mat4, Vec3, Vec4},
+    pbr::{
+        MeshPipeline, MeshPipelineKey, RenderMeshInstances, SetMeshBindGroup, SetMeshViewBindGroup,
+    },
+    render::{
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        mesh::{
+            allocator::MeshAllocator, Indices, Mesh, Mesh3d, MeshVertexBufferLayoutRef,
+            PrimitiveTopology, RenderMesh, RenderMeshBufferInfo,
+        },
+        render_asset::RenderAssets,
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand,
+            RenderCommandResult, SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d},
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer,
+            BufferInitDescriptor, BufferUsages, PipelineCache, RenderPipelineDescriptor,
+            SamplerBindingType, Shader, ShaderStages, SpecializedMeshPipeline,
+            SpecializedMeshPipelineError, SpecializedMeshPipelines, TextureSampleType,
+            VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode,
+        },
+        renderer::RenderDevice,
+        sync_world::MainEntity,
+        texture::GpuImage,
+        view::{ExtractedView, InheritedVisibility, Msaa, NoFrustumCulling},
+        Render, RenderApp, RenderSet,
+    },
+    transform::components::{GlobalTransform, Transform},
+};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    render::text_render, Text3d, Text3dLayoutInfo, Text3dSet, Text3dStyling, TextAtlas,
+    TextAtlasHandle,
+};
+
+const INSTANCED_SHADER: Handle<Shader> = weak_handle!("e4b1d7a2-93c6-4f58-8a0d-2c5f7e91b364");
+
+/// Renders this text's glyphs with [`Text3dInstancedPlugin`]
+/// instead of its own mesh.
+///
+/// The entity still needs a [`Mesh3d`] for layout, but should not have a material.
+#[derive(Debug, Clone, Copy, Default, Component)]
+#[require(Text3dLayoutInfo)]
+pub struct Text3dInstanced;
+
+/// Renders all [`Text3dInstanced`] text with a single instanced draw per [`TextAtlas`],
+/// for large numbers of labels where one mesh per entity does not scale.
+///
+/// Each glyph's fill quad is uploaded as an instance with its transform, atlas rectangle,
+/// color and metadata. Strokes, shadows, decoration lines, custom drawers and
+/// texture array atlases are not supported. Glyphs are drawn unlit in the [`Transparent3d`] phase,
+/// sorted per atlas instead of per glyph.
+///
+/// Requires the `3d` feature.
+#[derive(Debug, Default)]
+pub struct Text3dInstancedPlugin;
+
+impl Plugin for Text3dInstancedPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, INSTANCED_SHADER, "instanced.wgsl", Shader::from_wgsl);
+        app.add_plugins(ExtractComponentPlugin::<GlyphInstances>::default());
+        app.add_systems(
+            PostUpdate,
+            glyph_instance_system.in_set(Text3dSet).after(text_render),
+        );
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_command::<Transparent3d, DrawGlyphInstances>()
+            .init_resource::<SpecializedMeshPipelines<InstancedPipeline>>()
+            .add_systems(
+                Render,
+                (
+                    queue_glyph_instances.in_set(RenderSet::QueueMeshes),
+                    prepare_glyph_instances.in_set(RenderSet::PrepareResources),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<InstancedPipeline>();
+    }
+}
+
+/// Size of a [`GlyphInstance`] in the instance buffer.
+const INSTANCE_SIZE: usize = 6 * 4 * size_of::<f32>();
+
+/// Per glyph data of the instance buffer.
+#[derive(Debug, Clone, Copy)]
+struct GlyphInstance {
+    /// Rows of the affine transform of the unit quad.
+    transform: [Vec4; 3],
+    /// Rectangle in the atlas in pixels, `min` then `max`.
+    atlas_rect: Vec4,
+    color: LinearRgba,
+    /// Index, segment and line of the glyph.
+    meta: Vec4,
+}
+
+impl GlyphInstance {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        let [x, y, z] = self.transform;
+        for v in [x, y, z, self.atlas_rect, self.color.to_vec4(), self.meta] {
+            for f in v.to_array() {
+                bytes.extend_from_slice(&f.to_ne_bytes());
+            }
+        }
+    }
+}
+
+/// Glyphs of all instanced text using an atlas, on a batch entity with a unit quad mesh.
+#[derive(Debug, Clone, Component, Default)]
+struct GlyphInstances {
+    image: AssetId<Image>,
+    instances: Vec<GlyphInstance>,
+}
+
+impl ExtractComponent for GlyphInstances {
+    type QueryData = &'static GlyphInstances;
+    type QueryFilter = ();
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<'_, Self::QueryData>) -> Option<Self> {
+        Some(item.clone())
+    }
+}
+
+fn unit_quad() -> Mesh {
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ],
+    )
+    .with_inserted_indices(Indices::U16(vec![0, 1, 2, 1, 3, 2]))
+}
+
+/// Collects glyphs of [`Text3dInstanced`] text into one batch entity per atlas.
+fn glyph_instance_system(
+    mut commands: Commands,
+    mut batches: Local<FxHashMap<AssetId<TextAtlas>, Entity>>,
+    mut quad: Local<Handle<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    atlases: Res<Assets<TextAtlas>>,
+    texts: Query<
+        (
+            Ref<Text3dLayoutInfo>,
+            Ref<GlobalTransform>,
+            &Text3d,
+            &Text3dStyling,
+            &TextAtlasHandle,
+            &InheritedVisibility,
+        ),
+        With<Text3dInstanced>,
+    >,
+    mut removed: RemovedComponents<Text3dInstanced>,
+    mut instances: Query<&mut GlyphInstances>,
+) {
+    let removed = removed.read().count() > 0;
+    if !removed
+        && !atlases.is_changed()
+        && !texts
+            .iter()
+            .any(|(info, transform, ..)| info.is_changed() || transform.is_changed())
+    {
+        return;
+    }
+    if *quad == Handle::default() {
+        *quad = meshes.add(unit_quad());
+    }
+    let mut collected: FxHashMap<AssetId<TextAtlas>, Vec<GlyphInstance>> = FxHashMap::default();
+    for (info, transform, text, styling, atlas, visibility) in &texts {
+        if !visibility.get() {
+            continue;
+        }
+        let instances = collected.entry(atlas.0.id()).or_default();
+        let affine = transform.affine();
+        for glyph in &info.glyphs {
+            let (Some(quad), Some(atlas_rect)) = (glyph.quad, glyph.atlas_rect) else {
+                continue;
+            };
+            let local = Affine3A::from_scale_rotation_translation(
+                quad.size().extend(1.0),
+                Default::default(),
+                quad.min.extend(0.0),
+            );
+            let matrix = Mat4::from(affine * local);
+            let color = text
+                .segments
+                .get(glyph.segment)
+                .and_then(|(_, style)| style.fill_color)
+                .unwrap_or(styling.color);
+            instances.push(GlyphInstance {
+                transform: [matrix.row(0), matrix.row(1), matrix.row(2)],
+                atlas_rect: Vec4::new(
+                    atlas_rect.min.x,
+                    atlas_rect.min.y,
+                    atlas_rect.max.x,
+                    atlas_rect.max.y,
+                ),
+                color: color.into(),
+                meta: Vec3::new(glyph.index as f32, glyph.segment as f32, glyph.line as f32)
+                    .extend(0.0),
+            });
+        }
+    }
+    // Batches of atlases no longer used are emptied.
+    for (id, entity) in batches.iter() {
+        if !collected.contains_key(id) {
+            if let Ok(mut batch) = instances.get_mut(*entity) {
+                batch.instances.clear();
+            }
+        }
+    }
+    for (id, glyphs) in collected {
+        let image = atlases
+            .get(id)
+            .map(|atlas| atlas.image.id())
+            .unwrap_or_default();
+        match batches.get(&id).and_then(|e| instances.get_mut(*e).ok()) {
+            Some(mut batch) => {
+                batch.image = image;
+                batch.instances = glyphs;
+            }
+            None => {
+                let entity = commands
+                    .spawn((
+                        GlyphInstances {
+                            image,
+                            instances: glyphs,
+                        },
+                        Mesh3d(quad.clone()),
+                        Transform::default(),
+                        NoFrustumCulling,
+                    ))
+                    .id();
+                batches.insert(id, entity);
+            }
+        }
+    }
+}
+
+/// Instance buffer and atlas bind group of a batch in the render world.
+#[derive(Component)]
+struct PreparedGlyphInstances {
+    buffer: Buffer,
+    length: usize,
+    bind_group: BindGroup,
+}
+
+fn prepare_glyph_instances(
+    mut commands: Commands,
+    query: Query<(Entity, &GlyphInstances)>,
+    render_device: Res<RenderDevice>,
+    images: Res<RenderAssets<GpuImage>>,
+    pipeline: Res<InstancedPipeline>,
+    mut bytes: Local<Vec<u8>>,
+) {
+    for (entity, glyphs) in &query {
+        let Some(image) = images.get(glyphs.image) else {
+            commands.entity(entity).remove::<PreparedGlyphInstances>();
+            continue;
+        };
+        bytes.clear();
+        bytes.reserve(glyphs.instances.len() * INSTANCE_SIZE);
+        for instance in &glyphs.instances {
+            instance.write(&mut bytes);
+        }
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("text3d_glyph_instances"),
+            contents: &bytes,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        });
+        let bind_group = render_device.create_bind_group(
+            "text3d_instanced_atlas",
+            &pipeline.atlas_layout,
+            &BindGroupEntries::sequential((&image.texture_view, &image.sampler)),
+        );
+        commands.entity(entity).insert(PreparedGlyphInstances {
+            buffer,
+            length: glyphs.instances.len(),
+            bind_group,
+        });
+    }
+}
+
+fn queue_glyph_instances(
+    draw_functions: Res<DrawFunctions<Transparent3d>>,
+    pipeline: Res<InstancedPipeline>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancedPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<RenderMesh>>,
+    render_mesh_instances: Res<RenderMeshInstances>,
+    batches: Query<(Entity, &MainEntity), With<GlyphInstances>>,
+    mut phases: ResMut<ViewSortedRenderPhases<Transparent3d>>,
+    views: Query<(&ExtractedView, &Msaa)>,
+) {
+    let draw_function = draw_functions.read().id::<DrawGlyphInstances>();
+    for (view, msaa) in &views {
+        let Some(phase) = phases.get_mut(&view.retained_view_entity) else {
+            continue;
+        };
+        let view_key = MeshPipelineKey::from_msaa_samples(msaa.samples())
+            | MeshPipelineKey::from_hdr(view.hdr)
+            | MeshPipelineKey::BLEND_ALPHA;
+        let rangefinder = view.rangefinder3d();
+        for (entity, main_entity) in &batches {
+            let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(*main_entity)
+            else {
+                continue;
+            };
+            let Some(mesh) = meshes.get(mesh_instance.mesh_asset_id) else {
+                continue;
+            };
+            let key =
+                view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology());
+            let Ok(pipeline) = pipelines.specialize(&pipeline_cache, &pipeline, key, &mesh.layout)
+            else {
+                continue;
+            };
+            phase.add(Transparent3d {
+                entity: (entity, *main_entity),
+                pipeline,
+                draw_function,
+                distance: rangefinder.distance_translation(&mesh_instance.translation),
+                batch_range: 0..1,
+                extra_index: PhaseItemExtraIndex::None,
+                indexed: true,
+            });
+        }
+    }
+}
+
+#[derive(Resource)]
+struct InstancedPipeline {
+    mesh_pipeline: MeshPipeline,
+    atlas_layout: BindGroupLayout,
+}
+
+impl FromWorld for InstancedPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let atlas_layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "text3d_instanced_atlas_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::VERTEX_FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                ),
+            ),
+        );
+        InstancedPipeline {
+            mesh_pipeline: world.resource::<MeshPipeline>().clone(),
+            atlas_layout,
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for InstancedPipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+        descriptor.label = Some("text3d_instanced_pipeline".into());
+        descriptor.layout.push(self.atlas_layout.clone());
+        descriptor.vertex.shader = INSTANCED_SHADER;
+        let vec4 = VertexFormat::Float32x4.size();
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: INSTANCE_SIZE as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: (0..6)
+                .map(|i| VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: i * vec4,
+                    shader_location: 3 + i as u32,
+                })
+                .collect(),
+        });
+        if let Some(fragment) = &mut descriptor.fragment {
+            fragment.shader = INSTANCED_SHADER;
+        }
+        Ok(descriptor)
+    }
+}
+
+type DrawGlyphInstances = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    SetAtlasBindGroup<2>,
+    DrawInstanced,
+);
+
+struct SetAtlasBindGroup<const I: usize>;
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetAtlasBindGroup<I> {
+    type Param = ();
+    type ViewQuery = ();
+    type ItemQuery = Read<PreparedGlyphInstances>;
+
+    fn render<'w>(
+        _: &P,
+        _: ROQueryItem<'w, Self::ViewQuery>,
+        prepared: Option<ROQueryItem<'w, Self::ItemQuery>>,
+        _: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(prepared) = prepared else {
+            return RenderCommandResult::Skip;
+        };
+        pass.set_bind_group(I, &prepared.bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+struct DrawInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawInstanced {
+    type Param = (
+        SRes<RenderAssets<RenderMesh>>,
+        SRes<RenderMeshInstances>,
+        SRes<MeshAllocator>,
+    );
+    type ViewQuery = ();
+    type ItemQuery = Read<PreparedGlyphInstances>;
+
+    fn render<'w>(
+        item: &P,
+        _: ROQueryItem<'w, Self::ViewQuery>,
+        prepared: Option<ROQueryItem<'w, Self::ItemQuery>>,
+        (meshes, render_mesh_instances, mesh_allocator): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let mesh_allocator = mesh_allocator.into_inner();
+        let Some(prepared) = prepared.filter(|x| x.length > 0) else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(item.main_entity())
+        else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(vertices) = mesh_allocator.mesh_vertex_slice(&mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Skip;
+        };
+        pass.set_vertex_buffer(0, vertices.buffer.slice(..));
+        pass.set_vertex_buffer(1, prepared.buffer.slice(..));
+        let instances = 0..prepared.length as u32;
+        match &gpu_mesh.buffer_info {
+            RenderMeshBufferInfo::Indexed {
+                index_format,
+                count,
+            } => {
+                let Some(indices) = mesh_allocator.mesh_index_slice(&mesh_instance.mesh_asset_id)
+                else {
+                    return RenderCommandResult::Skip;
+                };
+                pass.set_index_buffer(indices.buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(
+                    indices.range.start..(indices.range.start + count),
+                    vertices.range.start as i32,
+                    instances,
+                );
+            }
+            RenderMeshBufferInfo::NonIndexed => {
+                pass.draw(vertices.range, instances);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
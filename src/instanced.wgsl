@@ -0,0 +1,44 @@
+#import bevy_pbr::mesh_view_bindings::view
+
+@group(2) @binding(0) var atlas_texture: texture_2d<f32>;
+@group(2) @binding(1) var atlas_sampler: sampler;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(3) transform_x: vec4<f32>,
+    @location(4) transform_y: vec4<f32>,
+    @location(5) transform_z: vec4<f32>,
+    @location(6) atlas_rect: vec4<f32>,
+    @location(7) color: vec4<f32>,
+    @location(8) glyph_meta: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) glyph_meta: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let local = vec4(vertex.position, 1.0);
+    let world = vec3(
+        dot(vertex.transform_x, local),
+        dot(vertex.transform_y, local),
+        dot(vertex.transform_z, local),
+    );
+    // The atlas rectangle is in pixels.
+    let size = vec2<f32>(textureDimensions(atlas_texture));
+    var out: VertexOutput;
+    out.clip_position = view.clip_from_world * vec4(world, 1.0);
+    out.uv = mix(vertex.atlas_rect.xy, vertex.atlas_rect.zw, vertex.position.xy) / size;
+    out.color = vertex.color;
+    out.glyph_meta = vertex.glyph_meta;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color * textureSample(atlas_texture, atlas_sampler, in.uv);
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use bevy::ecs::resource::Resource;
+use rustc_hash::FxHashSet;
+
+use crate::{Text3d, Text3dSegment};
+
+/// A table of shared strings for [`Text3dSegment::Interned`],
+/// so labels repeated across thousands of texts share one allocation.
+///
+/// Equality checks of interned segments, i.e. in [`Text3d::set_segments`],
+/// compare pointers before contents.
+#[derive(Debug, Default, Resource)]
+pub struct Text3dInterner {
+    strings: FxHashSet<Arc<str>>,
+    hits: usize,
+    misses: usize,
+}
+
+/// Statistics of a [`Text3dInterner`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InternStats {
+    /// Number of distinct strings.
+    pub strings: usize,
+    /// Total length of distinct strings in bytes.
+    pub bytes: usize,
+    /// Number of strings that were already interned.
+    pub hits: usize,
+    /// Number of strings added.
+    pub misses: usize,
+}
+
+impl Text3dInterner {
+    /// Obtain the shared copy of a string, adding it if missing.
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(result) = self.strings.get(s) {
+            self.hits += 1;
+            return result.clone();
+        }
+        self.misses += 1;
+        let result: Arc<str> = s.into();
+        self.strings.insert(result.clone());
+        result
+    }
+
+    /// Create a [`Text3dSegment::Interned`] segment.
+    pub fn segment(&mut self, s: &str) -> Text3dSegment {
+        Text3dSegment::Interned(self.intern(s))
+    }
+
+    /// Replace the [`Text3dSegment::String`]s of a [`Text3d`] with interned segments.
+    pub fn intern_text(&mut self, text: &mut Text3d) {
+        for (segment, _) in &mut text.segments {
+            if let Text3dSegment::String(s) = segment {
+                *segment = self.segment(s);
+            }
+        }
+    }
+
+    /// Remove strings no longer used outside of this table, returns the number of strings removed.
+    pub fn collect_unused(&mut self) -> usize {
+        let len = self.strings.len();
+        self.strings.retain(|s| Arc::strong_count(s) > 1);
+        len - self.strings.len()
+    }
+
+    pub fn stats(&self) -> InternStats {
+        InternStats {
+            strings: self.strings.len(),
+            bytes: self.strings.iter().map(|s| s.len()).sum(),
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+}
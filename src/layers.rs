@@ -1,14 +1,37 @@
 use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Formatter},
     num::NonZero,
     ops::{BitOr, BitOrAssign},
+    sync::Arc,
 };
 
 use bevy::{color::Srgba, math::Vec2};
 
-use crate::{line::LineMode, SegmentStyle, Text3dStyling};
+use crate::{GlowStyle, LineMode, SegmentStyle, Text3dDrawer, Text3dDrawers, Text3dStyling};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Layer(u8);
+/// Sort key of a quad in a text mesh, higher layers are drawn in front of lower layers.
+///
+/// The low byte orders parts of a glyph and can be combined with `|`,
+/// the high byte is a signed bias, added to by [`SegmentStyle::layer`].
+///
+/// Quads of the same layer are placed at the same depth,
+/// different layers are separated by [`Text3dStyling::layer_offset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layer(u16);
+
+impl Ord for Layer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Flip the sign bit so negative biases sort below unbiased layers.
+        (self.0 ^ 0x8000).cmp(&(other.0 ^ 0x8000))
+    }
+}
+
+impl PartialOrd for Layer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
 impl BitOr for Layer {
     type Output = Layer;
@@ -26,30 +49,88 @@ impl BitOrAssign for Layer {
 
 #[allow(non_upper_case_globals)]
 impl Layer {
+    /// Set on everything except text shadows.
     pub const NoShadow: Layer = Layer(0x80);
+    /// Glows, behind the glyph and in front of text shadows.
+    pub const Glow: Layer = Layer(0x40);
+    /// Strikethrough lines.
     pub const Strikethrough: Layer = Layer(0x4);
     /// Depend on the offset, either fill or stroke.
     pub const Higher: Layer = Layer(0x2);
+    /// Underlines.
     pub const Underline: Layer = Layer(0x1);
 
     pub const None: Layer = Layer(0);
+
+    /// Behind everything, including text shadows of all segments.
+    pub const Background: Layer = Layer(0).with_bias(i8::MIN);
+    /// In front of everything in all segments.
+    pub const Foreground: Layer = Layer(0xFF).with_bias(i8::MAX);
+
+    pub const fn new(value: u8) -> Layer {
+        Layer(value as u16)
+    }
+
+    /// Signed bias of the layer, by default `0`.
+    pub const fn bias(self) -> i8 {
+        (self.0 >> 8) as u8 as i8
+    }
+
+    /// Move the layer above or below all layers with a smaller or larger bias.
+    pub const fn with_bias(self, bias: i8) -> Layer {
+        Layer((self.0 & 0xFF) | ((bias as u8 as u16) << 8))
+    }
 }
 
+/// What a [`DrawRequest`] draws.
+#[derive(Clone)]
 pub enum DrawType {
+    /// The glyph's fill if `None`, or its stroke.
     Glyph(Option<NonZero<u32>>),
+    /// The glyph's fill or stroke blurred by a radius, used by soft text shadows.
+    Blurred(Option<NonZero<u32>>, f32),
+    /// The glyph dilated and blurred by a radius, with its stroke if any.
+    Glow(Option<NonZero<u32>>, GlowStyle),
+    /// A decoration line under the glyph, with the glyph's fill or stroke.
     Line(Option<NonZero<u32>>, LineMode),
+    /// A quad drawn by a [`Text3dDrawer`].
+    Custom(Arc<dyn Text3dDrawer>),
 }
 
+impl Debug for DrawType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Glyph(stroke) => f.debug_tuple("Glyph").field(stroke).finish(),
+            Self::Blurred(stroke, radius) => f
+                .debug_tuple("Blurred")
+                .field(stroke)
+                .field(radius)
+                .finish(),
+            Self::Glow(stroke, glow) => f.debug_tuple("Glow").field(stroke).field(glow).finish(),
+            Self::Line(stroke, mode) => f.debug_tuple("Line").field(stroke).field(mode).finish(),
+            Self::Custom(_) => f.debug_tuple("Custom").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// A quad to be drawn for each glyph, generated from [`Text3dStyling`] and [`SegmentStyle`].
+#[derive(Debug, Clone)]
 pub struct DrawRequest {
     pub sort: Layer,
     pub request: DrawType,
     pub color: Srgba,
+    /// Offset in local space, used by text shadows.
     pub offset: Vec2,
 }
 
 impl Text3dStyling {
     /// Note: Things drawn last gets rendered first.
-    pub(crate) fn fill_draw_requests(&self, attrs: &SegmentStyle, requests: &mut Vec<DrawRequest>) {
+    pub(crate) fn fill_draw_requests(
+        &self,
+        attrs: &SegmentStyle,
+        drawers: Option<&Text3dDrawers>,
+        requests: &mut Vec<DrawRequest>,
+    ) {
         requests.clear();
         #[allow(non_snake_case)]
         let FILL = if self.stroke_in_front {
@@ -67,6 +148,8 @@ impl Text3dStyling {
         let stroke = attrs.stroke.or(self.stroke);
         let fill_color = attrs.fill_color.unwrap_or(self.color);
         let stroke_color = attrs.stroke_color.unwrap_or(self.stroke_color);
+        // Fill and stroke share a quad in the fill's layer.
+        let two_tone = self.two_tone && fill && stroke.is_some();
         let fill_stroke: &[_] = match (fill, stroke) {
             (true, None) => &[(None, fill_color, FILL)],
             (true, Some(stroke)) => &[
@@ -76,21 +159,53 @@ impl Text3dStyling {
             (false, None) => &[],
             (false, Some(stroke)) => &[(Some(stroke), stroke_color, STROKE)],
         };
-        let normal_shadow: &[_] = match self.text_shadow {
-            Some((color, offset)) => &[
-                (None, Vec2::ZERO, Layer::NoShadow),
-                (Some(color), offset, Layer::None),
-            ],
-            None => &[(None, Vec2::ZERO, Layer::None)],
-        };
-        for (shadow_color, offset, shadow_layer) in normal_shadow.iter().copied() {
+        let has_shadow = self.text_shadow.is_some() || !self.text_shadows.is_empty();
+        let normal_shadow = [(
+            None,
+            Vec2::ZERO,
+            0.0,
+            if has_shadow {
+                Layer::NoShadow
+            } else {
+                Layer::None
+            },
+        )];
+        let shadows = self
+            .text_shadow
+            .map(|(color, offset)| (Some(color), offset, 0.0, Layer::None))
+            .into_iter()
+            .chain(
+                self.text_shadows
+                    .iter()
+                    .map(|(color, offset, blur)| (Some(*color), *offset, *blur, Layer::None)),
+            );
+        for (shadow_color, offset, blur, shadow_layer) in normal_shadow.into_iter().chain(shadows) {
             for (stroke, color, regular_layer) in fill_stroke.iter().copied() {
-                requests.push(DrawRequest {
-                    request: DrawType::Glyph(stroke),
-                    color: shadow_color.unwrap_or(color),
-                    offset,
-                    sort: regular_layer | shadow_layer,
-                });
+                if blur > 0.0 {
+                    // Two-tone strokes cover the fill.
+                    if !two_tone || stroke.is_some() {
+                        requests.push(DrawRequest {
+                            request: DrawType::Blurred(stroke, blur),
+                            color: shadow_color.unwrap_or(color),
+                            offset,
+                            sort: regular_layer | shadow_layer,
+                        });
+                    }
+                } else if !two_tone {
+                    requests.push(DrawRequest {
+                        request: DrawType::Glyph(stroke),
+                        color: shadow_color.unwrap_or(color),
+                        offset,
+                        sort: regular_layer | shadow_layer,
+                    });
+                } else if stroke.is_some() {
+                    requests.push(DrawRequest {
+                        request: DrawType::Glyph(stroke),
+                        color: shadow_color.unwrap_or(fill_color),
+                        offset,
+                        sort: FILL | shadow_layer,
+                    });
+                }
                 if attrs.underline.is_some_and(|x| x) {
                     requests.push(DrawRequest {
                         request: DrawType::Line(stroke, LineMode::Underline),
@@ -109,5 +224,33 @@ impl Text3dStyling {
                 }
             }
         }
+        if let Some(glow) = self.glow.filter(|_| fill || stroke.is_some()) {
+            requests.push(DrawRequest {
+                request: DrawType::Glow(stroke, glow),
+                color: glow.color,
+                offset: Vec2::ZERO,
+                sort: Layer::Glow,
+            });
+        }
+        if let Some(drawers) = drawers {
+            for name in self.drawers.iter().chain(&attrs.drawers) {
+                let Some(drawer) = drawers.get(name) else {
+                    continue;
+                };
+                requests.push(DrawRequest {
+                    request: DrawType::Custom(drawer.clone()),
+                    color: fill_color,
+                    offset: Vec2::ZERO,
+                    sort: drawer.layer(),
+                });
+            }
+        }
+        if let Some(bias) = attrs.layer {
+            for request in requests.iter_mut() {
+                request.sort = request
+                    .sort
+                    .with_bias(request.sort.bias().saturating_add(bias));
+            }
+        }
     }
 }
@@ -0,0 +1,290 @@
+use std::{ops::Range, sync::Arc};
+
+use bevy::{
+    ecs::{change_detection::DetectChanges, component::Component, system::Query, world::Ref},
+    math::{Rect, Vec2},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+/// Layout information of a [`Text3d`](crate::Text3d) in local space, for hit-testing and caret placement,
+/// or custom effects like syncing particles to glyph positions.
+///
+/// Add this component to a [`Text3d`](crate::Text3d) entity to opt-in,
+/// it is updated every time the text is rendered.
+///
+/// Byte offsets are indices into the text as if all segments of the [`Text3d`](crate::Text3d)
+/// are concatenated into a single string.
+#[derive(Debug, Component, Default, Clone)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dLayoutInfo {
+    /// Rendered glyphs from left to right, top to bottom.
+    pub glyphs: Vec<GlyphLayoutInfo>,
+    /// Laid out lines from top to bottom.
+    pub lines: Vec<LineLayoutInfo>,
+    /// The laid out text, byte offsets are indices into this string.
+    pub text: String,
+}
+
+/// Per-glyph layout output of a [`Text3d`](crate::Text3d) in local space,
+/// for custom effects, hit testing, accessibility or syncing particles to glyph positions.
+///
+/// Add this component to a [`Text3d`](crate::Text3d) entity to opt-in,
+/// it is updated every time the text is rendered.
+/// This is a flat view of [`Text3dLayoutInfo::glyphs`], which also has line and caret queries.
+#[derive(Debug, Component, Default, Clone)]
+#[require(Text3dLayoutInfo)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dGlyphLayout {
+    /// Rendered glyphs from left to right, top to bottom.
+    pub glyphs: Vec<Text3dGlyph>,
+}
+
+/// Layout output of a glyph in [`Text3dGlyphLayout`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct Text3dGlyph {
+    /// Byte range of the cluster this glyph is shaped from, in [`Text3dLayoutInfo::text`].
+    pub cluster: Range<usize>,
+    /// Index of the [`Text3dSegment`](crate::Text3dSegment) this glyph belongs to.
+    pub segment: usize,
+    /// Bounding rectangle of the glyph's advance in local space, spans the entire line height.
+    pub rect: Rect,
+    /// Index of the line in [`Text3dLayoutInfo::lines`].
+    pub line: usize,
+    /// Rectangle of the glyph's fill in the [`TextAtlas`](crate::TextAtlas) in pixels,
+    /// `None` if nothing is drawn.
+    pub atlas_rect: Option<Rect>,
+}
+
+impl From<&GlyphLayoutInfo> for Text3dGlyph {
+    fn from(glyph: &GlyphLayoutInfo) -> Self {
+        Text3dGlyph {
+            cluster: glyph.cluster.clone(),
+            segment: glyph.segment,
+            rect: glyph.rect,
+            line: glyph.line,
+            atlas_rect: glyph.atlas_rect,
+        }
+    }
+}
+
+/// Layout information of a glyph.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct GlyphLayoutInfo {
+    /// Index of the glyph, matches [`GlyphMeta::Index`](crate::GlyphMeta::Index).
+    pub index: usize,
+    /// Index of the [`Text3dSegment`](crate::Text3dSegment) this glyph belongs to.
+    pub segment: usize,
+    /// Index of the line in [`Text3dLayoutInfo::lines`].
+    pub line: usize,
+    /// Byte range of the cluster this glyph is shaped from.
+    pub cluster: Range<usize>,
+    /// Bounding rectangle of the glyph's advance in local space, spans the entire line height.
+    pub rect: Rect,
+    /// If true, the glyph is rendered right to left.
+    pub rtl: bool,
+    /// Rectangle of the glyph's fill quad in local space, `None` if nothing is drawn.
+    pub quad: Option<Rect>,
+    /// Rectangle of the glyph's fill in the [`TextAtlas`](crate::TextAtlas) in pixels.
+    pub atlas_rect: Option<Rect>,
+    /// Range of vertices in the text's mesh drawn for this glyph,
+    /// including strokes and shadows but not underlines.
+    pub vertices: Range<usize>,
+    /// If true, `vertices` refers to the mesh of [`Text3dDynamicMesh::entity`](crate::Text3dDynamicMesh::entity)
+    /// instead of the text's mesh.
+    pub dynamic: bool,
+    /// [`SegmentStyle::tag`](crate::SegmentStyle::tag) of the glyph's segment.
+    pub tag: Option<Arc<str>>,
+}
+
+/// Layout information of a line.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct LineLayoutInfo {
+    /// Range of glyphs in [`Text3dLayoutInfo::glyphs`].
+    pub glyphs: Range<usize>,
+    /// Byte range of the line.
+    pub bytes: Range<usize>,
+    /// Bounding rectangle of the line in local space.
+    pub rect: Rect,
+    /// Y position of the baseline in local space.
+    pub baseline: f32,
+}
+
+/// Result of [`Text3dLayoutInfo::word_at`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct Text3dWord {
+    /// Byte range of the word in [`Text3dLayoutInfo::text`].
+    pub bytes: Range<usize>,
+    /// Bounding rectangle of the word's glyphs on its line in local space.
+    pub rect: Rect,
+}
+
+/// Result of [`Text3dLayoutInfo::hit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text3dHit {
+    /// Index of the glyph in [`Text3dLayoutInfo::glyphs`], if hit directly.
+    pub glyph: Option<usize>,
+    /// Index of the line in [`Text3dLayoutInfo::lines`].
+    pub line: usize,
+    /// Byte offset of the nearest caret position.
+    pub byte: usize,
+    /// [`SegmentStyle::tag`](crate::SegmentStyle::tag) of the glyph, if hit directly.
+    pub tag: Option<Arc<str>>,
+}
+
+impl Text3dLayoutInfo {
+    pub(crate) fn clear(&mut self) {
+        self.glyphs.clear();
+        self.lines.clear();
+        self.text.clear();
+    }
+
+    pub(crate) fn transform(&mut self, f: impl Fn(Vec2) -> Vec2) {
+        let transform_rect = |rect: &mut Rect| *rect = Rect::from_corners(f(rect.min), f(rect.max));
+        for glyph in &mut self.glyphs {
+            transform_rect(&mut glyph.rect);
+            if let Some(quad) = &mut glyph.quad {
+                transform_rect(quad);
+            }
+        }
+        for line in &mut self.lines {
+            transform_rect(&mut line.rect);
+            line.baseline = f(Vec2::new(0.0, line.baseline)).y;
+        }
+    }
+
+    /// Find the glyph at a position in local space.
+    pub fn glyph_at(&self, position: Vec2) -> Option<&GlyphLayoutInfo> {
+        self.glyphs.iter().find(|g| g.rect.contains(position))
+    }
+
+    /// Find the [`SegmentStyle::tag`](crate::SegmentStyle::tag) of the glyph at a position in local space.
+    pub fn tag_at(&self, position: Vec2) -> Option<&Arc<str>> {
+        self.glyph_at(position)?.tag.as_ref()
+    }
+
+    /// Find the first glyph whose cluster contains a byte offset.
+    pub fn glyph_for_byte(&self, byte: usize) -> Option<&GlyphLayoutInfo> {
+        self.glyphs.iter().find(|g| g.cluster.contains(&byte))
+    }
+
+    /// Find the word under a position in local space, using unicode word boundaries.
+    ///
+    /// Returns `None` if the position is not on a glyph or the glyph is whitespace or punctuation.
+    pub fn word_at(&self, position: Vec2) -> Option<Text3dWord> {
+        let glyph = self.glyph_at(position)?;
+        self.word_for_byte(glyph.cluster.start)
+    }
+
+    /// Find the word containing a byte offset, using unicode word boundaries.
+    pub fn word_for_byte(&self, byte: usize) -> Option<Text3dWord> {
+        let (start, word) = self
+            .text
+            .split_word_bound_indices()
+            .find(|(start, word)| (*start..start + word.len()).contains(&byte))?;
+        if !word.chars().any(char::is_alphanumeric) {
+            return None;
+        }
+        let bytes = start..start + word.len();
+        let line = self.glyph_for_byte(byte)?.line;
+        let rect = self
+            .glyphs
+            .iter()
+            .filter(|g| {
+                g.line == line && g.cluster.start >= bytes.start && g.cluster.end <= bytes.end
+            })
+            .map(|g| g.rect)
+            .reduce(|a, b| a.union(b))?;
+        Some(Text3dWord { bytes, rect })
+    }
+
+    /// Find the line and nearest caret position to a position in local space.
+    ///
+    /// Positions above or below the text are clamped to the first or last line.
+    pub fn hit(&self, position: Vec2) -> Option<Text3dHit> {
+        let (line_index, line) = self
+            .lines
+            .iter()
+            .enumerate()
+            .find(|(_, line)| position.y >= line.rect.min.y && position.y <= line.rect.max.y)
+            .or_else(|| {
+                let first = self.lines.first()?;
+                if position.y > first.rect.max.y {
+                    Some((0, first))
+                } else {
+                    self.lines.iter().enumerate().next_back()
+                }
+            })?;
+        let glyphs = self.glyphs.get(line.glyphs.clone()).unwrap_or(&[]);
+        let mut best = (f32::MAX, None, line.bytes.start);
+        for (i, glyph) in glyphs.iter().enumerate() {
+            let direct = glyph.rect.min.x <= position.x && glyph.rect.max.x >= position.x;
+            // The visually left edge is the start of the cluster for left to right text.
+            let (left, right) = if glyph.rtl {
+                (glyph.cluster.end, glyph.cluster.start)
+            } else {
+                (glyph.cluster.start, glyph.cluster.end)
+            };
+            for (x, byte) in [(glyph.rect.min.x, left), (glyph.rect.max.x, right)] {
+                let distance = (x - position.x).abs();
+                if distance < best.0 {
+                    best = (distance, direct.then_some(line.glyphs.start + i), byte);
+                }
+            }
+        }
+        Some(Text3dHit {
+            glyph: best.1,
+            line: line_index,
+            byte: best.2,
+            tag: best.1.and_then(|i| self.glyphs.get(i)?.tag.clone()),
+        })
+    }
+
+    /// Returns a caret rectangle with a width of `0` in local space before the glyph at a byte offset.
+    pub fn caret_rect(&self, byte: usize) -> Option<Rect> {
+        let at_edge = |glyph: &GlyphLayoutInfo, x: f32| {
+            let line = self.lines.get(glyph.line)?;
+            Some(Rect::new(x, line.rect.min.y, x, line.rect.max.y))
+        };
+        if let Some(glyph) = self.glyph_for_byte(byte) {
+            let x = if glyph.rtl {
+                glyph.rect.max.x
+            } else {
+                glyph.rect.min.x
+            };
+            return at_edge(glyph, x);
+        }
+        // After the last glyph of a line.
+        let glyph = self.glyphs.iter().rev().find(|g| g.cluster.end == byte)?;
+        let x = if glyph.rtl {
+            glyph.rect.min.x
+        } else {
+            glyph.rect.max.x
+        };
+        at_edge(glyph, x)
+    }
+}
+
+/// Copies [`Text3dLayoutInfo`] into [`Text3dGlyphLayout`] after text is rendered.
+pub fn glyph_layout_system(mut query: Query<(&mut Text3dGlyphLayout, Ref<Text3dLayoutInfo>)>) {
+    for (mut glyph_layout, layout) in query.iter_mut() {
+        if !layout.is_changed() && !glyph_layout.is_added() {
+            continue;
+        }
+        glyph_layout.glyphs.clear();
+        glyph_layout
+            .glyphs
+            .extend(layout.glyphs.iter().map(Text3dGlyph::from));
+    }
+}
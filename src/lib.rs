@@ -2,69 +2,213 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::collapsible_if)]
+#[cfg(feature = "a11y")]
+mod accessibility;
+mod animation;
 mod atlas;
+#[cfg(feature = "3d")]
+mod atlas_extension;
+mod batch;
+#[cfg(feature = "bevy_text")]
+mod bevy_fonts;
+mod billboard;
+mod builder;
 mod change_detection;
+mod clip;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod color_span;
 mod color_table;
+#[cfg(feature = "console")]
+mod console;
+#[cfg(feature = "debug")]
+mod debug_gizmos;
+mod debug_overlay;
+mod diagnostics;
+mod drawer;
+mod dynamic_mesh;
+mod editor;
+#[cfg(feature = "effects")]
+mod effects;
 mod fetch;
+mod floating;
+mod font_asset;
+mod font_usage;
+mod hidden;
+mod highlight;
+mod html;
+mod icon;
+#[cfg(feature = "3d")]
+mod instanced;
+mod intern;
 mod layers;
+mod layout;
 mod line;
 mod loading;
+#[cfg(feature = "fluent")]
+mod localization;
+mod marquee;
+mod mesh_builder;
 mod mesh_util;
 mod misc;
 mod parse;
+mod patch;
+mod path;
 mod prepare;
+mod quality;
 mod render;
+mod rich_text;
+mod screen_size;
+mod shape_cache;
+mod stylesheet;
 mod styling;
 mod tess;
 mod text3d;
+#[cfg(feature = "ui")]
+mod ui;
+mod validate;
+mod worker;
 pub use prepare::{DrawStyle, FontSystemGuard, TextProgressReportCallback, TextRenderer};
+pub use quality::TextQuality;
 
-pub use atlas::{TextAtlas, TextAtlasHandle};
+#[cfg(feature = "a11y")]
+pub use accessibility::Text3dAccessible;
+pub use animation::{GlyphAnimationInput, GlyphTransform, Text3dGlyphAnimation};
+pub use atlas::{TextAtlas, TextAtlasEvent, TextAtlasHandle};
+#[cfg(feature = "3d")]
+pub use atlas_extension::{
+    TextAtlasArrayExtension, TextAtlasArrayMaterial, TextAtlasExtension, TextAtlasExtensionPlugin,
+    TextAtlasMaterial, TextTwoToneExtension, TextTwoToneMaterial,
+};
+pub use batch::{Text3dBatch, Text3dBatchPlugin, Text3dBatched};
 #[cfg(feature = "reflect")]
 use bevy::prelude::{Reflect, ReflectDefault, ReflectResource};
 use bevy::{
     app::{App, First, Plugin, PostUpdate},
     asset::{AssetApp, AssetId, Assets},
     ecs::{
+        event::EventWriter,
         query::With,
         resource::Resource,
         schedule::{common_conditions::resource_exists, IntoScheduleConfigs, SystemSet},
-        system::{Query, ResMut},
+        system::{Local, Query, Res, ResMut},
         world::Ref,
     },
     image::Image,
     transform::TransformSystem,
     window::{PrimaryWindow, Window},
 };
+pub use billboard::{BillboardMode, Text3dBillboard, Text3dBillboardPlugin};
+pub use builder::Text3dBuilder;
+pub use clip::{ClipSdf, ClipShape, Text3dClip, Text3dClipPlugin};
+pub use color_span::Text3dColorSpans;
+#[cfg(feature = "console")]
+pub use console::{Text3dConsole, Text3dConsoleSubmit};
 
+use atlas::AtlasClearQueue;
 use change_detection::TouchMaterialSet;
 #[cfg(feature = "2d")]
 pub use change_detection::TouchTextMaterial2dPlugin;
 #[cfg(feature = "3d")]
 pub use change_detection::TouchTextMaterial3dPlugin;
-pub use fetch::{FetchedTextSegment, SharedTextSegment, TextFetch};
+#[cfg(feature = "clipboard")]
+pub use clipboard::{CopyRange, Text3dCopyToClipboard};
+#[cfg(feature = "debug")]
+pub use debug_gizmos::{Text3dDebugPlugin, Text3dDebugSettings};
+pub use debug_overlay::{Text3dDebugOverlay, Text3dDebugOverlayPlugin};
+pub use diagnostics::Text3dDiagnosticsPlugin;
+pub use drawer::{DrawerInput, DrawerTexture, Text3dDrawer, Text3dDrawers};
+pub use dynamic_mesh::Text3dDynamicMesh;
+pub use editor::Text3dEditor;
+#[cfg(feature = "effects")]
+pub use effects::{
+    Text3dEffects, Text3dEffectsExtension, Text3dEffectsPlugin, Text3dStandardMaterial,
+};
+pub use fetch::{FetchedTextSegment, NumberFormat, SharedTextSegment, TextFetch};
+pub use floating::{FloatingText, FloatingTextPool, Text3dFloatingPlugin};
+pub use font_asset::{Font3d, Font3dLoadError, Font3dLoader, Text3dFontEvent};
+pub use font_usage::{FontUsage, SegmentFontUsage, Text3dFontUsage};
+pub use hidden::Text3dHidden;
+pub use highlight::Text3dWordHighlight;
+pub use html::{HtmlErrorKind, HtmlParseError};
+pub use icon::{Text3dIcon, Text3dIcons};
+#[cfg(feature = "3d")]
+pub use instanced::{Text3dInstanced, Text3dInstancedPlugin};
+pub use intern::{InternStats, Text3dInterner};
+pub use layers::{DrawRequest, DrawType, Layer};
+pub use layout::{
+    GlyphLayoutInfo, LineLayoutInfo, Text3dGlyph, Text3dGlyphLayout, Text3dHit, Text3dLayoutInfo,
+    Text3dWord,
+};
+pub use line::LineMode;
 use loading::{load_cosmic_fonts_system, LoadCosmicFonts};
+#[cfg(feature = "fluent")]
+pub use localization::{
+    fetch_localized, FluentAsset, FluentAssetLoader, FluentLoadError, LocalizedText, Text3dLocale,
+};
+pub use marquee::{MarqueeAxis, Text3dMarquee, Text3dMarqueePlugin};
+pub use mesh_builder::Text3dMeshBuilder;
+use mesh_util::SharedMeshes;
 pub use misc::*;
-pub use parse::ParseError;
+pub use parse::{escape_rich_text, ParseError, ParseSpan, SpannedParseError};
+pub use path::Text3dPath;
+pub use rich_text::{
+    RichText3d, RichTextAsset, RichTextAssetLoader, RichTextLoadError, RichTextSegment,
+    RichTextValue, Text3dRichTextPlugin,
+};
+pub use screen_size::{Text3dConstantScreenSize, Text3dConstantScreenSizePlugin};
+pub use shape_cache::Text3dShapeCache;
+pub use stylesheet::{StyledText3d, Text3dStyleSheetPlugin, TextStyleSheet};
+#[cfg(feature = "serde")]
+pub use stylesheet::{TextStyleSheetLoadError, TextStyleSheetLoader};
 pub use styling::{SegmentStyle, Text3dStyling};
-pub use text3d::{Text3d, Text3dSegment};
+pub use text3d::{ComputedSegment, SegmentRemoval, Text3d, Text3dKeepSegments, Text3dSegment};
+#[cfg(feature = "ui")]
+pub use ui::{Text3dUiNode, Text3dUiPlugin};
+pub use validate::Text3dMissingFont;
 
 fn synchronize_scale_factor(
     mut settings: ResMut<Text3dPlugin>,
     main_window: Query<Ref<Window>, With<PrimaryWindow>>,
     mut atlases: ResMut<Assets<TextAtlas>>,
     mut images: ResMut<Assets<Image>>,
+    mut events: EventWriter<TextAtlasEvent>,
+    mut queue: ResMut<AtlasClearQueue>,
+    quality: Res<TextQuality>,
+    mut applied_quality: Local<TextQuality>,
 ) {
+    let mut scale_factor = settings.scale_factor;
     if settings.sync_scale_factor_with_main_window {
         if let Ok(window) = main_window.single() {
-            if window.scale_factor() != settings.scale_factor {
-                settings.scale_factor = window.scale_factor();
-                for (_, atlas) in atlases.iter_mut() {
-                    atlas.clear(&mut images);
-                }
-            }
+            scale_factor = window.scale_factor();
         }
     }
+    let quality_changed = *applied_quality != *quality;
+    *applied_quality = *quality;
+    if scale_factor != settings.scale_factor || quality_changed {
+        if scale_factor != settings.scale_factor {
+            settings.scale_factor = scale_factor;
+        }
+        queue.pending.clear();
+        queue.pending.extend(atlases.ids());
+    }
+    let count = settings
+        .atlases_cleared_per_frame
+        .unwrap_or(usize::MAX)
+        .min(queue.pending.len());
+    for id in queue.pending.drain(..count).collect::<Vec<_>>() {
+        let Some(atlas) = atlases.get_mut(id) else {
+            continue;
+        };
+        atlas.clear(&mut images);
+        if let Some(image) = images.get(atlas.image.id()) {
+            events.write(TextAtlasEvent::Cleared {
+                atlas: id,
+                dimension: image.size(),
+            });
+        }
+        queue.cleared.insert(id);
+    }
 }
 
 /// Text3d Plugin, add [`Text3dPluginSettings`] before this to modify its behavior.
@@ -80,6 +224,8 @@ pub struct Text3dPlugin {
     pub default_atlas_dimension: (usize, usize),
     /// This should be the primary window's `scale_factor`. For example if this value is 2, a 32 x 32 glyph will
     /// take up 64 x 64 pixels.
+    ///
+    /// Glyphs are rasterized at this value multiplied by [`TextQuality::scale_multiplier`].
     pub scale_factor: f32,
     /// Currently the [`Window`]'s scale factor is not correct at app startup,
     /// if true synchronizes scale factor with the [`PrimaryWindow`]'s scale factor.
@@ -102,6 +248,61 @@ pub struct Text3dPlugin {
     /// Must add `TouchTextMaterial*dPlugin`s to non-standard materials, otherwise
     /// text drawn before font system is loaded will not be rendered.
     pub asynchronous_load: bool,
+    /// If true, text entities that produce identical meshes will share the same [`Mesh`](bevy::render::mesh::Mesh) asset,
+    /// saving memory for repeated labels.
+    ///
+    /// A shared mesh is copied before being modified,
+    /// so handles of shared meshes might change when their text is updated.
+    pub share_identical_meshes: bool,
+    /// If true, by default, the [`Aabb`](bevy::render::primitives::Aabb) of text entities is updated
+    /// in the same frame their mesh is rewritten, including the depth added by
+    /// [`Text3dStyling::layer_offset`].
+    ///
+    /// Bevy does not update bounding boxes of modified meshes, set to false if you manage them yourself.
+    pub update_aabb: bool,
+    /// If true, glyphs that are not in the atlas at runtime are rasterized in a background task
+    /// instead of during [`Text3dSet`], avoiding hitches when new characters or font sizes appear.
+    ///
+    /// Missing glyphs are not rendered until they are ready, at which point all text is redrawn.
+    /// Text rendering is paused while the background task runs, similar to [`TextRenderer::prepare_task`].
+    pub asynchronous_rasterization: bool,
+    /// If true, `uv0` of text meshes are in pixels instead of normalized to the atlas,
+    /// meshes are not rescaled when the atlas grows.
+    ///
+    /// Materials must divide `uv0` by the atlas size,
+    /// see [`TextAtlasExtension`] for a [`StandardMaterial`](bevy::pbr::StandardMaterial) extension that does this.
+    pub pixel_uvs: bool,
+    /// If set, when the scale factor or [`TextQuality`] changes, at most this many atlases are cleared and redrawn per frame
+    /// instead of all at once, roughly in the order they are created.
+    ///
+    /// Text using an atlas waiting to be cleared is not updated until the atlas is cleared.
+    /// This has no effect if all text uses the default atlas.
+    pub atlases_cleared_per_frame: Option<usize>,
+    /// Fonts used for generic families like `serif` and `monospace`,
+    /// set these for `font: "monospace"` to resolve predictably across platforms.
+    pub generic_families: GenericFamilies,
+    /// If true, sends [`Text3dMissingFont`] when text uses a font family that is not loaded,
+    /// instead of silently rendering with a fallback font.
+    pub validate_fonts: bool,
+    /// If true, the default atlas is a texture array that grows by adding layers instead of doubling in height,
+    /// see [`TextAtlas::new_array`].
+    ///
+    /// Materials must sample `uv0` as a layered texture,
+    /// see [`TextAtlasArrayExtension`]. Not compatible with `pixel_uvs`.
+    pub array_atlas: bool,
+    /// If not 0, glyphs that are not in the atlas at runtime are rasterized by this many background threads,
+    /// which take glyphs from a shared queue.
+    ///
+    /// Unlike `asynchronous_rasterization`, text rendering is never paused, missing glyphs are not rendered
+    /// until they are ready a frame or more later, at which point all text is redrawn.
+    ///
+    /// Will panic on platforms like wasm where threads are not supported.
+    pub rasterization_threads: usize,
+    /// What is drawn in place of glyphs waiting for `asynchronous_rasterization` or `rasterization_threads`.
+    pub glyph_placeholder: GlyphPlaceholder,
+    /// What happens to [`Text3dSegment::Extract`] entities when their [`Text3d`] is removed,
+    /// can be overridden per entity by adding [`SegmentRemoval`].
+    pub segment_removal: SegmentRemoval,
 }
 
 /// A [`Resource`] that contains paths of fonts to be loaded.
@@ -126,6 +327,17 @@ impl Default for Text3dPlugin {
             load_system_fonts: false,
             asynchronous_load: false,
             locale: None,
+            share_identical_meshes: false,
+            update_aabb: true,
+            asynchronous_rasterization: false,
+            pixel_uvs: false,
+            atlases_cleared_per_frame: None,
+            generic_families: GenericFamilies::default(),
+            validate_fonts: false,
+            array_atlas: false,
+            rasterization_threads: 0,
+            glyph_placeholder: GlyphPlaceholder::Invisible,
+            segment_removal: SegmentRemoval::Immediate,
         }
     }
 }
@@ -139,25 +351,80 @@ pub struct Text3dSet;
 impl Plugin for Text3dPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<TextAtlas>();
+        app.add_event::<TextAtlasEvent>();
+        app.add_event::<Text3dFontEvent>();
+        app.add_event::<Text3dMissingFont>();
+        app.add_event::<Text3dTruncated>();
+        app.add_event::<TextSegmentUpdated>();
+        app.add_event::<TextRemeshed>();
         app.init_resource::<LoadFonts>();
+        app.init_resource::<SharedMeshes>();
+        app.init_resource::<AtlasClearQueue>();
+        app.init_resource::<TextQuality>();
         app.insert_resource::<Text3dPlugin>(self.clone());
+        if self.rasterization_threads > 0 {
+            app.insert_resource(worker::RasterWorker::new(self.rasterization_threads));
+        }
         let (x, y) = self.default_atlas_dimension;
+        let (image, atlas) = if self.array_atlas {
+            (
+                TextAtlas::empty_array_image(x, y),
+                TextAtlas::new_array(TextAtlas::DEFAULT_IMAGE),
+            )
+        } else {
+            (
+                TextAtlas::empty_image(x, y),
+                TextAtlas::new(TextAtlas::DEFAULT_IMAGE),
+            )
+        };
         app.world_mut()
             .resource_mut::<Assets<Image>>()
-            .insert(&TextAtlas::DEFAULT_IMAGE, TextAtlas::empty_image(x, y));
+            .insert(&TextAtlas::DEFAULT_IMAGE, image);
         app.world_mut()
             .resource_mut::<Assets<TextAtlas>>()
-            .insert(AssetId::default(), TextAtlas::new(TextAtlas::DEFAULT_IMAGE));
+            .insert(AssetId::default(), atlas);
         app.add_systems(First, synchronize_scale_factor);
         app.add_systems(
             First,
             load_cosmic_fonts_system.run_if(resource_exists::<LoadCosmicFonts>),
         );
+        app.init_asset::<Font3d>();
+        app.init_asset_loader::<Font3dLoader>();
+        app.add_systems(
+            First,
+            font_asset::load_font3d_system
+                .run_if(resource_exists::<TextRenderer>)
+                .after(load_cosmic_fonts_system),
+        );
+        #[cfg(feature = "bevy_text")]
+        app.add_systems(
+            First,
+            bevy_fonts::share_bevy_fonts_system
+                .run_if(resource_exists::<TextRenderer>)
+                .after(load_cosmic_fonts_system)
+                .before(font_asset::font_event_system),
+        );
+        app.add_systems(
+            First,
+            font_asset::font_event_system
+                .run_if(resource_exists::<TextRenderer>)
+                .after(font_asset::load_font3d_system),
+        );
         app.add_systems(
             PostUpdate,
             (
+                text3d::deferred_segment_despawn_system,
+                fetch::orphan_fetch_system,
                 fetch::text_fetch_system,
+                hidden::visibility_defer_system,
+                hidden::hidden_text_system,
+                editor::text_editor_system,
                 render::text_render.run_if(resource_exists::<TextRenderer>),
+                atlas::atlas_event_system,
+                layout::glyph_layout_system,
+                highlight::word_highlight_system,
+                color_span::color_span_system,
+                animation::glyph_animation_system,
             )
                 .chain()
                 .in_set(Text3dSet)
@@ -167,6 +434,54 @@ impl Plugin for Text3dPlugin {
             PostUpdate,
             Text3dSet.before(TransformSystem::TransformPropagate),
         );
+        app.init_resource::<Text3dInterner>();
+        #[cfg(feature = "fluent")]
+        {
+            app.init_asset::<FluentAsset>();
+            app.init_asset_loader::<FluentAssetLoader>();
+            app.add_systems(
+                PostUpdate,
+                localization::localization_system
+                    .in_set(Text3dSet)
+                    .before(render::text_render),
+            );
+        }
+        #[cfg(feature = "clipboard")]
+        {
+            app.add_event::<Text3dCopyToClipboard>();
+            app.add_systems(
+                PostUpdate,
+                clipboard::clipboard_system.after(fetch::text_fetch_system),
+            );
+        }
+        #[cfg(feature = "a11y")]
+        app.add_systems(
+            PostUpdate,
+            accessibility::accessibility_system
+                .in_set(Text3dSet)
+                .after(fetch::text_fetch_system),
+        );
+        #[cfg(feature = "console")]
+        {
+            app.add_event::<Text3dConsoleSubmit>();
+            app.add_systems(
+                PostUpdate,
+                (console::console_input_system, console::console_log_system)
+                    .chain()
+                    .in_set(Text3dSet)
+                    .before(fetch::text_fetch_system),
+            );
+        }
+        if self.validate_fonts {
+            app.add_systems(
+                PostUpdate,
+                validate::validate_fonts_system
+                    .run_if(resource_exists::<TextRenderer>)
+                    .in_set(Text3dSet)
+                    .after(fetch::text_fetch_system)
+                    .before(render::text_render),
+            );
+        }
         app.configure_sets(PostUpdate, TouchMaterialSet.in_set(Text3dSet));
         #[cfg(feature = "2d")]
         app.add_plugins(TouchTextMaterial2dPlugin::<bevy::sprite::ColorMaterial>::default());
@@ -178,8 +493,24 @@ impl Plugin for Text3dPlugin {
             .register_type::<Text3dStyling>()
             .register_type::<Text3dSegment>()
             .register_type::<SharedTextSegment>()
+            .register_type::<Text3dKeepSegments>()
+            .register_type::<SegmentRemoval>()
+            .register_type::<TextQuality>()
             .register_type::<FetchedTextSegment>()
+            .register_type::<Text3dEditor>()
+            .register_type::<Text3dLayoutInfo>()
+            .register_type::<Text3dGlyphLayout>()
+            .register_type::<Text3dPath>()
+            .register_type::<Text3dFontUsage>()
+            .register_type::<Text3dScaleFactorOverride>()
+            .register_type::<Text3dDynamicMesh>()
+            .register_type::<Text3dIcons>()
+            .register_type::<Text3dWordHighlight>()
+            .register_type::<Text3dHidden>()
+            .register_type::<Text3dColorSpans>()
             .register_type::<Text3dPlugin>();
+        #[cfg(all(feature = "reflect", feature = "a11y"))]
+        app.register_type::<Text3dAccessible>();
     }
 
     fn cleanup(&self, app: &mut App) {
@@ -187,6 +518,10 @@ impl Plugin for Text3dPlugin {
             .world_mut()
             .remove_resource::<LoadFonts>()
             .unwrap_or_default();
+        // Provided by the user via `TextRenderer::with_font_system`.
+        if app.world().contains_resource::<TextRenderer>() {
+            return;
+        }
         if self.asynchronous_load {
             app.insert_resource(self.load_fonts_concurrent(fonts));
         } else {
@@ -8,9 +8,9 @@ use cosmic_text::{fontdb::ID, ttf_parser::Face, FontSystem, LayoutGlyph};
 use zeno::{Command, Point};
 
 use crate::{
-    styling::{GlyphEntry, GlyphTextureOf},
+    styling::{GlyphEntry, GlyphTextureOf, StrokeOutline},
     tess::CommandEncoder,
-    SegmentStyle, Text3dSegment, Text3dStyling, TextAtlas,
+    SegmentStyle, StrokeJoin, Text3dSegment, TextAtlas, Weight,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -22,8 +22,9 @@ pub(crate) struct LineRun {
     pub size: f32,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) enum LineMode {
+/// A decoration line drawn by [`DrawType::Line`](crate::DrawType::Line).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineMode {
     Underline,
     Strikethrough,
 }
@@ -93,7 +94,7 @@ impl From<LineMode> for GlyphTextureOf {
 }
 
 impl LineMode {
-    pub fn select<T>(&self, underline: T, strikethrough: T) -> T {
+    pub(crate) fn select<T>(&self, underline: T, strikethrough: T) -> T {
         match self {
             LineMode::Underline => underline,
             LineMode::Strikethrough => strikethrough,
@@ -101,7 +102,7 @@ impl LineMode {
     }
 
     /// Requires a valid first point.
-    pub fn new_run(
+    pub(crate) fn new_run(
         &self,
         size: f32,
         mut index: usize,
@@ -136,14 +137,14 @@ impl LineMode {
         result
     }
 
-    pub fn validate(&self, style: &SegmentStyle) -> bool {
+    pub(crate) fn validate(&self, style: &SegmentStyle) -> bool {
         match self {
             LineMode::Underline => style.underline.unwrap_or_default(),
             LineMode::Strikethrough => style.strikethrough.unwrap_or_default(),
         }
     }
 
-    pub fn boundary(
+    pub(crate) fn boundary(
         &self,
         glyphs: &[LayoutGlyph],
         segments: &[(Text3dSegment, SegmentStyle)],
@@ -174,7 +175,7 @@ impl LineMode {
         (min, max)
     }
 
-    pub fn size(&self, font_system: &mut FontSystem, id: ID, size: f32) -> f32 {
+    pub(crate) fn size(&self, font_system: &mut FontSystem, id: ID, size: f32) -> f32 {
         font_system
             .db()
             .with_face_data(id, |file, _| {
@@ -191,7 +192,7 @@ impl LineMode {
             .unwrap_or(size)
     }
 
-    pub fn get_line_rect(
+    pub(crate) fn get_line_rect(
         &self,
         font_system: &mut FontSystem,
         size: f32,
@@ -220,25 +221,30 @@ impl LineMode {
             .flatten()
     }
 
-    pub fn get_atlas_rect(
+    pub(crate) fn get_atlas_rect(
         &self,
         font_system: &mut FontSystem,
         font: ID,
+        size: f32,
         scale_factor: f32,
         atlas: &mut TextAtlas,
         image: &mut Image,
         tess_commands: &mut CommandEncoder,
-        attrs: &SegmentStyle,
-        style: &Text3dStyling,
+        join: StrokeJoin,
+        weight: Weight,
         stroke: Option<NonZero<u32>>,
     ) -> Option<Rect> {
         let entry = GlyphEntry {
             font,
             glyph_id: (*self).into(),
-            join: style.stroke_join,
-            size: FloatOrd(style.size),
-            weight: attrs.weight.unwrap_or(style.weight),
+            join,
+            size: FloatOrd(size),
+            weight,
             stroke,
+            padding: 0,
+            scale_factor: FloatOrd(scale_factor),
+            variations: 0,
+            outline: 0,
         };
         atlas
             .glyphs
@@ -254,7 +260,7 @@ impl LineMode {
                         };
                         self.cache_texture(
                             entry,
-                            style.size,
+                            size,
                             scale_factor,
                             atlas,
                             image,
@@ -267,7 +273,7 @@ impl LineMode {
             })
     }
 
-    pub fn cache_texture(
+    pub(crate) fn cache_texture(
         &self,
         entry: GlyphEntry,
         size: f32,
@@ -285,6 +291,7 @@ impl LineMode {
         let unit_per_em = face.units_per_em() as f32;
         let d = metrics.thickness as f32 / unit_per_em * size * scale_factor;
         tess_commands.commands.clear();
+        tess_commands.set_outline(&StrokeOutline::from_join(entry.join), 1.0);
         tess_commands
             .commands
             .push(Command::MoveTo(Point::new(0., 0.)));
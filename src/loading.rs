@@ -34,6 +34,7 @@ impl Text3dPlugin {
         for data in fonts.font_embedded {
             system.db_mut().load_font_data(data.to_vec());
         }
+        self.generic_families.apply(system.db_mut());
         TextRenderer::new(system)
     }
 
@@ -48,6 +49,7 @@ impl Text3dPlugin {
         let receiver = sender.clone();
 
         let system_fonts = self.load_system_fonts;
+        let generic_families = self.generic_families.clone();
 
         std::thread::spawn(move || {
             let empty = cosmic_text::fontdb::Database::new();
@@ -66,6 +68,7 @@ impl Text3dPlugin {
             for data in fonts.font_embedded {
                 system.db_mut().load_font_data(data.to_vec());
             }
+            generic_families.apply(system.db_mut());
             sender.set(TextRenderer::new(system))
         });
         LoadCosmicFonts(receiver)
@@ -0,0 +1,195 @@
+use std::{string::FromUtf8Error, sync::Arc};
+
+use bevy::{
+    asset::{io::Reader, Asset, AssetEvent, AssetLoader, Assets, Handle, LoadContext},
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        event::EventReader,
+        resource::Resource,
+        system::{Commands, Local, Query, Res},
+        world::Ref,
+    },
+    reflect::TypePath,
+};
+use fluent::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+use crate::{FetchedTextSegment, ParseError, Text3dSegment};
+
+/// A fluent translation file (`.ftl`) loaded as an asset.
+#[derive(Debug, Clone, Asset, TypePath)]
+pub struct FluentAsset(pub Arc<FluentResource>);
+
+/// Error when loading a [`FluentAsset`].
+#[derive(Debug, thiserror::Error)]
+pub enum FluentLoadError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Utf8(#[from] FromUtf8Error),
+    #[error("Fluent syntax error: {0}")]
+    Syntax(String),
+}
+
+/// [`AssetLoader`] of [`FluentAsset`] for `.ftl` files.
+#[derive(Debug, Default, TypePath)]
+pub struct FluentAssetLoader;
+
+impl AssetLoader for FluentAssetLoader {
+    type Asset = FluentAsset;
+    type Settings = ();
+    type Error = FluentLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &(),
+        _: &mut LoadContext<'_>,
+    ) -> Result<FluentAsset, FluentLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        match FluentResource::try_new(String::from_utf8(bytes)?) {
+            Ok(resource) => Ok(FluentAsset(Arc::new(resource))),
+            Err((_, errors)) => Err(FluentLoadError::Syntax(
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )),
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ftl"]
+    }
+}
+
+/// The active language and its translation files.
+///
+/// All [`LocalizedText`] are resolved again when this resource or its files change.
+#[derive(Debug, Clone, Resource)]
+pub struct Text3dLocale {
+    pub language: LanguageIdentifier,
+    /// Translation files, messages in later files override earlier ones.
+    pub resources: Vec<Handle<FluentAsset>>,
+}
+
+impl Text3dLocale {
+    pub fn new(language: LanguageIdentifier, resources: Vec<Handle<FluentAsset>>) -> Self {
+        Self {
+            language,
+            resources,
+        }
+    }
+}
+
+/// Writes a localized message into [`FetchedTextSegment`].
+///
+/// The key is either a message id like `greeting` or a message attribute like `greeting.title`,
+/// if the message is missing, the key is written instead.
+#[derive(Debug, Clone, Component, Default)]
+#[require(FetchedTextSegment)]
+pub struct LocalizedText {
+    pub key: String,
+    /// Variables passed to the message, like `$name`.
+    pub args: Vec<(String, String)>,
+}
+
+impl LocalizedText {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Add a variable passed to the message.
+    pub fn with_arg(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.args.push((name.into(), value.into()));
+        self
+    }
+
+    fn resolve(&self, bundle: &FluentBundle<Arc<FluentResource>>) -> String {
+        let (id, attribute) = match self.key.split_once('.') {
+            Some((id, attribute)) => (id, Some(attribute)),
+            None => (self.key.as_str(), None),
+        };
+        let Some(message) = bundle.get_message(id) else {
+            return self.key.clone();
+        };
+        let pattern = match attribute {
+            Some(attribute) => message.get_attribute(attribute).map(|x| x.value()),
+            None => message.value(),
+        };
+        let Some(pattern) = pattern else {
+            return self.key.clone();
+        };
+        let mut args = FluentArgs::new();
+        for (name, value) in &self.args {
+            args.set(name.as_str(), value.as_str());
+        }
+        let args = (!self.args.is_empty()).then_some(&args);
+        bundle
+            .format_pattern(pattern, args, &mut Vec::new())
+            .into_owned()
+    }
+}
+
+/// Returns a `fetch_string` function for [`Text3d::parse`](crate::Text3d::parse)
+/// that spawns a [`LocalizedText`] for `{loc:key}` segments,
+/// other values are passed to `fetch_string`.
+pub fn fetch_localized<'a, 'w, 's, F>(
+    commands: &'a mut Commands<'w, 's>,
+    mut fetch_string: F,
+) -> impl FnMut(&str) -> Result<Text3dSegment, ParseError> + use<'a, 'w, 's, F>
+where
+    F: FnMut(&str) -> Result<Text3dSegment, ParseError> + 'a,
+{
+    move |value| match value.strip_prefix("loc:") {
+        Some(key) => Ok(Text3dSegment::Extract(
+            commands.spawn(LocalizedText::new(key.trim())).id(),
+        )),
+        None => fetch_string(value),
+    }
+}
+
+/// Resolves [`LocalizedText`] against [`Text3dLocale`].
+pub fn localization_system(
+    locale: Option<Res<Text3dLocale>>,
+    assets: Res<Assets<FluentAsset>>,
+    mut events: EventReader<AssetEvent<FluentAsset>>,
+    mut bundle: Local<Option<FluentBundle<Arc<FluentResource>>>>,
+    mut query: Query<(Ref<LocalizedText>, &mut FetchedTextSegment)>,
+) {
+    let Some(locale) = locale else {
+        return;
+    };
+    let mut rebuild = locale.is_changed() || bundle.is_none();
+    for event in events.read() {
+        rebuild |= locale
+            .resources
+            .iter()
+            .any(|handle| event.is_loaded_with_dependencies(handle) || event.is_modified(handle));
+    }
+    if rebuild {
+        let mut new_bundle = FluentBundle::new_concurrent(vec![locale.language.clone()]);
+        // Unicode isolation marks are not useful for rendering.
+        new_bundle.set_use_isolating(false);
+        for handle in &locale.resources {
+            if let Some(asset) = assets.get(handle) {
+                new_bundle.add_resource_overriding(asset.0.clone());
+            }
+        }
+        *bundle = Some(new_bundle);
+    }
+    let Some(bundle) = bundle.as_ref() else {
+        return;
+    };
+    for (text, segment) in query.iter_mut() {
+        if rebuild || text.is_changed() {
+            FetchedTextSegment::set_if_changed(segment, text.resolve(bundle));
+        }
+    }
+}
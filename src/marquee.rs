@@ -0,0 +1,297 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::Assets,
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res, ResMut},
+        world::Ref,
+    },
+    math::IVec2,
+    render::mesh::{Mesh, Mesh2d, Mesh3d, VertexAttributeValues},
+    time::Time,
+};
+
+use crate::{
+    animation::glyph_animation_system,
+    change_detection::TouchMaterialSet,
+    mesh_util::{rescale_uvs, SharedMeshes},
+    Text3dDimensionOut, Text3dLayoutInfo, Text3dPlugin, Text3dSet,
+};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+/// Direction of a [`Text3dMarquee`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum MarqueeAxis {
+    /// Scrolls from right to left.
+    #[default]
+    Horizontal,
+    /// Scrolls from bottom to top.
+    Vertical,
+}
+
+/// Scrolls a [`Text3d`](crate::Text3d) through a window with wrap-around, like a stock ticker.
+///
+/// The window starts at the left or top edge of the text, glyphs are clipped to the window
+/// and reappear at the other end after `gap`. Decoration lines are not drawn.
+///
+/// The text's mesh is modified in place, with `share_identical_meshes`
+/// the mesh is copied before being scrolled.
+/// Cannot be combined with [`Text3dGlyphAnimation`](crate::Text3dGlyphAnimation).
+///
+/// Requires [`Text3dMarqueePlugin`].
+#[derive(Debug, Clone, Component)]
+#[require(Text3dLayoutInfo)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct Text3dMarquee {
+    pub axis: MarqueeAxis,
+    /// Length of the visible window in local space.
+    pub window: f32,
+    /// Scroll speed in local units per second.
+    pub speed: f32,
+    /// Space between the end of the text and its repetition.
+    pub gap: f32,
+    /// Current scroll distance, advanced by `speed` each frame.
+    pub scroll: f32,
+    /// If true, stops scrolling.
+    pub paused: bool,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    base: MarqueeBase,
+}
+
+/// Vertices of the mesh before scrolling.
+#[derive(Debug, Clone, Default)]
+struct MarqueeBase {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    /// Size of the atlas `uvs` are normalized to.
+    atlas_dimension: IVec2,
+}
+
+impl Text3dMarquee {
+    /// Scroll horizontally through a window of length `window` at `speed` units per second.
+    pub fn new(window: f32, speed: f32) -> Self {
+        Text3dMarquee {
+            axis: MarqueeAxis::Horizontal,
+            window,
+            speed,
+            gap: 0.0,
+            scroll: 0.0,
+            paused: false,
+            base: MarqueeBase::default(),
+        }
+    }
+
+    pub fn vertical(mut self) -> Self {
+        self.axis = MarqueeAxis::Vertical;
+        self
+    }
+
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+}
+
+fn mesh_attributes(mesh: &mut Mesh) -> Option<(&mut Vec<[f32; 3]>, &mut Vec<[f32; 2]>)> {
+    let mut positions = None;
+    let mut uvs = None;
+    for (attribute, values) in mesh.attributes_mut() {
+        match values {
+            VertexAttributeValues::Float32x3(values)
+                if attribute.id == Mesh::ATTRIBUTE_POSITION.id =>
+            {
+                positions = Some(values)
+            }
+            VertexAttributeValues::Float32x2(values) if attribute.id == Mesh::ATTRIBUTE_UV_0.id => {
+                uvs = Some(values)
+            }
+            _ => (),
+        }
+    }
+    Some((positions?, uvs?))
+}
+
+/// Scrolls [`Text3dMarquee`]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dMarqueePlugin;
+
+impl Plugin for Text3dMarqueePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            marquee_system
+                .in_set(Text3dSet)
+                .after(glyph_animation_system)
+                .before(TouchMaterialSet),
+        );
+        #[cfg(feature = "reflect")]
+        app.register_type::<Text3dMarquee>();
+    }
+}
+
+/// Scrolls [`Text3dMarquee`]s after text meshes are rendered.
+pub fn marquee_system(
+    time: Res<Time>,
+    settings: Res<Text3dPlugin>,
+    shared_meshes: Res<SharedMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(
+        &mut Text3dMarquee,
+        Ref<Text3dLayoutInfo>,
+        &Text3dDimensionOut,
+        Option<&mut Mesh2d>,
+        Option<&mut Mesh3d>,
+    )>,
+) {
+    let dt = time.delta_secs();
+    for (mut marquee, layout, output, mut mesh2d, mut mesh3d) in query.iter_mut() {
+        let Some(mut handle) = mesh2d
+            .as_ref()
+            .map(|x| x.0.clone())
+            .or_else(|| mesh3d.as_ref().map(|x| x.0.clone()))
+        else {
+            continue;
+        };
+        let redrawn = layout.is_changed();
+        if !marquee.paused {
+            marquee.scroll += marquee.speed * dt;
+        } else if !redrawn && !marquee.is_changed() {
+            continue;
+        }
+        // Copy on write, since other entities may be using the same mesh.
+        if shared_meshes.is_shared(handle.id()) {
+            let Some(copy) = meshes.get(handle.id()).cloned() else {
+                continue;
+            };
+            handle = meshes.add(copy);
+            if let Some(mesh2d) = &mut mesh2d {
+                mesh2d.0 = handle.clone();
+            }
+            if let Some(mesh3d) = &mut mesh3d {
+                mesh3d.0 = handle.clone();
+            }
+        }
+        let Some(mesh) = meshes.get_mut(handle.id()) else {
+            continue;
+        };
+        let Some((positions, uvs)) = mesh_attributes(mesh) else {
+            continue;
+        };
+        let marquee = marquee.bypass_change_detection();
+        let base = &mut marquee.base;
+        // The mesh was redrawn, its vertices are not scrolled.
+        if redrawn || base.positions.len() != positions.len() {
+            base.positions.clone_from(positions);
+            base.uvs.clone_from(uvs);
+            base.atlas_dimension = output.atlas_dimension;
+        } else if base.atlas_dimension != output.atlas_dimension {
+            // The atlas grew and uvs of the mesh were rescaled in place.
+            if !settings.pixel_uvs {
+                rescale_uvs(&mut base.uvs, base.atlas_dimension, output.atlas_dimension);
+            }
+            base.atlas_dimension = output.atlas_dimension;
+        }
+        // Coordinate along the axis, increasing in the direction text is read.
+        let (axis, sign) = match marquee.axis {
+            MarqueeAxis::Horizontal => (0, 1.0),
+            MarqueeAxis::Vertical => (1, -1.0),
+        };
+        let Some((start, end)) = base.positions.iter().map(|v| v[axis] * sign).fold(
+            None,
+            |acc: Option<(f32, f32)>, c| {
+                Some(acc.map_or((c, c), |(min, max)| (min.min(c), max.max(c))))
+            },
+        ) else {
+            continue;
+        };
+        let period = (end - start + marquee.gap).max(f32::EPSILON);
+        marquee.scroll = marquee.scroll.rem_euclid(period);
+        let window = (start, start + marquee.window);
+        // Hide vertices not belonging to glyphs, i.e. decoration lines.
+        for v in positions.iter_mut() {
+            *v = [0.0; 3];
+        }
+        for glyph in layout.glyphs.iter().filter(|glyph| !glyph.dynamic) {
+            let range = glyph.vertices.clone();
+            let (Some(vertices), Some(base_uvs)) = (
+                base.positions.get(range.clone()),
+                base.uvs.get(range.clone()),
+            ) else {
+                continue;
+            };
+            let Some(glyph_start) = vertices
+                .iter()
+                .map(|v| v[axis] * sign)
+                .min_by(|a, b| a.total_cmp(b))
+            else {
+                continue;
+            };
+            let shift =
+                start + (glyph_start - start - marquee.scroll).rem_euclid(period) - glyph_start;
+            for (i, (quad, quad_uvs)) in vertices.chunks(4).zip(base_uvs.chunks(4)).enumerate() {
+                let out = range.start + i * 4;
+                clip_quad(
+                    quad,
+                    quad_uvs,
+                    &mut positions[out..out + quad.len()],
+                    &mut uvs[out..out + quad.len()],
+                    axis,
+                    sign,
+                    shift,
+                    window,
+                );
+            }
+        }
+    }
+}
+
+/// Shift a quad along an axis and clip it to a window, interpolating its uvs.
+#[allow(clippy::too_many_arguments)]
+fn clip_quad(
+    quad: &[[f32; 3]],
+    quad_uvs: &[[f32; 2]],
+    positions: &mut [[f32; 3]],
+    uvs: &mut [[f32; 2]],
+    axis: usize,
+    sign: f32,
+    shift: f32,
+    (min, max): (f32, f32),
+) {
+    let coord = |v: &[f32; 3]| v[axis] * sign + shift;
+    let (lo, hi) = quad.iter().fold((f32::MAX, f32::MIN), |(lo, hi), v| {
+        (lo.min(coord(v)), hi.max(coord(v)))
+    });
+    if hi <= min || lo >= max {
+        // Degenerate quads are not rasterized.
+        positions.fill(quad[0]);
+        uvs.copy_from_slice(quad_uvs);
+        return;
+    }
+    let uv_at = |target: f32| {
+        quad.iter()
+            .zip(quad_uvs)
+            .find(|(v, _)| coord(v) == target)
+            .map(|(_, uv)| uv[axis])
+            .unwrap_or_default()
+    };
+    let (uv_lo, uv_hi) = (uv_at(lo), uv_at(hi));
+    for ((v, uv), (out, out_uv)) in quad
+        .iter()
+        .zip(quad_uvs)
+        .zip(positions.iter_mut().zip(uvs.iter_mut()))
+    {
+        let c = coord(v).clamp(min, max);
+        *out = *v;
+        out[axis] = c * sign;
+        *out_uv = *uv;
+        if hi > lo {
+            out_uv[axis] = uv_lo + (c - lo) / (hi - lo) * (uv_hi - uv_lo);
+        }
+    }
+}
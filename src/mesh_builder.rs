@@ -0,0 +1,104 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    color::Srgba,
+    math::{Rect, Vec2},
+    render::mesh::{Mesh, PrimitiveTopology},
+};
+
+use crate::{
+    layers::Layer,
+    mesh_util::{ExtractedMesh, MeshPostProcess},
+    GlyphLayoutInfo, GlyphMeta,
+};
+
+/// Builds a [`Mesh`] from quads, with the same vertex layout as meshes of [`Text3d`](crate::Text3d).
+///
+/// Glyph placements and their rectangles in the [`TextAtlas`](crate::TextAtlas)
+/// can be obtained from [`Text3dLayoutInfo`](crate::Text3dLayoutInfo),
+/// this allows custom text composition like in-game editors.
+#[derive(Default)]
+pub struct Text3dMeshBuilder {
+    mesh: ExtractedMesh,
+}
+
+impl Text3dMeshBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offset on the z axis between layers, see [`Text3dStyling::layer_offset`](crate::Text3dStyling::layer_offset).
+    pub fn with_layer_offset(mut self, layer_offset: f32) -> Self {
+        self.mesh.layer_offset = layer_offset;
+        self
+    }
+
+    /// Emit a quad, `atlas_rect` is in pixels of the atlas.
+    ///
+    /// Quads with a higher `layer` are drawn in front, `uv1` is in the order of
+    /// bottom left, bottom right, top left, top right.
+    pub fn quad(
+        &mut self,
+        rect: Rect,
+        atlas_rect: Rect,
+        color: Srgba,
+        layer: u8,
+        uv1: [Vec2; 4],
+    ) -> &mut Self {
+        self.mesh.push_quad(
+            rect,
+            atlas_rect,
+            color,
+            Layer::new(layer),
+            uv1.map(|x| x.to_array()),
+        );
+        self
+    }
+
+    /// Emit the fill quad of a glyph with a constant `uv1`, does nothing if the glyph is not drawn.
+    pub fn glyph(
+        &mut self,
+        glyph: &GlyphLayoutInfo,
+        color: Srgba,
+        layer: u8,
+        uv1: Vec2,
+    ) -> &mut Self {
+        if let (Some(quad), Some(atlas_rect)) = (glyph.quad, glyph.atlas_rect) {
+            self.quad(quad, atlas_rect, color, layer, [uv1; 4]);
+        }
+        self
+    }
+
+    /// Number of quads emitted.
+    pub fn len(&self) -> usize {
+        self.mesh.sort.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mesh.sort.is_empty()
+    }
+
+    /// Sort quads by layer and write them into a [`Mesh`], replacing its vertex data.
+    ///
+    /// `atlas_size` is the size of the atlas' image, used to normalize uvs.
+    pub fn write(mut self, mesh: &mut Mesh, atlas_size: Vec2) {
+        self.mesh.finish(&MeshPostProcess {
+            uv1: (GlyphMeta::Index, GlyphMeta::Index),
+            glyph_meta: None,
+            bb_min: Vec2::ZERO,
+            dimension: Vec2::ONE,
+            offset: Vec2::ZERO,
+            scale: None,
+            atlas_size: Some(atlas_size),
+        });
+        self.mesh.write(mesh);
+    }
+
+    /// Sort quads by layer and create a new [`Mesh`].
+    ///
+    /// `atlas_size` is the size of the atlas' image, used to normalize uvs.
+    pub fn build(self, atlas_size: Vec2) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
+        self.write(&mut mesh, atlas_size);
+        mesh
+    }
+}
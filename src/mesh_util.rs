@@ -1,11 +1,18 @@
+use std::hash::{Hash, Hasher};
+
 use bevy::{
+    asset::{AssetId, Assets, Handle},
     color::{ColorToComponents, LinearRgba, Srgba},
-    image::Image,
-    math::{Rect, Vec2},
-    render::mesh::{Indices, Mesh, VertexAttributeValues},
+    ecs::resource::Resource,
+    math::{IVec2, Rect, Vec2, Vec3},
+    render::{
+        mesh::{Indices, Mesh, VertexAttributeValues},
+        primitives::Aabb,
+    },
 };
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 
-use crate::{layers::Layer, GlyphMeta, Text3dStyling};
+use crate::{layers::Layer, GlyphMeta, Text3dStyling, ATTRIBUTE_GLYPH_META};
 
 // Take the allocation if possible but clear the data.
 macro_rules! recycle_mesh {
@@ -37,53 +44,119 @@ fn corners(rect: Rect) -> [[f32; 2]; 4] {
     ]
 }
 
-pub(crate) struct ExtractedMesh<'t> {
-    pub mesh: &'t mut Mesh,
+/// Value of [`GlyphMeta::RowX`] and [`GlyphMeta::ColY`] at a vertex.
+fn post_process_meta(
+    meta_type: GlyphMeta,
+    position: &[f32; 3],
+    min: Vec2,
+    dimension: Vec2,
+) -> Option<f32> {
+    match meta_type {
+        GlyphMeta::RowX => Some((position[0] - min.x) / dimension.x),
+        GlyphMeta::ColY => Some((position[1] - min.y) / dimension.y),
+        _ => None,
+    }
+}
+
+/// Tangent of every vertex of a text mesh, quads face `+z` with `u` along `+x`.
+pub(crate) const TEXT_TANGENT: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+/// Vertex data of a text mesh, taken from a [`Mesh`] to reuse its allocations.
+///
+/// Does not borrow the [`Mesh`] so that multiple meshes can be finalized in parallel.
+#[derive(Default)]
+pub(crate) struct ExtractedMesh {
     pub positions: Vec<[f32; 3]>,
     pub normals: Vec<[f32; 3]>,
     pub uv0: Vec<[f32; 2]>,
     pub uv1: Vec<[f32; 2]>,
+    /// Values of [`ATTRIBUTE_GLYPH_META`], empty unless [`Text3dStyling::glyph_meta`] is set.
+    pub glyph_meta: Vec<[f32; 4]>,
     pub colors: Vec<[f32; 4]>,
-    pub indices: Vec<u16>,
-    pub sort: &'t mut Vec<(Layer, [u16; 6])>,
+    pub indices: Vec<u32>,
+    pub sort: Vec<(Layer, [u32; 6])>,
     pub layer_offset: f32,
+    /// If true, writes [`Mesh::ATTRIBUTE_TANGENT`].
+    pub tangents: bool,
+    /// Index of the line being emitted, for [`GlyphMeta::LineIndex`].
+    pub line: usize,
+    /// Horizontal start and width of the line being emitted, for [`GlyphMeta::LineProgress`].
+    pub line_span: (f32, f32),
+    /// Index of the word being emitted, for [`GlyphMeta::WordIndex`].
+    pub word: usize,
+    /// Horizontal start and width of the word being emitted, for [`GlyphMeta::WordProgress`].
+    pub word_span: (f32, f32),
+}
+
+/// Post processing of an [`ExtractedMesh`] after all quads are emitted.
+pub(crate) struct MeshPostProcess {
+    pub uv1: (GlyphMeta, GlyphMeta),
+    pub glyph_meta: Option<[GlyphMeta; 4]>,
+    /// Minimum of the text block's bounding box.
+    pub bb_min: Vec2,
+    pub dimension: Vec2,
+    /// Offset applied before `world_scale`.
+    pub offset: Vec2,
+    /// Scale of `em`, if `world_scale` is set.
+    pub scale: Option<Vec2>,
+    /// Size of the atlas, if `None`, uv0 is left in pixels.
+    pub atlas_size: Option<Vec2>,
 }
 
-impl<'t> ExtractedMesh<'t> {
-    pub fn new(
-        mesh: &'t mut Mesh,
-        sort_buffer: &'t mut Vec<(Layer, [u16; 6])>,
-        layer_offset: f32,
-    ) -> Self {
-        sort_buffer.clear();
+impl ExtractedMesh {
+    pub fn new(mesh: &mut Mesh, layer_offset: f32) -> Self {
         let positions = recycle_mesh!(mesh, ATTRIBUTE_POSITION, Float32x3);
         let normals = recycle_mesh!(mesh, ATTRIBUTE_NORMAL, Float32x3);
         let uv0 = recycle_mesh!(mesh, ATTRIBUTE_UV_0, Float32x2);
         let uv1 = recycle_mesh!(mesh, ATTRIBUTE_UV_1, Float32x2);
+        let glyph_meta = match mesh.remove_attribute(ATTRIBUTE_GLYPH_META) {
+            Some(VertexAttributeValues::Float32x4(mut v)) => {
+                v.clear();
+                v
+            }
+            _ => Vec::new(),
+        };
+        mesh.remove_attribute(Mesh::ATTRIBUTE_TANGENT);
         let colors = recycle_mesh!(mesh, ATTRIBUTE_COLOR, Float32x4);
 
-        let mut indices = if let Some(Indices::U16(indices)) = mesh.remove_indices() {
-            indices
-        } else {
-            Vec::new()
+        let mut indices = match mesh.remove_indices() {
+            Some(Indices::U32(indices)) => indices,
+            _ => Vec::new(),
         };
         indices.clear();
         ExtractedMesh {
-            mesh,
             positions,
             normals,
             uv0,
             uv1,
+            glyph_meta,
             colors,
             indices,
-            sort: sort_buffer,
+            sort: Vec::new(),
             layer_offset,
+            tangents: false,
+            line: 0,
+            line_span: (0.0, 0.0),
+            word: 0,
+            word_span: (0.0, 0.0),
         }
     }
 
-    pub fn pixel_to_uv(&mut self, image: &Image) {
-        let inv_width = 1.0 / image.width() as f32;
-        let inv_height = 1.0 / image.height() as f32;
+    /// Set the line of subsequent quads.
+    pub fn set_line(&mut self, line: usize, start: f32, width: f32) {
+        self.line = line;
+        self.line_span = (start, width);
+    }
+
+    /// Set the word of subsequent quads.
+    pub fn set_word(&mut self, word: usize, start: f32, width: f32) {
+        self.word = word;
+        self.word_span = (start, width);
+    }
+
+    pub fn pixel_to_uv(&mut self, atlas_size: Vec2) {
+        let inv_width = 1.0 / atlas_size.x;
+        let inv_height = 1.0 / atlas_size.y;
 
         self.uv0.iter_mut().for_each(|[x, y]| {
             *x *= inv_width;
@@ -91,20 +164,27 @@ impl<'t> ExtractedMesh<'t> {
         });
     }
 
-    pub fn post_process_uv1(&mut self, styling: &Text3dStyling, min: Vec2, dimension: Vec2) {
-        for (meta_type, i) in [(styling.uv1.0, 0), (styling.uv1.1, 1)] {
-            match meta_type {
-                GlyphMeta::RowX => {
-                    for (uv1, position) in self.uv1.iter_mut().zip(self.positions.iter()) {
-                        uv1[i] = (position[0] - min.x) / dimension.x;
-                    }
+    pub fn post_process_uv1(&mut self, uv1: (GlyphMeta, GlyphMeta), min: Vec2, dimension: Vec2) {
+        for (meta_type, i) in [(uv1.0, 0), (uv1.1, 1)] {
+            for (uv1, position) in self.uv1.iter_mut().zip(self.positions.iter()) {
+                if let Some(value) = post_process_meta(meta_type, position, min, dimension) {
+                    uv1[i] = value;
                 }
-                GlyphMeta::ColY => {
-                    for (uv1, position) in self.uv1.iter_mut().zip(self.positions.iter()) {
-                        uv1[i] = (position[1] - min.y) / dimension.y;
-                    }
+            }
+        }
+    }
+
+    pub fn post_process_glyph_meta(
+        &mut self,
+        glyph_meta: [GlyphMeta; 4],
+        min: Vec2,
+        dimension: Vec2,
+    ) {
+        for (i, meta_type) in glyph_meta.into_iter().enumerate() {
+            for (meta, position) in self.glyph_meta.iter_mut().zip(self.positions.iter()) {
+                if let Some(value) = post_process_meta(meta_type, position, min, dimension) {
+                    meta[i] = value;
                 }
-                _ => (),
             }
         }
     }
@@ -118,46 +198,119 @@ impl<'t> ExtractedMesh<'t> {
         }
     }
 
-    pub fn cache_rectangle(
+    /// Multiply the alpha of vertices after `start` by a gradient that
+    /// goes from `1` at `from` to `0` at `to` on the x axis.
+    pub fn fade_right(&mut self, start: usize, from: f32, to: f32) {
+        let len = (to - from).max(f32::EPSILON);
+        for (color, position) in self.colors[start..]
+            .iter_mut()
+            .zip(self.positions[start..].iter())
+        {
+            color[3] *= ((to - position[0]) / len).clamp(0.0, 1.0);
+        }
+    }
+
+    /// Move vertices after `start` from `pivot` to `position` and rotate them to align the x axis with `tangent`.
+    pub fn place_on_path(&mut self, start: usize, pivot: Vec2, position: Vec2, tangent: Vec2) {
+        let normal = tangent.perp();
+        for [x, y, _] in &mut self.positions[start..] {
+            let local = Vec2::new(*x, *y) - pivot;
+            let v = position + tangent * local.x + normal * local.y;
+            *x = v.x;
+            *y = v.y;
+        }
+    }
+
+    /// Returns the bounding box of all vertices.
+    pub fn bounding_box(&self) -> Option<Rect> {
+        self.positions.iter().fold(None, |rect, [x, y, _]| {
+            let point = Vec2::new(*x, *y);
+            Some(match rect {
+                Some(rect) => rect.union_point(point),
+                None => Rect::from_corners(point, point),
+            })
+        })
+    }
+
+    pub fn cache_rectangle2(
         &mut self,
-        base: Vec2,
+        mesh_rect: Rect,
         texture: Rect,
         color: Srgba,
-        scale_factor: f32,
         layer: Layer,
         real_index: usize,
         advance: f32,
         magic_number: f32,
         styling: &Text3dStyling,
     ) {
-        let mesh_rect = Rect {
-            min: base,
-            max: base + texture.size() / scale_factor,
+        let meta = |meta_type| {
+            self.meta_corners(
+                meta_type,
+                mesh_rect,
+                real_index,
+                advance,
+                magic_number,
+                styling,
+            )
         };
-        self.cache_rectangle2(
-            mesh_rect,
-            texture,
-            color,
-            layer,
-            real_index,
-            advance,
-            magic_number,
-            styling,
-        );
+        let (x, y) = (meta(styling.uv1.0), meta(styling.uv1.1));
+        let uv1_buffer = [0, 1, 2, 3].map(|i| [x[i], y[i]]);
+        let glyph_meta = styling.glyph_meta.map(|fields| {
+            let [a, b, c, d] = fields.map(meta);
+            [0, 1, 2, 3].map(|i| [a[i], b[i], c[i], d[i]])
+        });
+
+        self.push_quad(mesh_rect, texture, color, layer, uv1_buffer);
+        if let Some(glyph_meta) = glyph_meta {
+            self.glyph_meta.extend(glyph_meta);
+        }
     }
 
-    pub fn cache_rectangle2(
-        &mut self,
+    /// Value of a [`GlyphMeta`] at each corner of a quad,
+    /// [`GlyphMeta::RowX`] and [`GlyphMeta::ColY`] are computed in post processing.
+    fn meta_corners(
+        &self,
+        meta_type: GlyphMeta,
         mesh_rect: Rect,
-        texture: Rect,
-        color: Srgba,
-        layer: Layer,
         real_index: usize,
         advance: f32,
         magic_number: f32,
         styling: &Text3dStyling,
+    ) -> [f32; 4] {
+        let progress = |(start, width): (f32, f32)| {
+            if width > 0.0 {
+                ((mesh_rect.center().x - start) / width).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        };
+        match meta_type {
+            GlyphMeta::Index => [real_index as f32; 4],
+            GlyphMeta::Advance => {
+                let x0 = advance / styling.size;
+                let x1 = (advance + mesh_rect.width()) / styling.size;
+                [x0, x1, x0, x1]
+            }
+            GlyphMeta::PerGlyphAdvance => [(advance + mesh_rect.width() / 2.0) / styling.size; 4],
+            GlyphMeta::MagicNumber => [magic_number; 4],
+            GlyphMeta::LineIndex => [self.line as f32; 4],
+            GlyphMeta::LineProgress => [progress(self.line_span); 4],
+            GlyphMeta::WordIndex => [self.word as f32; 4],
+            GlyphMeta::WordProgress => [progress(self.word_span); 4],
+            GlyphMeta::RowX | GlyphMeta::ColY => [0.0; 4],
+        }
+    }
+
+    /// Emit a quad with `texture` in pixels.
+    pub fn push_quad(
+        &mut self,
+        mesh_rect: Rect,
+        texture: Rect,
+        color: Srgba,
+        layer: Layer,
+        uv1: [[f32; 2]; 4],
     ) {
-        let i = self.positions.len() as u16;
+        let i = self.positions.len() as u32;
         self.sort
             .push((layer, [i, i + 1, i + 2, i + 1, i + 3, i + 2]));
 
@@ -168,49 +321,24 @@ impl<'t> ExtractedMesh<'t> {
 
         // First we cache the pixel position since the texture may be resized.
         self.uv0.extend(corners(texture));
-
-        let mut uv1_buffer = [[0., 0.], [0., 0.], [0., 0.], [0., 0.]];
-
-        for (meta_type, i) in [(styling.uv1.0, 0), (styling.uv1.1, 1)] {
-            match meta_type {
-                GlyphMeta::Index => {
-                    for pair in &mut uv1_buffer {
-                        pair[i] = real_index as f32;
-                    }
-                }
-                GlyphMeta::Advance => {
-                    let x0 = advance / styling.size;
-                    let x1 = (advance + mesh_rect.width()) / styling.size;
-                    uv1_buffer[0][i] = x0;
-                    uv1_buffer[1][i] = x1;
-                    uv1_buffer[2][i] = x0;
-                    uv1_buffer[3][i] = x1;
-                }
-                GlyphMeta::PerGlyphAdvance => {
-                    let x = (advance + mesh_rect.width() / 2.0) / styling.size;
-                    uv1_buffer[0][i] = x;
-                    uv1_buffer[1][i] = x;
-                    uv1_buffer[2][i] = x;
-                    uv1_buffer[3][i] = x;
-                }
-                GlyphMeta::MagicNumber => {
-                    uv1_buffer[0][i] = magic_number;
-                    uv1_buffer[1][i] = magic_number;
-                    uv1_buffer[2][i] = magic_number;
-                    uv1_buffer[3][i] = magic_number;
-                }
-                GlyphMeta::RowX => (),
-                GlyphMeta::ColY => (),
-            }
-        }
-
-        self.uv1.extend(uv1_buffer);
+        self.uv1.extend(uv1);
     }
 }
 
-impl Drop for ExtractedMesh<'_> {
-    fn drop(&mut self) {
-        use std::mem::take;
+impl ExtractedMesh {
+    /// Apply post processing and sort quads by layer, this does not require a [`Mesh`].
+    pub fn finish(&mut self, post: &MeshPostProcess) {
+        self.post_process_uv1(post.uv1, post.bb_min, post.dimension);
+        if let Some(glyph_meta) = post.glyph_meta {
+            self.post_process_glyph_meta(glyph_meta, post.bb_min, post.dimension);
+        }
+        match post.scale {
+            Some(scale) => self.translate(|v| *v = (*v + post.offset) * scale),
+            None => self.translate(|v| *v += post.offset),
+        }
+        if let Some(atlas_size) = post.atlas_size {
+            self.pixel_to_uv(atlas_size);
+        }
         self.sort.sort_by_key(|x| x.0);
         if self.layer_offset != 0.0 {
             let mut offset = 0.0;
@@ -229,32 +357,219 @@ impl Drop for ExtractedMesh<'_> {
         }
         self.indices
             .extend(self.sort.drain(..).flat_map(|(_, v)| v));
+    }
+
+    /// Hash of the vertex data, must be called after [`ExtractedMesh::finish`].
+    pub fn hash(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        let floats = self
+            .positions
+            .iter()
+            .flatten()
+            .chain(self.uv0.iter().flatten())
+            .chain(self.uv1.iter().flatten())
+            .chain(self.colors.iter().flatten())
+            .chain(self.glyph_meta.iter().flatten());
+        for x in floats {
+            x.to_bits().hash(&mut hasher);
+        }
+        self.indices.hash(&mut hasher);
+        self.tangents.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Bounding box of the vertices, must be called after [`ExtractedMesh::finish`].
+    pub fn aabb(&self) -> Option<Aabb> {
+        Aabb::enclosing(self.positions.iter().map(|p| Vec3::from_array(*p)))
+    }
+
+    /// Write the vertex data into a [`Mesh`], must be called after [`ExtractedMesh::finish`].
+    pub fn write(self, mesh: &mut Mesh) {
+        if self.tangents {
+            let len = self.positions.len().max(3);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, vec![TEXT_TANGENT; len]);
+        } else {
+            mesh.remove_attribute(Mesh::ATTRIBUTE_TANGENT);
+        }
         if !self.positions.is_empty() {
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_POSITION, take(&mut self.positions));
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_NORMAL, take(&mut self.normals));
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_COLOR, take(&mut self.colors));
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_UV_0, take(&mut self.uv0));
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_UV_1, take(&mut self.uv1));
-            self.mesh
-                .insert_indices(Indices::U16(take(&mut self.indices)));
+            let len = self.positions.len();
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uv0);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, self.uv1);
+            // Quads emitted without metadata, i.e. by `Text3dMeshBuilder`, disable the attribute.
+            if self.glyph_meta.len() == len {
+                mesh.insert_attribute(ATTRIBUTE_GLYPH_META, self.glyph_meta);
+            } else {
+                mesh.remove_attribute(ATTRIBUTE_GLYPH_META);
+            }
+            // Switch to `U32` when vertices exceed the range of `U16`, i.e. past 16384 quads.
+            if len <= u16::MAX as usize + 1 {
+                mesh.insert_indices(Indices::U16(
+                    self.indices.into_iter().map(|i| i as u16).collect(),
+                ));
+            } else {
+                mesh.insert_indices(Indices::U32(self.indices));
+            }
         } else {
             // Placeholder, since empty mesh panics on some platforms.
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0, 0.0, 0.0]; 3]);
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 3]);
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![[0.0, 0.0, 0.0, 0.0]; 3]);
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; 3]);
-            self.mesh
-                .insert_attribute(Mesh::ATTRIBUTE_UV_1, vec![[0.0, 0.0]; 3]);
-            self.mesh.insert_indices(Indices::U16(vec![0, 1, 2]));
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0, 0.0, 0.0]; 3]);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 1.0, 0.0]; 3]);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![[0.0, 0.0, 0.0, 0.0]; 3]);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; 3]);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, vec![[0.0, 0.0]; 3]);
+            mesh.insert_indices(Indices::U16(vec![0, 1, 2]));
         }
     }
 }
+
+/// Rescale uvs normalized to an atlas of size `from` to an atlas of size `to`.
+pub(crate) fn rescale_uvs(uvs: &mut [[f32; 2]], from: IVec2, to: IVec2) {
+    for [x, y] in uvs {
+        *x *= from.x as f32 / to.x as f32;
+        *y *= from.y as f32 / to.y as f32;
+    }
+}
+
+/// Hash the vertex attributes and indices of a mesh.
+pub(crate) fn hash_mesh(mesh: &Mesh) -> u64 {
+    let mut hasher = FxHasher::default();
+    for (attribute, values) in mesh.attributes() {
+        attribute.id.hash(&mut hasher);
+        values.get_bytes().hash(&mut hasher);
+    }
+    if let Some(indices) = mesh.indices() {
+        for index in indices.iter() {
+            index.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Returns true if two meshes have the same topology, vertex attributes and indices.
+pub(crate) fn mesh_eq(a: &Mesh, b: &Mesh) -> bool {
+    let indices_eq = match (a.indices(), b.indices()) {
+        (Some(x), Some(y)) => x.len() == y.len() && x.iter().eq(y.iter()),
+        (None, None) => true,
+        _ => false,
+    };
+    a.primitive_topology() == b.primitive_topology()
+        && indices_eq
+        && a.attributes()
+            .map(|(attribute, values)| (attribute.id, values.get_bytes()))
+            .eq(b
+                .attributes()
+                .map(|(attribute, values)| (attribute.id, values.get_bytes())))
+}
+
+/// Meshes shared between text entities that produce identical output,
+/// used when [`Text3dPlugin::share_identical_meshes`](crate::Text3dPlugin::share_identical_meshes) is set.
+///
+/// Every written mesh is registered as a candidate by its hash,
+/// a mesh only becomes shared once a second entity produces the same content.
+#[derive(Debug, Resource, Default)]
+pub(crate) struct SharedMeshes {
+    by_hash: FxHashMap<u64, Vec<Handle<Mesh>>>,
+    by_id: FxHashMap<AssetId<Mesh>, u64>,
+    shared: FxHashSet<AssetId<Mesh>>,
+}
+
+impl SharedMeshes {
+    /// Returns true if a mesh is used by multiple entities and must not be modified.
+    pub fn is_shared(&self, id: AssetId<Mesh>) -> bool {
+        self.shared.contains(&id)
+    }
+
+    /// Remove meshes no longer used by any entity,
+    /// and stop sharing meshes only used by a single entity.
+    pub fn cleanup(&mut self) {
+        for handles in self.by_hash.values_mut() {
+            handles.retain(|handle| {
+                let count = match handle {
+                    Handle::Strong(arc) => std::sync::Arc::strong_count(arc),
+                    Handle::Weak(_) => 0,
+                };
+                if count <= 2 {
+                    self.shared.remove(&handle.id());
+                }
+                if count <= 1 {
+                    self.by_id.remove(&handle.id());
+                }
+                count > 1
+            });
+        }
+        self.by_hash.retain(|_, handles| !handles.is_empty());
+    }
+
+    /// Register a mesh after it is written,
+    /// returns a different handle if a mesh with identical content already exists.
+    pub fn share(
+        &mut self,
+        hash: u64,
+        handle: &Handle<Mesh>,
+        meshes: &Assets<Mesh>,
+    ) -> Option<Handle<Mesh>> {
+        // Meshes not yet shared are modified in place, so the previous hash might be stale.
+        if let Some(previous) = self.by_id.remove(&handle.id()) {
+            if let Some(handles) = self.by_hash.get_mut(&previous) {
+                handles.retain(|x| x.id() != handle.id());
+            }
+        }
+        let mesh = meshes.get(handle.id())?;
+        let candidates = self.by_hash.entry(hash).or_default();
+        if let Some(existing) = candidates
+            .iter()
+            .find(|x| meshes.get(x.id()).is_some_and(|x| mesh_eq(x, mesh)))
+        {
+            self.shared.insert(existing.id());
+            return Some(existing.clone());
+        }
+        candidates.push(handle.clone());
+        self.by_id.insert(handle.id(), hash);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::{Assets, RenderAssetUsages},
+        render::mesh::{Mesh, PrimitiveTopology},
+    };
+
+    use super::{hash_mesh, SharedMeshes};
+
+    fn quad(x: f32) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[x, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+        )
+    }
+
+    #[test]
+    fn share_on_second_match() {
+        let mut meshes = Assets::<Mesh>::default();
+        let mut shared = SharedMeshes::default();
+        let a = meshes.add(quad(0.0));
+        let b = meshes.add(quad(0.0));
+        let c = meshes.add(quad(0.5));
+
+        assert_eq!(shared.share(hash_mesh(&quad(0.0)), &a, &meshes), None);
+        assert!(!shared.is_shared(a.id()));
+
+        assert_eq!(
+            shared.share(hash_mesh(&quad(0.0)), &b, &meshes),
+            Some(a.clone())
+        );
+        assert!(shared.is_shared(a.id()));
+
+        // Same hash but different content is never shared.
+        assert_eq!(shared.share(hash_mesh(&quad(0.0)), &c, &meshes), None);
+        assert!(!shared.is_shared(c.id()));
+    }
+}
@@ -1,10 +1,17 @@
 use bevy::{
-    ecs::component::Component,
+    color::Srgba,
+    ecs::{component::Component, entity::Entity, event::Event},
     math::{IVec2, Vec2},
+    render::{mesh::MeshVertexAttribute, render_resource::VertexFormat},
 };
 use cosmic_text::{Style as CosmicStyle, Weight as CosmicWeight};
-use std::ops::{Deref, DerefMut};
-use zeno::Join;
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+use zeno::{Cap, Join};
+
+use crate::patch::PatchState;
 
 #[cfg(feature = "reflect")]
 use bevy::{
@@ -12,9 +19,81 @@ use bevy::{
     prelude::{Reflect, ReflectDefault},
 };
 
+/// Where lines of text can be broken when exceeding [`Text3dBounds`], by default [`TextWrap::WordOrGlyph`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextWrap {
+    /// No wrapping.
+    None,
+    /// Break between any glyphs.
+    Glyph,
+    /// Break between words.
+    Word,
+    /// Break between words, or between glyphs if a word does not fit on a line.
+    #[default]
+    WordOrGlyph,
+}
+
+impl From<TextWrap> for cosmic_text::Wrap {
+    fn from(val: TextWrap) -> Self {
+        match val {
+            TextWrap::None => cosmic_text::Wrap::None,
+            TextWrap::Glyph => cosmic_text::Wrap::Glyph,
+            TextWrap::Word => cosmic_text::Wrap::Word,
+            TextWrap::WordOrGlyph => cosmic_text::Wrap::WordOrGlyph,
+        }
+    }
+}
+
+/// A dilated and blurred copy of a glyph drawn behind it, see [`Text3dStyling::glow`](crate::Text3dStyling::glow).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlowStyle {
+    pub color: Srgba,
+    /// How far the glow extends from the glyph, in the same unit as [`Text3dStyling::size`](crate::Text3dStyling::size).
+    pub radius: f32,
+    /// Multiplies the glow's opacity before clamping, values above `1` make the glow denser.
+    pub intensity: f32,
+}
+
+impl GlowStyle {
+    pub const fn new(color: Srgba, radius: f32) -> Self {
+        GlowStyle {
+            color,
+            radius,
+            intensity: 1.0,
+        }
+    }
+
+    pub const fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+/// What is drawn in place of a glyph waiting to be rasterized in the background,
+/// see [`Text3dPlugin::glyph_placeholder`](crate::Text3dPlugin::glyph_placeholder).
+///
+/// Text is redrawn with the real glyph once it is cached.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum GlyphPlaceholder {
+    /// Draw nothing.
+    #[default]
+    Invisible,
+    /// Draw a translucent box in the glyph's color.
+    Box,
+    /// Scale the same glyph cached at the closest other font size, which may look blurry,
+    /// draw nothing if not cached at any size.
+    OtherSize,
+}
+
 /// Horizontal align of text.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TextAlign {
     #[default]
     Left,
@@ -32,9 +111,15 @@ impl TextAlign {
     }
 }
 
+/// Vertex attribute with four [`GlyphMeta`] values,
+/// emitted if [`Text3dStyling::glyph_meta`](crate::Text3dStyling::glyph_meta) is set.
+pub const ATTRIBUTE_GLYPH_META: MeshVertexAttribute =
+    MeshVertexAttribute::new("Text3dGlyphMeta", 736201945, VertexFormat::Float32x4);
+
 /// Determines what kind of data each field in `uv1` carry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GlyphMeta {
     /// Left to right count of the glyph, `0`, `1`, etc.
     #[default]
@@ -49,12 +134,21 @@ pub enum GlyphMeta {
     ColY,
     /// The [`SegmentStyle::magic_number`](crate::SegmentStyle::magic_number) field
     MagicNumber,
+    /// Top to bottom count of the glyph's line after wrapping, `0`, `1`, etc.
+    LineIndex,
+    /// Returns x position of the center of a glyph in its line, from `0.0` at the start to `1.0` at the end.
+    LineProgress,
+    /// Count of the glyph's word separated by whitespace, `0`, `1`, etc.
+    WordIndex,
+    /// Returns x position of the center of a glyph in its word, from `0.0` at the start to `1.0` at the end.
+    WordProgress,
 }
 
 /// Determines the maximum width of rendered text, by default infinite.
 #[derive(Debug, Component)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text3dBounds {
     pub width: f32,
 }
@@ -68,6 +162,7 @@ impl Default for Text3dBounds {
 /// Anchor of a text block, usually in `(-0.5, -0.5)..=(0.5, 0.5)`.
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextAnchor(pub Vec2);
 
 impl Deref for TextAnchor {
@@ -110,11 +205,23 @@ pub struct Text3dDimensionOut {
     /// Returns `aabb`'s x and y derived from font's line height.
     pub dimension: Vec2,
     pub(crate) atlas_dimension: IVec2,
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    pub(crate) patch: Option<PatchState>,
 }
 
+/// Overrides [`Text3dPlugin::scale_factor`](crate::Text3dPlugin::scale_factor) for a single text entity,
+/// for example to rasterize a magnified inspection view at a higher resolution.
+///
+/// Glyphs are cached per scale factor, so this does not affect other text sharing the same atlas.
+#[derive(Debug, Component, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct Text3dScaleFactorOverride(pub f32);
+
 /// Allows italic or oblique faces to be selected.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Style {
     /// A face that is neither italic not obliqued.
     Normal,
@@ -154,6 +261,7 @@ impl From<CosmicStyle> for Style {
 /// Specifies the weight of glyphs in the font, their degree of blackness or stroke thickness.
 #[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Debug, Hash)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Weight(pub u16);
 
 impl Default for Weight {
@@ -196,8 +304,111 @@ impl From<CosmicWeight> for Weight {
     }
 }
 
+/// Sent when a [`Text3d`](crate::Text3d) has more glyphs than
+/// [`Text3dStyling::max_glyphs`](crate::Text3dStyling::max_glyphs) and is truncated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct Text3dTruncated {
+    pub entity: Entity,
+    /// Number of glyphs in the layout.
+    pub glyphs: usize,
+    /// Number of glyphs drawn.
+    pub max_glyphs: usize,
+}
+
+/// Sent when a [`FetchedTextSegment`](crate::FetchedTextSegment) used by a [`Text3d`](crate::Text3d)
+/// is updated by [`TextFetch`](crate::TextFetch).
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct TextSegmentUpdated {
+    /// Entity of the [`Text3d`](crate::Text3d).
+    pub entity: Entity,
+    /// Index of the updated segment in [`Text3d::segments`](crate::Text3d::segments).
+    pub segment_index: usize,
+    /// [`SegmentStyle::tag`](crate::SegmentStyle::tag) of the updated segment.
+    pub tag: Option<Arc<str>>,
+}
+
+/// Sent when the mesh of a [`Text3d`](crate::Text3d) is regenerated.
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub struct TextRemeshed {
+    pub entity: Entity,
+    /// Same as [`Text3dDimensionOut::dimension`].
+    pub dimension: Vec2,
+}
+
+/// A 4-byte OpenType feature tag like `liga` or `tnum`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureTag(pub [u8; 4]);
+
+impl FeatureTag {
+    pub const fn new(tag: &[u8; 4]) -> Self {
+        FeatureTag(*tag)
+    }
+
+    /// Kerning (`kern`), adjusts spacing between specific character pairs.
+    pub const KERNING: FeatureTag = FeatureTag::new(b"kern");
+    /// Standard ligatures (`liga`), like `fi` and `fl`.
+    pub const STANDARD_LIGATURES: FeatureTag = FeatureTag::new(b"liga");
+    /// Contextual ligatures (`clig`).
+    pub const CONTEXTUAL_LIGATURES: FeatureTag = FeatureTag::new(b"clig");
+    /// Contextual alternates (`calt`).
+    pub const CONTEXTUAL_ALTERNATES: FeatureTag = FeatureTag::new(b"calt");
+    /// Discretionary ligatures (`dlig`).
+    pub const DISCRETIONARY_LIGATURES: FeatureTag = FeatureTag::new(b"dlig");
+    /// Small caps (`smcp`), lowercase to small capitals.
+    pub const SMALL_CAPS: FeatureTag = FeatureTag::new(b"smcp");
+    /// All small caps (`c2sc`), uppercase to small capitals.
+    pub const ALL_SMALL_CAPS: FeatureTag = FeatureTag::new(b"c2sc");
+    /// Tabular numbers (`tnum`), digits of the same width, useful for counters.
+    pub const TABULAR_NUMBERS: FeatureTag = FeatureTag::new(b"tnum");
+    /// Proportional numbers (`pnum`).
+    pub const PROPORTIONAL_NUMBERS: FeatureTag = FeatureTag::new(b"pnum");
+    /// Oldstyle numbers (`onum`).
+    pub const OLDSTYLE_NUMBERS: FeatureTag = FeatureTag::new(b"onum");
+    /// Slashed zero (`zero`).
+    pub const SLASHED_ZERO: FeatureTag = FeatureTag::new(b"zero");
+}
+
+impl From<FeatureTag> for cosmic_text::FeatureTag {
+    fn from(val: FeatureTag) -> Self {
+        cosmic_text::FeatureTag::new(&val.0)
+    }
+}
+
+/// A 4-byte variable font axis tag like `wght` or `wdth`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AxisTag(pub [u8; 4]);
+
+impl AxisTag {
+    pub const fn new(tag: &[u8; 4]) -> Self {
+        AxisTag(*tag)
+    }
+
+    /// Weight (`wght`), usually `100.0` to `900.0`.
+    pub const WEIGHT: AxisTag = AxisTag::new(b"wght");
+    /// Width (`wdth`), in percentage of the normal width.
+    pub const WIDTH: AxisTag = AxisTag::new(b"wdth");
+    /// Slant (`slnt`), in counter-clockwise degrees.
+    pub const SLANT: AxisTag = AxisTag::new(b"slnt");
+    /// Italic (`ital`), `0.0` or `1.0`.
+    pub const ITALIC: AxisTag = AxisTag::new(b"ital");
+    /// Optical size (`opsz`), in points.
+    pub const OPTICAL_SIZE: AxisTag = AxisTag::new(b"opsz");
+}
+
+impl From<AxisTag> for cosmic_text::ttf_parser::Tag {
+    fn from(val: AxisTag) -> Self {
+        cosmic_text::ttf_parser::Tag::from_bytes(&val.0)
+    }
+}
+
 /// Type of joins between curves.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StrokeJoin {
     #[default]
     Round,
@@ -214,3 +425,89 @@ impl From<StrokeJoin> for Join {
         }
     }
 }
+
+/// Type of caps at the ends of dashes of a stroke, see [`Text3dStyling::stroke_dash`](crate::Text3dStyling::stroke_dash).
+///
+/// Glyph outlines are closed so caps have no effect on solid strokes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StrokeCap {
+    Butt,
+    Square,
+    #[default]
+    Round,
+}
+
+impl From<StrokeCap> for Cap {
+    fn from(val: StrokeCap) -> Self {
+        match val {
+            StrokeCap::Butt => Cap::Butt,
+            StrokeCap::Square => Cap::Square,
+            StrokeCap::Round => Cap::Round,
+        }
+    }
+}
+
+/// Concrete font families used for generic families like `serif` or `monospace`,
+/// if `None`, uses `cosmic_text`'s platform dependent default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct GenericFamilies {
+    pub serif: Option<String>,
+    pub sans_serif: Option<String>,
+    pub monospace: Option<String>,
+    pub cursive: Option<String>,
+    pub fantasy: Option<String>,
+}
+
+impl GenericFamilies {
+    /// Set the family used for `serif`.
+    pub fn with_serif(mut self, family: impl Into<String>) -> Self {
+        self.serif = Some(family.into());
+        self
+    }
+
+    /// Set the family used for `sans-serif`.
+    pub fn with_sans_serif(mut self, family: impl Into<String>) -> Self {
+        self.sans_serif = Some(family.into());
+        self
+    }
+
+    /// Set the family used for `monospace`.
+    pub fn with_monospace(mut self, family: impl Into<String>) -> Self {
+        self.monospace = Some(family.into());
+        self
+    }
+
+    /// Set the family used for `cursive`.
+    pub fn with_cursive(mut self, family: impl Into<String>) -> Self {
+        self.cursive = Some(family.into());
+        self
+    }
+
+    /// Set the family used for `fantasy`.
+    pub fn with_fantasy(mut self, family: impl Into<String>) -> Self {
+        self.fantasy = Some(family.into());
+        self
+    }
+
+    /// Apply the families to a font [`Database`](cosmic_text::fontdb::Database).
+    pub fn apply(&self, db: &mut cosmic_text::fontdb::Database) {
+        if let Some(family) = &self.serif {
+            db.set_serif_family(family.clone());
+        }
+        if let Some(family) = &self.sans_serif {
+            db.set_sans_serif_family(family.clone());
+        }
+        if let Some(family) = &self.monospace {
+            db.set_monospace_family(family.clone());
+        }
+        if let Some(family) = &self.cursive {
+            db.set_cursive_family(family.clone());
+        }
+        if let Some(family) = &self.fantasy {
+            db.set_fantasy_family(family.clone());
+        }
+    }
+}
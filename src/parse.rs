@@ -1,4 +1,12 @@
-use std::{iter::repeat_n, num::NonZeroU32, str::FromStr};
+use std::{
+    fmt::Display,
+    iter::{repeat_n, Peekable},
+    num::NonZeroU32,
+    str::{Chars, FromStr},
+    sync::Arc,
+};
+
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     color_table::parse_color,
@@ -10,25 +18,6 @@ trait Flip {
     fn flip(&mut self);
 }
 
-impl Flip for Option<Weight> {
-    fn flip(&mut self) {
-        *self = match *self {
-            Some(w) if w <= Weight::NORMAL => Some(Weight::BOLD),
-            None => Some(Weight::BOLD),
-            _ => Some(Weight::NORMAL),
-        }
-    }
-}
-
-impl Flip for Option<Style> {
-    fn flip(&mut self) {
-        *self = match *self {
-            Some(Style::Normal) | None => Some(Style::Italic),
-            _ => Some(Style::Italic),
-        }
-    }
-}
-
 impl Flip for Option<bool> {
     fn flip(&mut self) {
         *self = match *self {
@@ -42,7 +31,7 @@ impl Text3d {
     /// Call [`Text3d::parse`] with no custom parsing functions.
     ///
     /// Only standard styles are supported, see [`Text3d::parse`] for details.
-    pub fn parse_raw(text: &str) -> Result<Self, ParseError> {
+    pub fn parse_raw(text: &str) -> Result<Self, SpannedParseError> {
         Text3d::parse(
             text,
             |command| Err(ParseError::BadCommand(command.into())),
@@ -54,8 +43,8 @@ impl Text3d {
     ///
     /// # Example
     ///
-    /// ```
-    /// "Deals **{blue:{damage_number}}** {red:fire} damage to the enemy."
+    /// ```text
+    /// Deals **{blue:{damage_number}}** {red:fire} damage to the enemy.
     /// ```
     ///
     /// # Syntax
@@ -84,7 +73,12 @@ impl Text3d {
     /// * `s-4` Sets stroke to a number.
     /// * `s-red` Parses color names as stroke color.
     /// * `v-4.0` Sets the `magic_number` field.
+    /// * `p-4` Sets the `padding` field.
+    /// * `z-1` Sets the `layer` field, `z--1` draws below other segments.
     /// * `f-Roboto` Sets the font to Roboto.
+    /// * `nowrap` Prevents line breaks at spaces.
+    /// * `tag(shop_item_3)` Sets the `tag` field, reported when hit-testing the segment's glyphs.
+    /// * `ruby(かんじ)` Draws a ruby annotation (furigana) above the segment, i.e. `{ruby(かんじ):漢字}`.
     ///
     /// ## Dynamic value
     ///
@@ -96,15 +90,54 @@ impl Text3d {
     /// The result should either be a string fetched from the world
     /// or an [`Entity`](bevy::ecs::entity::Entity) with a [`FetchedTextSegment`](crate::FetchedTextSegment) component.
     ///
+    /// ## Inline image
+    ///
+    /// ```md
+    /// {image: gold_coin}
+    /// ```
+    ///
+    /// Inserts an icon registered in [`Text3dIcons`](crate::Text3dIcons), laid out as a single glyph.
+    ///
+    /// ## Localized value
+    ///
+    /// ```md
+    /// {loc: key.path}
+    /// ```
+    ///
+    /// Passed to the `fetch_string` function as `loc:key.path`,
+    /// with the `fluent` feature, use `fetch_localized` to handle these values.
     ///
     /// ## Markdown
     ///
     /// A subset of markdown features are supported:
     /// * `*emphasis*`
     /// * `**strong**`
+    /// * `***strong emphasis***`
     /// * `__underline__`
     /// * `~~strikethrough~~`
     /// * `\*` escape character
+    /// * `` `code` `` uses the `monospace` family and the `code` drawer,
+    ///   register a [`Text3dDrawer`](crate::Text3dDrawer) named `code` to draw a background,
+    ///   a backtick without a closing backtick is kept as text.
+    /// * `# header` to `###### header` at the start of a line are bold
+    ///   and joined with the `h1` to `h6` styles from `stylesheet` if they exist, i.e. to set their size.
+    /// * `[text](target)` is underlined and joined with the `link` style if it exists,
+    ///   `target` is set as the `tag` of the text, reported by [`Text3dHit::tag`](crate::Text3dHit::tag) when clicked,
+    ///   styles opened inside `[text]` must be closed before `]`.
+    /// * `- item` at the start of a line is drawn as a bullet, wrapped lines of the item are indented after the bullet.
+    ///
+    /// ## Escaping
+    ///
+    /// Any character preceded by `\` is kept as text, i.e. `\{`, `\}`, `\*`, `\_`, `\~`, `\#` and `\\`,
+    /// this also applies inside `{}` like `{image: a\}b}`, but not inside `` `code` ``.
+    /// Outside of `{}`, `{{` is also kept as `{` at any depth, and `}}` is kept as `}` outside of styles.
+    /// Inside styles `}}` closes two styles, use `\}` for a literal `}`.
+    /// `{/}` closes the innermost style, `{/bold}` also checks its name.
+    /// Use [`escape_rich_text`] on user generated content before inserting it into rich text.
+    /// A `}` without an open style or a `{` that is never closed is a [`ParseError::BracketMismatch`].
+    ///
+    /// Emphasis follows CommonMark's delimiter run rules, so `*` runs can be nested
+    /// like `***a** b*` and unmatched or space surrounded `*` are kept as text.
     ///
     /// ## Inputs
     ///
@@ -114,16 +147,63 @@ impl Text3d {
     /// * `stylesheet`: Parses strings as [`SegmentStyle`].
     ///
     /// We trim whitespaces before passing arguments to these functions.
+    /// The `h1` to `h6` and `link` styles are skipped if `stylesheet` returns [`ParseError::MissingStyle`],
+    /// other errors are returned.
+    ///
+    /// Errors are returned as [`SpannedParseError`] with the location of the character that caused them.
     pub fn parse(
+        text: &str,
+        fetch_string: impl FnMut(&str) -> Result<Text3dSegment, ParseError>,
+        stylesheet: impl FnMut(&str) -> Result<SegmentStyle, ParseError>,
+    ) -> Result<Self, SpannedParseError> {
+        Self::parse_spanned(text, fetch_string, stylesheet, false)
+    }
+
+    /// Parse rich text like [`Text3d::parse`], but style scopes must be closed by name,
+    /// for long documents where a misplaced `}` is hard to find.
+    ///
+    /// ```md
+    /// {red: Deals {bold: 10 {/bold} damage. {/red}
+    /// ```
+    ///
+    /// `{/red}` closes the scope opened by `{red:`, `{/}` closes the innermost scope regardless of its name.
+    /// Unlike [`Text3d::parse`], a bare `}` closing a scope, mismatched names or unclosed scopes are errors
+    /// that report where the scope was opened.
+    pub fn parse_strict(
+        text: &str,
+        fetch_string: impl FnMut(&str) -> Result<Text3dSegment, ParseError>,
+        stylesheet: impl FnMut(&str) -> Result<SegmentStyle, ParseError>,
+    ) -> Result<Self, SpannedParseError> {
+        Self::parse_spanned(text, fetch_string, stylesheet, true)
+    }
+
+    /// Parse and add the location of errors.
+    fn parse_spanned(
+        text: &str,
+        fetch_string: impl FnMut(&str) -> Result<Text3dSegment, ParseError>,
+        stylesheet: impl FnMut(&str) -> Result<SegmentStyle, ParseError>,
+        strict: bool,
+    ) -> Result<Self, SpannedParseError> {
+        let mut offset = 0;
+        Self::parse_with_offset(text, fetch_string, stylesheet, strict, &mut offset)
+            .map_err(|error| SpannedParseError::new(error, ParseSpan::new(text, offset)))
+    }
+
+    /// Parse while writing the byte offset of the current character to `offset`.
+    fn parse_with_offset(
         text: &str,
         mut fetch_string: impl FnMut(&str) -> Result<Text3dSegment, ParseError>,
         mut stylesheet: impl FnMut(&str) -> Result<SegmentStyle, ParseError>,
+        strict: bool,
+        offset: &mut usize,
     ) -> Result<Self, ParseError> {
         #[derive(Debug, Clone, Copy)]
         enum ParseState {
             Text,
             Command,
             Image,
+            Localize,
+            Code,
         }
 
         let mut buffer = String::new();
@@ -139,30 +219,90 @@ impl Text3d {
             };
         }
         use ParseState::*;
-        let mut iter = text.chars().peekable();
+        let mut iter = Cursor {
+            chars: text.chars().peekable(),
+            offset: 0,
+        };
+        let mut delimiters = Vec::new();
+        let mut prev = None;
+        let mut line_start = true;
+        let mut header = false;
+        // Index of the first segment, depth of `styles` and offset of the `[` of the current link.
+        let mut link: Option<(usize, usize, usize)> = None;
+        // Offset of the current `{`.
+        let mut command_start = 0;
+        // Names and offsets of open style scopes.
+        let mut scopes: Vec<(String, usize)> = Vec::new();
+        let scope_error =
+            |expected: String, opened: usize, found: String| ParseError::ScopeMismatch {
+                expected,
+                found,
+                opened: ParseSpan::new(text, opened),
+            };
+        // Error if a closing bracket would close the style of the current link.
+        let check_link = |link: Option<(usize, usize, usize)>, depth: usize, found: &str| match link
+        {
+            Some((_, link_depth, opened)) if depth <= link_depth => {
+                Err(scope_error("link".into(), opened, found.into()))
+            }
+            _ => Ok(()),
+        };
         while let Some(c) = iter.next() {
+            *offset = iter.offset - c.len_utf8();
+            let was_line_start = std::mem::replace(&mut line_start, false);
             match (c, state) {
+                ('{', Text) if iter.next_if_eq(&'{').is_some() => buffer.push('{'),
+                // `}}` closes two styles inside a style.
+                ('}', Text) if scopes.is_empty() && iter.next_if_eq(&'}').is_some() => {
+                    buffer.push('}')
+                }
+                ('\\', Command | Image | Localize) => match iter.next() {
+                    Some(c) => buffer.push(c),
+                    None => buffer.push('\\'),
+                },
                 ('{', Text) => {
                     push_segment(&buffer, &mut segments, &mut styles)?;
                     buffer.clear();
+                    command_start = *offset;
                     state = Command;
                 }
+                ('}', Command) if buffer.trim_start().starts_with('/') => {
+                    let name = buffer.trim().trim_start_matches('/').trim_start();
+                    check_link(link, styles.len(), &format!("{{/{name}}}"))?;
+                    let (expected, opened) = scopes.pop().ok_or(ParseError::BracketMismatch)?;
+                    if !name.is_empty() && name != expected {
+                        return Err(scope_error(expected, opened, format!("{{/{name}}}")));
+                    }
+                    let _ = styles.pop();
+                    buffer.clear();
+                    state = Text;
+                }
                 (':', Command) => match buffer.trim().split(",").collect::<Vec<_>>().as_slice() {
                     ["image"] => {
                         buffer.clear();
                         state = Image;
                     }
+                    ["loc"] => {
+                        buffer.clear();
+                        state = Localize;
+                    }
                     style_slice => {
                         let mut style = style!().clone();
                         for s in style_slice {
                             style = style.join(parse_style(s.trim(), &mut stylesheet)?)
                         }
                         styles.push(style);
+                        scopes.push((buffer.trim().into(), command_start));
                         buffer.clear();
                         state = Text;
                     }
                 },
                 ('}', Text) => {
+                    check_link(link, styles.len(), "}")?;
+                    let (expected, opened) = scopes.pop().ok_or(ParseError::BracketMismatch)?;
+                    if strict {
+                        return Err(scope_error(expected, opened, "}".into()));
+                    }
                     push_segment(&buffer, &mut segments, &mut styles)?;
                     buffer.clear();
                     let _ = styles.pop();
@@ -173,30 +313,31 @@ impl Text3d {
                     state = Text;
                 }
                 ('}', Image) => {
-                    return Err(ParseError::NotSupported("image"));
+                    segments.push((Text3dSegment::Image(buffer.trim().into()), style!().clone()));
+                    buffer.clear();
+                    state = Text;
+                }
+                ('}', Localize) => {
+                    let key = format!("loc:{}", buffer.trim());
+                    segments.push((fetch_string(&key)?, style!().clone()));
+                    buffer.clear();
+                    state = Text;
                 }
                 ('*', Text) => {
                     push_segment(&buffer, &mut segments, &mut styles)?;
                     buffer.clear();
                     let mut stars = 1;
-                    while let Some(c) = iter.peek() {
-                        if *c == '*' {
-                            stars += 1;
-                            iter.next();
-                        } else {
-                            break;
-                        }
-                    }
-                    match stars {
-                        1 => style!(mut).style.flip(),
-                        2 => style!(mut).weight.flip(),
-                        3 => {
-                            style!(mut).style.flip();
-                            style!(mut).weight.flip();
-                        }
-                        n if n % 2 == 0 => (),
-                        _ => style!(mut).style.flip(),
+                    while iter.next_if_eq(&'*').is_some() {
+                        stars += 1;
                     }
+                    delimiters.push(Delimiter::new(
+                        segments.len(),
+                        stars,
+                        prev,
+                        iter.peek().copied(),
+                    ));
+                    // Replaced by unmatched stars after all delimiters are known.
+                    segments.push((Text3dSegment::String(String::new()), style!().clone()));
                 }
                 ('_', Text) if iter.peek() == Some(&'_') => {
                     push_segment(&buffer, &mut segments, &mut styles)?;
@@ -210,7 +351,72 @@ impl Text3d {
                     iter.next();
                     style!(mut).strikethrough.flip()
                 }
-                (c, Command | Image) => buffer.push(c),
+                // An unmatched backtick is kept as text.
+                ('`', Text) if iter.clone().any(|c| c == '`') => {
+                    push_segment(&buffer, &mut segments, &mut styles)?;
+                    buffer.clear();
+                    state = Code;
+                }
+                ('`', Code) => {
+                    let mut style = style!().clone();
+                    style.font = Some("monospace".into());
+                    style.drawers.push("code".into());
+                    segments.push((Text3dSegment::String(std::mem::take(&mut buffer)), style));
+                    state = Text;
+                }
+                ('#', Text) if was_line_start => {
+                    let mut level = 1;
+                    while iter.next_if_eq(&'#').is_some() {
+                        level += 1;
+                    }
+                    if level <= 6 && iter.next_if_eq(&' ').is_some() {
+                        push_segment(&buffer, &mut segments, &mut styles)?;
+                        buffer.clear();
+                        let bold = SegmentStyle {
+                            weight: Some(Weight::BOLD),
+                            ..Default::default()
+                        };
+                        let heading = optional_style(&mut stylesheet, &format!("h{level}"))?;
+                        styles.push(style!().join(bold).join(heading));
+                        header = true;
+                    } else {
+                        buffer.extend(repeat_n('#', level));
+                    }
+                }
+                ('-', Text) if was_line_start && iter.next_if_eq(&' ').is_some() => {
+                    buffer.push_str(BULLET);
+                }
+                ('[', Text) if link.is_none() && is_link(iter.clone()) => {
+                    push_segment(&buffer, &mut segments, &mut styles)?;
+                    buffer.clear();
+                    let underline = SegmentStyle {
+                        underline: Some(true),
+                        ..Default::default()
+                    };
+                    let link_style = optional_style(&mut stylesheet, "link")?;
+                    styles.push(style!().join(underline).join(link_style));
+                    link = Some((segments.len(), styles.len(), *offset));
+                }
+                (']', Text) if link.is_some() => {
+                    if let Some((_, depth, _)) = link {
+                        if styles.len() > depth {
+                            let (expected, opened) =
+                                scopes.pop().ok_or(ParseError::BracketMismatch)?;
+                            return Err(scope_error(expected, opened, "]".into()));
+                        }
+                    }
+                    push_segment(&buffer, &mut segments, &mut styles)?;
+                    buffer.clear();
+                    let _ = styles.pop();
+                    iter.next();
+                    let target: String = iter.by_ref().take_while(|c| *c != ')').collect();
+                    let tag: Arc<str> = target.trim().into();
+                    let start = link.take().map(|(start, ..)| start).unwrap_or_default();
+                    for (_, style) in &mut segments[start..] {
+                        style.tag = Some(tag.clone());
+                    }
+                }
+                (c, Command | Image | Localize | Code) => buffer.push(c),
                 ('\\', Text) => {
                     if let Some(c) = iter.peek() {
                         buffer.push(*c);
@@ -229,6 +435,13 @@ impl Text3d {
                         }
                         iter.next();
                     }
+                    if linebreaks > 0 && header {
+                        push_segment(&buffer, &mut segments, &mut styles)?;
+                        buffer.clear();
+                        let _ = styles.pop();
+                        header = false;
+                    }
+                    line_start = was_line_start || linebreaks > 0;
                     match linebreaks {
                         0 => buffer.push(' '),
                         n => buffer.extend(repeat_n('\n', n)),
@@ -238,10 +451,113 @@ impl Text3d {
                     buffer.push(c);
                 }
             }
+            prev = Some(c);
+        }
+        *offset = text.len();
+        if matches!(state, Command | Image | Localize) {
+            return Err(ParseError::BracketMismatch);
+        }
+        if let Some((name, opened)) = scopes.pop().filter(|_| strict) {
+            return Err(ParseError::UnclosedScope {
+                name,
+                opened: ParseSpan::new(text, opened),
+            });
         }
         push_segment(&buffer, &mut segments, &mut styles)?;
+        process_emphasis(&mut segments, &mut delimiters);
+        segments
+            .retain(|(segment, _)| !matches!(segment, Text3dSegment::String(s) if s.is_empty()));
         Ok(Text3d { segments })
     }
+
+    /// Parse many strings sharing the same functions, see [`Text3d::parse`],
+    /// for loading large amounts of text like item descriptions at startup.
+    ///
+    /// `stylesheet` is only called once per distinct style name,
+    /// and identical font names and tags share the same [`Arc<str>`].
+    pub fn parse_batch<'t>(
+        texts: impl IntoIterator<Item = &'t str>,
+        mut fetch_string: impl FnMut(&str) -> Result<Text3dSegment, ParseError>,
+        mut stylesheet: impl FnMut(&str) -> Result<SegmentStyle, ParseError>,
+    ) -> Vec<Result<Self, SpannedParseError>> {
+        let mut styles = FxHashMap::<String, Result<SegmentStyle, ParseError>>::default();
+        let mut interned = FxHashSet::<Arc<str>>::default();
+        let mut intern = |s: &mut Option<Arc<str>>| {
+            if let Some(s) = s {
+                match interned.get(&**s) {
+                    Some(x) => *s = x.clone(),
+                    None => {
+                        interned.insert(s.clone());
+                    }
+                }
+            }
+        };
+        texts
+            .into_iter()
+            .map(|text| {
+                let mut result = Text3d::parse(text, &mut fetch_string, |name| {
+                    if let Some(style) = styles.get(name) {
+                        return style.clone();
+                    }
+                    let style = stylesheet(name);
+                    styles.insert(name.to_owned(), style.clone());
+                    style
+                })?;
+                for (_, style) in &mut result.segments {
+                    intern(&mut style.font);
+                    intern(&mut style.tag);
+                }
+                Ok(result)
+            })
+            .collect()
+    }
+}
+
+/// Escape a string so [`Text3d::parse`] displays it as is,
+/// i.e. for user generated content inserted into rich text.
+///
+/// Every special character is escaped with `\`, including `{` and `}`, so the result is valid
+/// at any depth inside styles. Whitespace other than a single space or line break
+/// is also escaped so it is not collapsed.
+pub fn escape_rich_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut prev_whitespace = false;
+    for c in text.chars() {
+        if matches!(
+            c,
+            '{' | '}' | '*' | '_' | '~' | '`' | '[' | ']' | '#' | '-' | '\\'
+        ) || (c.is_whitespace() && (prev_whitespace || !matches!(c, ' ' | '\n')))
+        {
+            result.push('\\');
+        }
+        prev_whitespace = c.is_whitespace();
+        result.push(c);
+    }
+    result
+}
+
+/// Drawn in place of `- ` at the start of a line.
+pub(crate) const BULLET: &str = "\u{2022} ";
+
+/// Check if the characters after `[` form a markdown link `text](target)` on the same line.
+fn is_link(mut iter: impl Iterator<Item = char>) -> bool {
+    iter.by_ref()
+        .take_while(|c| *c != '\n' && *c != '[')
+        .any(|c| c == ']')
+        && iter.next() == Some('(')
+        && iter.take_while(|c| *c != '\n').any(|c| c == ')')
+}
+
+/// Parse a comma separated list of styles like `red, s-black, s-10` into a single [`SegmentStyle`].
+pub(crate) fn parse_style_list(
+    styles: &str,
+    mut stylesheet: impl FnMut(&str) -> Result<SegmentStyle, ParseError>,
+) -> Result<SegmentStyle, ParseError> {
+    let mut result = SegmentStyle::default();
+    for s in styles.split(',') {
+        result = result.join(parse_style(s.trim(), &mut stylesheet)?);
+    }
+    Ok(result)
 }
 
 fn parse_style(
@@ -257,6 +573,24 @@ fn parse_style(
         } else {
             stylesheet(style)
         }
+    } else if let Some(number) = style.strip_prefix("p-") {
+        if let Ok(padding) = u16::from_str(number) {
+            Ok(SegmentStyle {
+                padding: Some(padding),
+                ..Default::default()
+            })
+        } else {
+            stylesheet(style)
+        }
+    } else if let Some(number) = style.strip_prefix("z-") {
+        if let Ok(layer) = i8::from_str(number) {
+            Ok(SegmentStyle {
+                layer: Some(layer),
+                ..Default::default()
+            })
+        } else {
+            stylesheet(style)
+        }
     } else if let Some(name) = style.strip_prefix("s-") {
         if let Ok(int) = u32::from_str(name) {
             Ok(SegmentStyle {
@@ -271,6 +605,19 @@ fn parse_style(
         } else {
             stylesheet(style)
         }
+    } else if let Some(tag) = style.strip_prefix("tag(").and_then(|x| x.strip_suffix(')')) {
+        Ok(SegmentStyle {
+            tag: Some(tag.trim().into()),
+            ..Default::default()
+        })
+    } else if let Some(ruby) = style
+        .strip_prefix("ruby(")
+        .and_then(|x| x.strip_suffix(')'))
+    {
+        Ok(SegmentStyle {
+            ruby: Some(ruby.trim().into()),
+            ..Default::default()
+        })
     } else if let Some(name) = style.strip_prefix("f-") {
         Ok(SegmentStyle {
             font: Some(name.into()),
@@ -299,11 +646,107 @@ fn parse_style(
                 strikethrough: Some(true),
                 ..Default::default()
             }),
+            "nowrap" => Ok(SegmentStyle {
+                nowrap: Some(true),
+                ..Default::default()
+            }),
             _ => stylesheet(style),
         }
     }
 }
 
+/// A run of `*` in markdown.
+#[derive(Debug, Clone, Copy)]
+struct Delimiter {
+    /// Index of the run's segment.
+    segment: usize,
+    /// Number of stars not matched yet.
+    count: usize,
+    /// Number of stars in the run.
+    length: usize,
+    open: bool,
+    close: bool,
+}
+
+impl Delimiter {
+    /// Determine if a run can open or close emphasis from its surrounding characters,
+    /// the start and end of text are treated as whitespace.
+    fn new(segment: usize, length: usize, prev: Option<char>, next: Option<char>) -> Self {
+        let space = |c: Option<char>| c.is_none_or(char::is_whitespace);
+        let punctuation = |c: Option<char>| c.is_some_and(|c| c.is_ascii_punctuation());
+        let left_flanking =
+            !space(next) && (!punctuation(next) || space(prev) || punctuation(prev));
+        let right_flanking =
+            !space(prev) && (!punctuation(prev) || space(next) || punctuation(next));
+        Delimiter {
+            segment,
+            count: length,
+            length,
+            open: left_flanking,
+            close: right_flanking,
+        }
+    }
+}
+
+/// Match `*` runs as CommonMark emphasis, style the segments between them
+/// and write unmatched stars back as text.
+fn process_emphasis(segments: &mut [(Text3dSegment, SegmentStyle)], delimiters: &mut [Delimiter]) {
+    let mut closer = 0;
+    while closer < delimiters.len() {
+        let c = delimiters[closer];
+        if !c.close || c.count == 0 {
+            closer += 1;
+            continue;
+        }
+        let opener = (0..closer).rev().find(|i| {
+            let o = &delimiters[*i];
+            // The rule of 3, `*a**b*` is not `*a*` and `*b*`.
+            let multiple_of_3 = (o.close || c.open)
+                && (o.length + c.length).is_multiple_of(3)
+                && !(o.length.is_multiple_of(3) && c.length.is_multiple_of(3));
+            o.open && o.count > 0 && !multiple_of_3
+        });
+        let Some(opener) = opener else {
+            closer += 1;
+            continue;
+        };
+        let strong = delimiters[opener].count >= 2 && c.count >= 2;
+        let range = delimiters[opener].segment + 1..c.segment;
+        for (_, style) in &mut segments[range] {
+            if strong {
+                style.weight = Some(Weight::BOLD);
+            } else {
+                style.style = Some(Style::Italic);
+            }
+        }
+        let used = if strong { 2 } else { 1 };
+        delimiters[opener].count -= used;
+        delimiters[closer].count -= used;
+        // Delimiters inside a match can no longer match outside of it.
+        for delimiter in &mut delimiters[opener + 1..closer] {
+            delimiter.open = false;
+            delimiter.close = false;
+        }
+    }
+    for delimiter in delimiters.iter() {
+        if let Some((Text3dSegment::String(s), _)) = segments.get_mut(delimiter.segment) {
+            s.extend(repeat_n('*', delimiter.count));
+        }
+    }
+}
+
+/// Look up a style applied by markdown, i.e. `h1` or `link`,
+/// a [`ParseError::MissingStyle`] means the style is not set and is ignored.
+fn optional_style(
+    stylesheet: &mut impl FnMut(&str) -> Result<SegmentStyle, ParseError>,
+    name: &str,
+) -> Result<SegmentStyle, ParseError> {
+    match stylesheet(name) {
+        Err(ParseError::MissingStyle(_)) => Ok(SegmentStyle::default()),
+        result => result,
+    }
+}
+
 fn push_segment(
     buffer: &str,
     spans: &mut Vec<(Text3dSegment, SegmentStyle)>,
@@ -318,8 +761,72 @@ fn push_segment(
     Ok(())
 }
 
+/// Characters of the parsed text and the byte offset after the last character taken.
+#[derive(Clone)]
+struct Cursor<'t> {
+    chars: Peekable<Chars<'t>>,
+    offset: usize,
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
+
+impl Cursor<'_> {
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    fn next_if_eq(&mut self, expected: &char) -> Option<char> {
+        let c = self.chars.next_if_eq(expected)?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
+
+/// Location of a [`ParseError`] in the parsed text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSpan {
+    /// Byte offset in the text.
+    pub offset: usize,
+    /// Line number, starting from 1.
+    pub line: usize,
+    /// Column in characters, starting from 1.
+    pub column: usize,
+    /// The line containing the error.
+    pub snippet: String,
+}
+
+impl ParseSpan {
+    pub fn new(text: &str, offset: usize) -> Self {
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        ParseSpan {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            snippet: text[line_start..].lines().next().unwrap_or_default().into(),
+        }
+    }
+}
+
+/// Displays the location and the snippet with a `^` under the error.
+impl Display for ParseSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "line {}, column {}:", self.line, self.column)?;
+        writeln!(f, "{}", self.snippet)?;
+        write!(f, "{}^", " ".repeat(self.column - 1))
+    }
+}
+
 /// Error emitted when parsing rich text.
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum ParseError {
     #[error("Feature {0} is not supported.")]
     NotSupported(&'static str),
@@ -331,4 +838,252 @@ pub enum ParseError {
     MissingStyle(String),
     #[error("{0}")]
     Custom(String),
+    #[error("Scope {expected} opened at {opened}\nis closed by {found}.")]
+    ScopeMismatch {
+        expected: String,
+        found: String,
+        opened: ParseSpan,
+    },
+    #[error("Scope {name} opened at {opened}\nis not closed.")]
+    UnclosedScope { name: String, opened: ParseSpan },
+}
+
+/// A [`ParseError`] and the location of the character that caused it, returned by [`Text3d::parse`].
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{error} at {span}")]
+pub struct SpannedParseError {
+    error: Box<ParseError>,
+    span: ParseSpan,
+}
+
+impl SpannedParseError {
+    pub(crate) fn new(error: ParseError, span: ParseSpan) -> Self {
+        SpannedParseError {
+            error: Box::new(error),
+            span,
+        }
+    }
+
+    /// The error without its location.
+    pub fn error(&self) -> &ParseError {
+        &self.error
+    }
+
+    /// The location of the error.
+    pub fn span(&self) -> &ParseSpan {
+        &self.span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        escape_rich_text,
+        misc::{Style, Weight},
+        ParseError, SegmentStyle, SpannedParseError, Text3d, Text3dSegment,
+    };
+
+    fn spans(text: &str) -> Vec<(String, SegmentStyle)> {
+        Text3d::parse_raw(text)
+            .unwrap()
+            .segments
+            .into_iter()
+            .map(|(segment, style)| match segment {
+                Text3dSegment::String(s) => (s, style),
+                segment => panic!("unexpected segment {segment:?}"),
+            })
+            .collect()
+    }
+
+    fn find<'t>(spans: &'t [(String, SegmentStyle)], text: &str) -> &'t SegmentStyle {
+        &spans.iter().find(|(s, _)| s == text).unwrap().1
+    }
+
+    fn is_bold(style: &SegmentStyle) -> bool {
+        style.weight == Some(Weight::BOLD)
+    }
+
+    fn is_italic(style: &SegmentStyle) -> bool {
+        style.style == Some(Style::Italic)
+    }
+
+    #[test]
+    fn nested_emphasis() {
+        let s = spans("***a** b*");
+        assert!(is_bold(find(&s, "a")) && is_italic(find(&s, "a")));
+        assert!(!is_bold(find(&s, " b")) && is_italic(find(&s, " b")));
+
+        let s = spans("*a **b *c **d** c* b** a*");
+        assert!(is_italic(find(&s, "a ")) && !is_bold(find(&s, "a ")));
+        assert!(is_italic(find(&s, "b ")) && is_bold(find(&s, "b ")));
+        assert!(is_italic(find(&s, "c ")) && is_bold(find(&s, "c ")));
+        assert!(is_italic(find(&s, "d")) && is_bold(find(&s, "d")));
+        assert!(is_italic(find(&s, " a")) && !is_bold(find(&s, " a")));
+
+        let s = spans("***bold italic*** mid");
+        assert!(is_bold(find(&s, "bold italic")) && is_italic(find(&s, "bold italic")));
+        assert_eq!(find(&s, " mid"), &SegmentStyle::default());
+    }
+
+    #[test]
+    fn unmatched_emphasis_is_text() {
+        let s = spans("**a*");
+        assert_eq!(s[0], ("*".into(), SegmentStyle::default()));
+        assert!(is_italic(find(&s, "a")));
+
+        let text: String = spans("a * b ** c*** d")
+            .into_iter()
+            .map(|(s, _)| s)
+            .collect();
+        assert_eq!(text, "a * b ** c*** d");
+    }
+
+    #[test]
+    fn deep_style_nesting() {
+        let s = spans("{red: {s-2: {v-1: {p-3: {z-1:x} } } } } y");
+        let x = find(&s, "x");
+        assert!(x.fill_color.is_some());
+        assert_eq!(x.stroke.map(|s| s.get()), Some(2));
+        assert_eq!(x.magic_number, Some(1.0));
+        assert_eq!(find(&s, " y"), &SegmentStyle::default());
+
+        let depth = 200;
+        let text = format!("{}x{}y", "{s-1: *".repeat(depth), "* } ".repeat(depth));
+        let s = spans(&text);
+        assert!(is_italic(find(&s, "x")));
+        assert_eq!(s.last().unwrap(), &(" y".into(), SegmentStyle::default()));
+    }
+
+    #[test]
+    fn mismatched_close_brackets() {
+        for text in ["a}", "a } b", "{red: a} }", "{red: {b", "{image: a"] {
+            let result = Text3d::parse_raw(text).map(|_| ());
+            assert!(
+                matches!(
+                    result.as_ref().map_err(SpannedParseError::error),
+                    Err(ParseError::BracketMismatch)
+                ),
+                "{text}: {result:?}"
+            );
+        }
+        // Unclosed scopes are closed at the end outside of strict mode.
+        assert!(Text3d::parse_raw("{red: a").is_ok());
+        let strict = Text3d::parse_strict(
+            "{red: a",
+            |command| Err(ParseError::BadCommand(command.into())),
+            |style| Err(ParseError::MissingStyle(style.into())),
+        );
+        assert!(matches!(
+            strict.as_ref().map_err(SpannedParseError::error),
+            Err(ParseError::UnclosedScope { .. })
+        ));
+    }
+
+    fn scope_mismatch(text: &str) -> Option<(String, String)> {
+        match Text3d::parse_raw(text)
+            .as_ref()
+            .map_err(SpannedParseError::error)
+        {
+            Err(ParseError::ScopeMismatch {
+                expected, found, ..
+            }) => Some((expected.clone(), found.clone())),
+            _ => None,
+        }
+    }
+
+    fn plain_text(text: &str) -> String {
+        spans(text).into_iter().map(|(s, _)| s).collect()
+    }
+
+    #[test]
+    fn literal_braces() {
+        assert_eq!(plain_text("{{a}} b"), "{a} b");
+        let s = spans("{red: {s-2: {{a\\} }}");
+        let a = find(&s, " {a} ");
+        assert!(a.fill_color.is_some() && a.stroke.is_some());
+        let s = spans("{red: {s-2:a} {/}b");
+        assert!(find(&s, "a").stroke.is_some());
+        assert_eq!(find(&s, "b"), &SegmentStyle::default());
+        let s = spans("{red: a\\}b}");
+        assert_eq!(s[0].0, " a}b");
+        assert!(s[0].1.fill_color.is_some());
+    }
+
+    #[test]
+    fn unmatched_backtick() {
+        assert_eq!(plain_text("It`s {red:x}"), "It`s x");
+        assert!(find(&spans("It`s {red:x}"), "x").fill_color.is_some());
+        let s = spans("a `b` c`d");
+        assert_eq!(find(&s, "b").font.as_deref(), Some("monospace"));
+        assert_eq!(find(&s, " c`d"), &SegmentStyle::default());
+    }
+
+    #[test]
+    fn nested_close() {
+        let s = spans("{red:{s-2:x}}b");
+        let x = find(&s, "x");
+        assert!(x.fill_color.is_some() && x.stroke.is_some());
+        assert_eq!(find(&s, "b"), &SegmentStyle::default());
+        assert_eq!(plain_text("{red:{s-2:x}}b"), "xb");
+        assert!(Text3d::parse_raw("{red:x}}").is_err());
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        let texts = [
+            "{a} }} {{b",
+            "**bold** __u__ ~~s~~ `code` [link](target)",
+            "# header\n- item\n\\ back\\slash",
+            "a  b\n\n\n c\t d",
+            "{red: unclosed",
+            "}",
+        ];
+        for text in texts {
+            let escaped = escape_rich_text(text);
+            assert_eq!(plain_text(&escaped), text);
+            let nested = plain_text(&format!("{{red:{{s-2:{escaped}}} }}"));
+            assert_eq!(nested, format!("{text} "));
+        }
+    }
+
+    #[test]
+    fn link_scopes() {
+        let s = spans("{red: [a {s-2:b} c](target) d}");
+        assert_eq!(find(&s, "b").tag.as_deref(), Some("target"));
+        assert!(find(&s, " d").fill_color.is_some());
+        assert_eq!(find(&s, " d").tag, None);
+
+        assert_eq!(
+            scope_mismatch("[a {red: b](target) c}"),
+            Some(("red".into(), "]".into()))
+        );
+        assert_eq!(
+            scope_mismatch("{red: [a} b](target)"),
+            Some(("link".into(), "}".into()))
+        );
+    }
+
+    #[test]
+    fn markdown_style_errors() {
+        let stylesheet = |name: &str| match name {
+            "h1" => Ok(SegmentStyle {
+                magic_number: Some(1.0),
+                ..Default::default()
+            }),
+            "link" => Err(ParseError::Custom("bad link".into())),
+            name => Err(ParseError::MissingStyle(name.into())),
+        };
+        let fetch = |command: &str| Err(ParseError::BadCommand(command.into()));
+        let text = Text3d::parse("# a\n## b", fetch, stylesheet).unwrap();
+        assert_eq!(text.segments[0].1.magic_number, Some(1.0));
+        let last = text.segments.last().unwrap();
+        assert_eq!(last.0, Text3dSegment::String("b".into()));
+        assert_eq!(last.1.magic_number, None);
+        assert_eq!(last.1.weight, Some(Weight::BOLD));
+        let result = Text3d::parse("[a](target)", fetch, stylesheet);
+        assert!(matches!(
+            result.as_ref().map_err(SpannedParseError::error),
+            Err(ParseError::Custom(_))
+        ));
+    }
 }
@@ -0,0 +1,208 @@
+use bevy::{
+    color::{ColorToComponents, LinearRgba},
+    math::{Rect, Vec2, Vec3},
+    render::mesh::{Mesh, VertexAttributeValues},
+};
+
+use crate::{
+    layers::{DrawRequest, DrawType},
+    text3d::{Text3d, Text3dSegment},
+    GlyphMeta, Text3dLayoutInfo, Text3dStyling,
+};
+
+/// Values of the last full render of a text, used to update its mesh in place
+/// when only colors or alignment change.
+#[derive(Debug, Clone)]
+pub(crate) struct PatchState {
+    /// Styling of the last full render.
+    pub styling: Text3dStyling,
+    /// Offset applied to the mesh before `world_scale`.
+    pub offset: Vec2,
+    /// Height of the text block.
+    pub height: f32,
+    /// Left and right edges of the ink and the advance width of each line, before `offset` is applied.
+    pub lines: Vec<(f32, f32, f32)>,
+}
+
+/// A styling change that can be applied without shaping.
+pub(crate) enum StylingPatch {
+    Colors,
+    Align,
+}
+
+impl Text3dStyling {
+    /// Copy fields that only affect vertex colors from `other`.
+    fn with_colors_of(&self, other: &Text3dStyling) -> Text3dStyling {
+        let mut result = self.clone();
+        result.color = other.color;
+        result.stroke_color = other.stroke_color;
+        if let (Some((color, _)), Some((other, _))) = (&mut result.text_shadow, other.text_shadow) {
+            *color = other;
+        }
+        if result.text_shadows.len() == other.text_shadows.len() {
+            for ((color, ..), (other, ..)) in
+                result.text_shadows.iter_mut().zip(&other.text_shadows)
+            {
+                *color = *other;
+            }
+        }
+        if let (Some(glow), Some(other)) = (&mut result.glow, other.glow) {
+            glow.color = other.color;
+        }
+        result
+    }
+}
+
+impl PatchState {
+    /// Returns the kind of patch that turns the last rendered styling into `styling`.
+    pub fn classify(&self, styling: &Text3dStyling) -> Option<StylingPatch> {
+        if self.styling.with_colors_of(styling) == *styling {
+            return Some(StylingPatch::Colors);
+        }
+        let mut aligned = self.styling.clone();
+        aligned.align = styling.align;
+        (aligned == *styling).then_some(StylingPatch::Align)
+    }
+}
+
+/// Returns true if every vertex of the mesh belongs to a glyph in `info`,
+/// which excludes ruby annotations and editor decorations.
+fn covered_by_glyphs(info: &Text3dLayoutInfo, vertices: usize) -> bool {
+    info.glyphs.iter().map(|x| x.vertices.len()).sum::<usize>() == vertices
+}
+
+/// Recolor the mesh of a text from its draw requests, returns false if the mesh
+/// does not match the requests and must be redrawn.
+pub(crate) fn patch_colors(
+    mesh: &mut Mesh,
+    info: &Text3dLayoutInfo,
+    text: &Text3d,
+    styling: &Text3dStyling,
+    requests: &mut Vec<DrawRequest>,
+) -> bool {
+    // Faded vertices and custom drawers have colors not derived from the draw requests.
+    if styling.overflow_fade.is_some() || !styling.drawers.is_empty() {
+        return false;
+    }
+    let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+    else {
+        return false;
+    };
+    if !covered_by_glyphs(info, colors.len()) {
+        return false;
+    }
+    let mut patched = Vec::with_capacity(colors.len());
+    for glyph in &info.glyphs {
+        let Some((segment, attrs)) = text.segments.get(glyph.segment) else {
+            return false;
+        };
+        // Icons are not colored by the styling.
+        if matches!(segment, Text3dSegment::Image(_)) {
+            patched.extend_from_slice(&colors[glyph.vertices.clone()]);
+            continue;
+        }
+        if glyph.vertices.is_empty() {
+            continue;
+        }
+        if !attrs.drawers.is_empty() {
+            return false;
+        }
+        styling.fill_draw_requests(attrs, None, requests);
+        // Each request draws a single quad, unless a decoration line spans multiple quads.
+        if requests.len() * 4 != glyph.vertices.len()
+            || requests
+                .iter()
+                .any(|x| matches!(x.request, DrawType::Line(..) | DrawType::Custom(_)))
+        {
+            return false;
+        }
+        for request in requests.drain(..) {
+            patched.extend([LinearRgba::from(request.color).to_f32_array(); 4]);
+        }
+    }
+    *colors = patched;
+    true
+}
+
+/// Move the lines of a text to a new alignment, returns the new dimension
+/// or `None` if the mesh must be redrawn.
+pub(crate) fn patch_align(
+    mesh: &mut Mesh,
+    info: &mut Text3dLayoutInfo,
+    state: &mut PatchState,
+    styling: &Text3dStyling,
+) -> Option<Vec2> {
+    // Sprite uvs depend on the bounding box and faded lines on their position.
+    let sprite_uv = |meta: &GlyphMeta| matches!(meta, GlyphMeta::RowX | GlyphMeta::ColY);
+    if [styling.uv1.0, styling.uv1.1].iter().any(sprite_uv)
+        || styling.glyph_meta.iter().flatten().any(sprite_uv)
+        || styling.overflow_fade.is_some()
+        || styling.hanging_punctuation.is_some()
+        || state.lines.len() != info.lines.len()
+    {
+        return None;
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    if !covered_by_glyphs(info, positions.len()) {
+        return None;
+    }
+    let fac = state.styling.align.as_fac() - styling.align.as_fac();
+    let shifts: Vec<f32> = state
+        .lines
+        .iter()
+        .map(|(_, _, width)| width * fac)
+        .collect();
+    let mut min_x = f32::MAX;
+    let mut max_x = f32::MIN;
+    for ((min, max, _), shift) in state.lines.iter_mut().zip(&shifts) {
+        *min += shift;
+        *max += shift;
+        if min <= max {
+            min_x = min_x.min(*min);
+            max_x = max_x.max(*max);
+        }
+    }
+    if max_x < min_x {
+        min_x = 0.0;
+        max_x = 0.001;
+    }
+    let bb_min = Vec2::new(min_x, -state.height);
+    let dimension = Vec2::new(max_x - min_x, state.height);
+    let center = bb_min + dimension / 2.;
+    let offset = *styling.anchor * dimension - center;
+    let scale = styling
+        .world_scale
+        .map(|x| x / styling.size)
+        .unwrap_or(Vec2::ONE);
+    let delta = |line: usize| (offset - state.offset + Vec2::X * shifts[line]) * scale;
+    for glyph in &mut info.glyphs {
+        let delta = delta(glyph.line);
+        for position in positions
+            .get_mut(glyph.vertices.clone())
+            .into_iter()
+            .flatten()
+        {
+            *position = (Vec3::from_array(*position) + delta.extend(0.0)).to_array();
+        }
+        glyph.rect = shift_rect(glyph.rect, delta);
+        glyph.quad = glyph.quad.map(|x| shift_rect(x, delta));
+    }
+    for (index, line) in info.lines.iter_mut().enumerate() {
+        line.rect = shift_rect(line.rect, delta(index));
+        line.baseline += delta(index).y;
+    }
+    state.offset = offset;
+    state.styling.align = styling.align;
+    Some(dimension)
+}
+
+fn shift_rect(rect: Rect, delta: Vec2) -> Rect {
+    Rect {
+        min: rect.min + delta,
+        max: rect.max + delta,
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::{ecs::component::Component, math::Vec2};
+
+#[cfg(feature = "reflect")]
+use bevy::{ecs::reflect::ReflectComponent, prelude::Reflect};
+
+/// Lays out a [`Text3d`](crate::Text3d) along a path instead of a straight line.
+///
+/// Each glyph is placed at its advance along the path and rotated to follow it,
+/// additional lines are offset along the path's normal.
+///
+/// [`Text3dStyling::align`](crate::Text3dStyling::align) decides where text is placed on the path,
+/// for example, centered text is centered on the path's midpoint.
+///
+/// # Note
+///
+/// [`Text3dLayoutInfo`](crate::Text3dLayoutInfo) and [`Text3dEditor`](crate::Text3dEditor)
+/// rectangles are not affected by the path.
+#[derive(Debug, Component, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub enum Text3dPath {
+    /// A circular arc from `start` to `end` in radians, counter-clockwise from the positive x axis.
+    ///
+    /// If `end < start`, text runs clockwise and faces outwards, this is usually desired for text
+    /// at the top of a circle. If `end > start`, text runs counter-clockwise and faces inwards.
+    Arc {
+        center: Vec2,
+        radius: f32,
+        start: f32,
+        end: f32,
+    },
+    /// A sampled polyline, text runs from the first point to the last point.
+    ///
+    /// Text beyond either end continues in a straight line.
+    Polyline(Vec<Vec2>),
+}
+
+impl Text3dPath {
+    /// Create a circular arc path.
+    pub fn arc(center: Vec2, radius: f32, start: f32, end: f32) -> Self {
+        Text3dPath::Arc {
+            center,
+            radius,
+            start,
+            end,
+        }
+    }
+
+    /// Create a polyline path.
+    pub fn polyline(points: impl IntoIterator<Item = Vec2>) -> Self {
+        Text3dPath::Polyline(points.into_iter().collect())
+    }
+
+    /// Length of the path.
+    pub fn length(&self) -> f32 {
+        match self {
+            Text3dPath::Arc {
+                radius, start, end, ..
+            } => (end - start).abs() * radius,
+            Text3dPath::Polyline(points) => points.windows(2).map(|w| w[0].distance(w[1])).sum(),
+        }
+    }
+
+    /// Returns the position and unit tangent at a distance along the path.
+    pub fn sample(&self, distance: f32) -> (Vec2, Vec2) {
+        match self {
+            Text3dPath::Arc {
+                center,
+                radius,
+                start,
+                end,
+            } => {
+                let sign = if end < start { -1.0 } else { 1.0 };
+                let angle = start + sign * distance / radius.max(f32::EPSILON);
+                let dir = Vec2::from_angle(angle);
+                (*center + dir * *radius, dir.perp() * sign)
+            }
+            Text3dPath::Polyline(points) => {
+                let segments = || {
+                    points
+                        .windows(2)
+                        .map(|w| (w[0], w[1]))
+                        .filter(|(a, b)| a != b)
+                };
+                let Some(first) = segments().next() else {
+                    let origin = points.first().copied().unwrap_or_default();
+                    return (origin + Vec2::new(distance, 0.0), Vec2::X);
+                };
+                if distance < 0.0 {
+                    let tangent = (first.1 - first.0).normalize();
+                    return (first.0 + tangent * distance, tangent);
+                }
+                let mut remaining = distance;
+                let mut last = first;
+                for (a, b) in segments() {
+                    let len = a.distance(b);
+                    let tangent = (b - a) / len;
+                    if remaining <= len {
+                        return (a + tangent * remaining, tangent);
+                    }
+                    remaining -= len;
+                    last = (a, b);
+                }
+                let tangent = (last.1 - last.0).normalize();
+                (last.1 + tangent * remaining, tangent)
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     collections::VecDeque,
     num::NonZero,
     ops::{Deref, DerefMut},
+    path::Path,
     sync::{Arc, Mutex, MutexGuard},
 };
 
@@ -9,12 +10,22 @@ use bevy::{
     asset::{AssetId, Assets},
     ecs::resource::Resource,
     image::Image,
+    math::FloatOrd,
 };
 use cosmic_text::{
-    ttf_parser::Face, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Style, Weight,
+    fontdb::{Database, Source, ID},
+    ttf_parser::Face,
+    Attrs, Buffer, Family, FontSystem, LayoutGlyph, Metrics, Shaping, Style, Weight,
 };
 
-use crate::{render::cache_glyph, tess::CommandEncoder, StrokeJoin, Text3dPlugin, TextAtlas};
+use crate::{
+    line::LineMode,
+    render::cache_glyph,
+    styling::{GlyphEntry, GlyphRaster, StrokeOutline},
+    tess::CommandEncoder,
+    worker::RasterizedGlyph,
+    AxisTag, StrokeJoin, Text3dFontEvent, Text3dPlugin, TextAtlas, TextQuality,
+};
 
 /// An [`Arc<Mutex>`] around [`cosmic_text::FontSystem`],
 /// rendering fonts require exclusive access.
@@ -26,20 +37,64 @@ impl TextRenderer {
         Self(Arc::new(Mutex::new(TextRendererInner {
             font_system,
             queue: VecDeque::new(),
+            rasterized: VecDeque::new(),
+            font_events: Vec::new(),
         })))
     }
 
+    /// Create a [`TextRenderer`] from a user provided [`FontSystem`],
+    /// i.e. one shared with another text crate.
+    ///
+    /// If inserted as a resource before [`Text3dPlugin`] finishes,
+    /// the plugin will not load any fonts, including [`LoadFonts`](crate::LoadFonts).
+    pub fn with_font_system(font_system: FontSystem) -> Self {
+        Self::new(font_system)
+    }
+
+    /// Create a [`TextRenderer`] from a user provided font [`Database`] and a locale like `en-US`.
+    ///
+    /// See [`TextRenderer::with_font_system`] for details.
+    pub fn with_database(locale: impl Into<String>, database: Database) -> Self {
+        Self::new(FontSystem::new_with_locale_and_db(locale.into(), database))
+    }
+
     // Methods uses `mut` to deter `Res` usage as that would block.
 
     /// Obtain the underlying [`FontSystem`].
-    pub fn lock(&mut self) -> FontSystemGuard {
+    pub fn lock(&mut self) -> FontSystemGuard<'_> {
         FontSystemGuard(self.0.lock().unwrap())
     }
 
     /// Obtain the underlying [`FontSystem`] if not loading.
-    pub fn try_lock(&mut self) -> Option<FontSystemGuard> {
+    pub fn try_lock(&mut self) -> Option<FontSystemGuard<'_>> {
         self.0.try_lock().ok().map(FontSystemGuard)
     }
+
+    /// Add a font from memory after startup, returns ids of the loaded faces.
+    ///
+    /// All text is redrawn and a [`Text3dFontEvent::Added`] is sent,
+    /// text that fell back to another font will pick up the new font if it matches better.
+    pub fn register_font_bytes(&mut self, data: impl Into<Vec<u8>>) -> Vec<ID> {
+        let source = Source::Binary(Arc::new(data.into()));
+        self.0.lock().unwrap().add_font(source)
+    }
+
+    /// Add a font file after startup, returns ids of the loaded faces.
+    ///
+    /// See [`TextRenderer::register_font_bytes`] for details.
+    pub fn register_font_file(&mut self, path: impl AsRef<Path>) -> std::io::Result<Vec<ID>> {
+        let data = std::fs::read(path)?;
+        Ok(self.register_font_bytes(data))
+    }
+
+    /// Remove faces added at runtime, glyphs of these faces are removed from all atlases
+    /// and a [`Text3dFontEvent::Removed`] is sent.
+    pub fn unregister_fonts(&mut self, faces: impl IntoIterator<Item = ID>) {
+        self.0
+            .lock()
+            .unwrap()
+            .remove_fonts(faces.into_iter().collect());
+    }
 }
 
 /// Mutex guard over a [`FontSystem`].
@@ -63,9 +118,61 @@ impl DerefMut for FontSystemGuard<'_> {
 pub(crate) struct TextRendererInner {
     pub(crate) font_system: FontSystem,
     pub(crate) queue: VecDeque<(AssetId<TextAtlas>, TextAtlas, Image)>,
+    /// Glyphs drawn by [`TextRenderer::rasterize_task`], copied into the live atlas when received.
+    pub(crate) rasterized: VecDeque<(AssetId<TextAtlas>, Vec<RasterizedGlyph>)>,
+    /// Font changes to be sent as [`Text3dFontEvent`]s.
+    pub(crate) font_events: Vec<Text3dFontEvent>,
+}
+
+impl TextRendererInner {
+    /// Load a font source and record a [`Text3dFontEvent::Added`].
+    pub(crate) fn add_font(&mut self, source: Source) -> Vec<ID> {
+        let db = self.font_system.db_mut();
+        let faces = db.load_font_source(source).to_vec();
+        let mut families = Vec::new();
+        for face in faces.iter().filter_map(|id| db.face(*id)) {
+            if let Some((family, _)) = face.families.first() {
+                if !families.contains(family) {
+                    families.push(family.clone());
+                }
+            }
+        }
+        self.font_events.push(Text3dFontEvent::Added {
+            faces: faces.clone(),
+            families,
+        });
+        faces
+    }
+
+    /// Remove font faces and record a [`Text3dFontEvent::Removed`].
+    pub(crate) fn remove_fonts(&mut self, faces: Vec<ID>) {
+        if faces.is_empty() {
+            return;
+        }
+        let db = self.font_system.db_mut();
+        for face in &faces {
+            db.remove_face(*face);
+        }
+        self.font_events.push(Text3dFontEvent::Removed { faces });
+    }
+}
+
+/// A glyph waiting to be rasterized asynchronously.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingGlyph {
+    pub glyph: LayoutGlyph,
+    pub stroke: Option<NonZero<u32>>,
+    pub outline: StrokeOutline,
+    pub weight: Weight,
+    pub padding: u16,
+    pub scale_factor: f32,
+    pub variations: Vec<(AxisTag, f32)>,
+    pub raster: GlyphRaster,
 }
 
 /// Style that only concerns drawing but not layout.
+///
+/// Text shadows reuse the textures of fill and stroke and do not need to be prepared.
 #[derive(Debug, Clone, Default)]
 pub struct DrawStyle {
     pub family: Arc<str>,
@@ -74,9 +181,19 @@ pub struct DrawStyle {
     pub stroke_join: StrokeJoin,
     pub weight: Weight,
     pub style: Style,
+    /// If `stroke` is set, prepare the fill in addition to the stroke.
+    pub fill: bool,
+    /// Prepare the underline texture.
+    pub underline: bool,
+    /// Prepare the strikethrough texture.
+    pub strikethrough: bool,
+    /// Should match [`Text3dStyling::glyph_padding`](crate::Text3dStyling::glyph_padding).
+    pub glyph_padding: u16,
+    /// Should match [`Text3dStyling::font_variations`](crate::Text3dStyling::font_variations).
+    pub font_variations: Vec<(AxisTag, f32)>,
 }
 
-pub(crate) fn family(name: &str) -> Family {
+pub(crate) fn family(name: &str) -> Family<'_> {
     match name {
         "" | "serif" => Family::Serif,
         "sans-serif" => Family::SansSerif,
@@ -88,12 +205,28 @@ pub(crate) fn family(name: &str) -> Family {
 }
 
 impl DrawStyle {
-    pub fn as_attrs(&self) -> Attrs {
+    pub fn as_attrs(&self) -> Attrs<'_> {
         Attrs::new()
             .family(family(&self.family))
             .weight(self.weight)
             .style(self.style)
     }
+
+    /// Strokes to be prepared, `None` represents the fill.
+    fn strokes(&self) -> impl Iterator<Item = Option<NonZero<u32>>> {
+        let fill = (self.stroke.is_none() || self.fill).then_some(None);
+        fill.into_iter().chain(self.stroke.map(Some))
+    }
+
+    /// Decoration lines to be prepared.
+    fn lines(&self) -> impl Iterator<Item = LineMode> {
+        [
+            self.underline.then_some(LineMode::Underline),
+            self.strikethrough.then_some(LineMode::Strikethrough),
+        ]
+        .into_iter()
+        .flatten()
+    }
 }
 
 /// A callback function that helps a loading screen keep track of progress.
@@ -122,9 +255,13 @@ impl TextRenderer {
     /// The [`TextAtlas`] and [`Image`] will be REPLACED after the task finishes.
     /// You should not call `prepare_task` with the same atlas
     /// or image multiple times, or modify them concurrently in the `World`.
+    ///
+    /// Glyphs are rasterized at [`Text3dPlugin::scale_factor`] multiplied by
+    /// [`TextQuality::scale_multiplier`], pass the current [`TextQuality`] resource.
     pub fn prepare_task<S, I>(
         &self,
         settings: &Text3dPlugin,
+        quality: TextQuality,
         workload: impl IntoIterator<Item = (AssetId<TextAtlas>, TextAtlas, Image, I)>
             + Send
             + Sync
@@ -136,10 +273,12 @@ impl TextRenderer {
         I: IntoIterator<Item = (S, DrawStyle)>,
     {
         let font_system = self.clone();
-        let scale_factor = settings.scale_factor;
+        let scale_factor = settings.scale_factor * quality.scale_multiplier();
         move || {
             let mut guard = font_system.0.lock().unwrap();
-            let TextRendererInner { font_system, queue } = guard.deref_mut();
+            let TextRendererInner {
+                font_system, queue, ..
+            } = guard.deref_mut();
             let mut tess_commands = CommandEncoder::default();
             for (id, mut atlas, mut image, workload) in workload {
                 for (str, style) in workload {
@@ -152,27 +291,54 @@ impl TextRenderer {
                     );
                     buffer.shape_until_scroll(font_system, false);
                     let join = style.stroke_join;
-                    let stroke = style.stroke;
+                    let outline = StrokeOutline::from_join(join);
                     let weight = style.weight;
+                    let mut fonts = Vec::new();
                     for run in buffer.layout_runs() {
                         for glyph in run.glyphs {
-                            font_system.db().with_face_data(glyph.font_id, |file, _| {
-                                let Ok(face) = Face::parse(file, 0) else {
-                                    return;
-                                };
-                                cache_glyph(
+                            for stroke in style.strokes() {
+                                font_system.db().with_face_data(glyph.font_id, |file, _| {
+                                    let Ok(face) = Face::parse(file, 0) else {
+                                        return;
+                                    };
+                                    cache_glyph(
+                                        scale_factor,
+                                        &mut atlas,
+                                        &mut image,
+                                        &mut tess_commands,
+                                        glyph,
+                                        stroke,
+                                        &outline,
+                                        weight,
+                                        style.glyph_padding,
+                                        &style.font_variations,
+                                        GlyphRaster::Coverage,
+                                        face,
+                                    );
+                                });
+                            }
+                            if !fonts.contains(&(glyph.font_id, FloatOrd(glyph.font_size))) {
+                                fonts.push((glyph.font_id, FloatOrd(glyph.font_size)));
+                            }
+                            callback.glyph_drawn();
+                        }
+                    }
+                    for (font, FloatOrd(size)) in fonts {
+                        for mode in style.lines() {
+                            for stroke in style.strokes() {
+                                mode.get_atlas_rect(
+                                    font_system,
+                                    font,
+                                    size,
                                     scale_factor,
                                     &mut atlas,
                                     &mut image,
                                     &mut tess_commands,
-                                    glyph,
-                                    stroke,
                                     join,
-                                    weight,
-                                    face,
+                                    weight.into(),
+                                    stroke,
                                 );
-                            });
-                            callback.glyph_drawn();
+                            }
                         }
                     }
                     callback.style_drawn();
@@ -183,6 +349,45 @@ impl TextRenderer {
         }
     }
 
+    /// Creates a function task that rasterizes glyphs missing at runtime on scratch images,
+    /// the glyphs are copied into the atlas as it is when the task finishes.
+    pub(crate) fn rasterize_task(
+        &self,
+        id: AssetId<TextAtlas>,
+        width: usize,
+        glyphs: Vec<(GlyphEntry, PendingGlyph)>,
+    ) -> impl FnOnce() + Send + Sync + 'static {
+        let font_system = self.clone();
+        move || {
+            let mut guard = font_system.0.lock().unwrap();
+            let TextRendererInner {
+                font_system,
+                rasterized,
+                ..
+            } = guard.deref_mut();
+            let mut tess_commands = CommandEncoder::default();
+            let mut result = Vec::new();
+            for (entry, glyph) in glyphs {
+                font_system
+                    .db()
+                    .with_face_data(glyph.glyph.font_id, |file, _| {
+                        let Ok(face) = Face::parse(file, 0) else {
+                            return;
+                        };
+                        result.push(RasterizedGlyph::new(
+                            id,
+                            entry,
+                            face,
+                            &glyph,
+                            width,
+                            &mut tess_commands,
+                        ));
+                    });
+            }
+            rasterized.push_back((id, result));
+        }
+    }
+
     /// Creates a function task that renders text to a [`TextAtlas`].
     ///
     /// This function prepare atlases by cloning the underlying images.
@@ -190,6 +395,7 @@ impl TextRenderer {
     pub fn prepare_images_cloned<S, I>(
         &self,
         settings: &Text3dPlugin,
+        quality: TextQuality,
         workload: impl IntoIterator<Item = (AssetId<TextAtlas>, I)> + Send + Sync + 'static,
         atlases: &mut Assets<TextAtlas>,
         images: &mut Assets<Image>,
@@ -207,7 +413,7 @@ impl TextRenderer {
                 Some((id, atlas, image, iter))
             })
             .collect();
-        self.prepare_task(settings, workload, callback)
+        self.prepare_task(settings, quality, workload, callback)
     }
 
     /// Creates a function task that renders text to a [`TextAtlas`].
@@ -219,6 +425,7 @@ impl TextRenderer {
     pub fn prepare_images<S, I>(
         &self,
         settings: &Text3dPlugin,
+        quality: TextQuality,
         workload: impl IntoIterator<Item = (AssetId<TextAtlas>, I)> + Send + Sync + 'static,
         atlases: &mut Assets<TextAtlas>,
         images: &mut Assets<Image>,
@@ -236,6 +443,6 @@ impl TextRenderer {
                 Some((id, atlas, image, iter))
             })
             .collect();
-        self.prepare_task(settings, workload, callback)
+        self.prepare_task(settings, quality, workload, callback)
     }
 }
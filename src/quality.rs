@@ -0,0 +1,56 @@
+use bevy::ecs::resource::Resource;
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectDefault, ReflectResource};
+
+/// Global text quality tier, for a graphics settings menu.
+///
+/// Each tier maps to a bundle of rasterization settings:
+///
+/// * The resolution glyphs are rasterized at, see [`TextQuality::scale_multiplier`].
+///   Lower tiers use smaller atlases and rasterize faster at the cost of blurrier text.
+/// * The number of blur passes of text shadows and glows, see [`TextQuality::blur_passes`].
+///
+/// [`Text3dPlugin::scale_factor`](crate::Text3dPlugin::scale_factor) and
+/// [`Text3dScaleFactorOverride`](crate::Text3dScaleFactorOverride) are not modified,
+/// glyphs are rasterized at their value multiplied by [`TextQuality::scale_multiplier`].
+/// Glyphs are always positioned without subpixel variants, atlases have no mipmaps
+/// and use a fixed format, so these are not part of the bundle.
+///
+/// Changing this at runtime clears all atlases and redraws all text,
+/// spread over frames according to [`Text3dPlugin::atlases_cleared_per_frame`](crate::Text3dPlugin::atlases_cleared_per_frame).
+///
+/// Defaults to [`TextQuality::High`], which does not change rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Resource)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource, Default))]
+pub enum TextQuality {
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+impl TextQuality {
+    /// Multiplier of the scale factor glyphs are rasterized at, `0.5`, `0.75` or `1.0`.
+    pub fn scale_multiplier(self) -> f32 {
+        match self {
+            TextQuality::Low => 0.5,
+            TextQuality::Medium => 0.75,
+            TextQuality::High => 1.0,
+        }
+    }
+
+    /// Number of box blurs that approximate a gaussian blur for
+    /// [`Text3dStyling::text_shadows`](crate::Text3dStyling::text_shadows) and
+    /// [`Text3dStyling::glow`](crate::Text3dStyling::glow), `1`, `2` or `3`.
+    ///
+    /// Fewer passes rasterize faster but produce boxier shadows.
+    pub fn blur_passes(self) -> u8 {
+        match self {
+            TextQuality::Low => 1,
+            TextQuality::Medium => 2,
+            TextQuality::High => 3,
+        }
+    }
+}
@@ -1,30 +1,61 @@
 use bevy::{
-    asset::{AssetId, Assets, RenderAssetUsages},
+    asset::{AssetEvent, AssetId, Assets, Handle, RenderAssetUsages},
+    color::Srgba,
     ecs::{
-        change_detection::DetectChanges,
-        system::{Local, Query, Res, ResMut},
+        change_detection::{DetectChanges, DetectChangesMut},
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::Without,
+        system::{Commands, Local, Query, Res, ResMut},
         world::{Mut, Ref},
     },
     image::Image,
     math::{FloatOrd, IVec2, Rect, Vec2, Vec3, Vec4},
-    render::mesh::{Indices, Mesh, Mesh2d, Mesh3d, PrimitiveTopology, VertexAttributeValues},
+    render::{
+        mesh::{Indices, Mesh, Mesh2d, Mesh3d, MeshAabb, PrimitiveTopology, VertexAttributeValues},
+        primitives::Aabb,
+    },
+    tasks::{AsyncComputeTaskPool, ComputeTaskPool, ParallelSliceMut, TaskPool},
 };
 use cosmic_text::{
+    fontdb::ID,
     ttf_parser::{Face, GlyphId},
-    Attrs, Buffer, Family, FontSystem, LayoutGlyph, Metrics, Shaping, Weight, Wrap,
+    Affinity, Attrs, Buffer, Cursor, FontSystem, LayoutGlyph, LayoutRun, Metrics, Shaping, Weight,
+    Wrap,
+};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use std::{
+    hash::{Hash, Hasher},
+    num::NonZero,
 };
-use std::num::NonZero;
 
 use crate::{
+    atlas::AtlasClearQueue,
+    drawer::drawer_texture,
+    dynamic_mesh::StaticLayout,
+    editor::Text3dEditor,
     fetch::FetchedTextSegment,
+    font_usage::Text3dFontUsage,
+    icon::{cache_icon, cache_pixels, icon_entry, invalidate_icon, ICON_PLACEHOLDER},
     layers::{DrawRequest, DrawType, Layer},
+    layout::{GlyphLayoutInfo, LineLayoutInfo, Text3dLayoutInfo},
     line::LineRun,
-    mesh_util::ExtractedMesh,
-    styling::GlyphEntry,
+    mesh_util::{hash_mesh, rescale_uvs, ExtractedMesh, MeshPostProcess, SharedMeshes},
+    parse::BULLET,
+    patch::{patch_align, patch_colors, PatchState, StylingPatch},
+    path::Text3dPath,
+    prepare::{family, PendingGlyph},
+    shape_cache::{set_rich_text_incremental, Text3dShapeCache},
+    styling::{
+        font_features, variations_key, GlyphEntry, GlyphRaster, GlyphTextureOf, StrokeOutline,
+    },
     tess::CommandEncoder,
     text3d::{Text3d, Text3dSegment},
-    SegmentStyle, StrokeJoin, Text3dBounds, Text3dDimensionOut, Text3dPlugin, Text3dStyling,
-    TextAtlas, TextAtlasHandle, TextRenderer,
+    worker::RasterWorker,
+    AxisTag, DrawerInput, GlyphPlaceholder, SegmentStyle, Text3dBounds, Text3dDimensionOut,
+    Text3dDrawers, Text3dDynamicMesh, Text3dHidden, Text3dIcon, Text3dIcons, Text3dPlugin,
+    Text3dScaleFactorOverride, Text3dStyling, Text3dTruncated, TextAtlas, TextAtlasHandle,
+    TextQuality, TextRemeshed, TextRenderer,
 };
 
 fn default_mesh() -> Mesh {
@@ -37,6 +68,29 @@ fn default_mesh() -> Mesh {
         .with_inserted_indices(Indices::U16(Vec::new()))
 }
 
+fn mesh_handle<'t>(
+    mesh2d: &'t Option<Mut<Mesh2d>>,
+    mesh3d: &'t Option<Mut<Mesh3d>>,
+) -> Option<&'t Handle<Mesh>> {
+    mesh2d
+        .as_ref()
+        .map(|x| &x.0)
+        .or_else(|| mesh3d.as_ref().map(|x| &x.0))
+}
+
+fn set_mesh_handle(
+    mesh2d: &mut Option<Mut<Mesh2d>>,
+    mesh3d: &mut Option<Mut<Mesh3d>>,
+    handle: Handle<Mesh>,
+) {
+    if let Some(handle_2d) = mesh2d {
+        handle_2d.0 = handle.clone();
+    }
+    if let Some(handle_3d) = mesh3d {
+        handle_3d.0 = handle;
+    }
+}
+
 fn get_mesh<'t>(
     mesh2d: &mut Option<Mut<Mesh2d>>,
     mesh3d: &mut Option<Mut<Mesh3d>>,
@@ -49,42 +103,94 @@ fn get_mesh<'t>(
     if id == AssetId::default() {
         let handle = meshes.add(default_mesh());
         id = handle.id();
-        if let Some(handle_2d) = mesh2d {
-            handle_2d.0 = handle.clone();
-        }
-        if let Some(handle_3d) = mesh3d {
-            handle_3d.0 = handle;
-        }
+        set_mesh_handle(mesh2d, mesh3d, handle);
     }
     meshes.get_mut(id)
 }
 
 pub fn text_render(
     settings: Res<Text3dPlugin>,
+    quality: Res<TextQuality>,
     font_system: ResMut<TextRenderer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut images: ResMut<Assets<Image>>,
     mut atlases: ResMut<Assets<TextAtlas>>,
-    mut text_query: Query<(
-        Ref<Text3d>,
-        Ref<Text3dBounds>,
-        Ref<Text3dStyling>,
-        &TextAtlasHandle,
-        Option<&mut Mesh2d>,
-        Option<&mut Mesh3d>,
-        &mut Text3dDimensionOut,
-    )>,
+    mut text_query: Query<
+        (
+            Entity,
+            Ref<Text3d>,
+            Ref<Text3dBounds>,
+            Ref<Text3dStyling>,
+            &TextAtlasHandle,
+            Option<&mut Mesh2d>,
+            Option<&mut Mesh3d>,
+            &mut Text3dDimensionOut,
+            Option<&mut Text3dEditor>,
+            Option<&mut Text3dLayoutInfo>,
+            Option<&mut Text3dShapeCache>,
+            Option<Ref<Text3dPath>>,
+            Option<&mut Text3dFontUsage>,
+            Option<Ref<Text3dScaleFactorOverride>>,
+            Option<&mut Text3dDynamicMesh>,
+        ),
+        Without<Text3dHidden>,
+    >,
     segments: Query<Ref<FetchedTextSegment>>,
+    mut shared_meshes: ResMut<SharedMeshes>,
     mut draw_requests: Local<Vec<DrawRequest>>,
-    mut sort_buffer: Local<Vec<(Layer, [u16; 6])>>,
+    mut pending: Local<
+        Vec<(
+            Entity,
+            Option<(AssetId<Mesh>, ExtractedMesh)>,
+            Option<(Entity, ExtractedMesh)>,
+            MeshPostProcess,
+        )>,
+    >,
+    mut pending_glyphs: Local<FxHashMap<AssetId<TextAtlas>, FxHashMap<GlyphEntry, PendingGlyph>>>,
+    mut rasterizing: Local<FxHashSet<AssetId<TextAtlas>>>,
+    mut clear_queue: ResMut<AtlasClearQueue>,
+    (icons, mut image_events, drawers, mut worker): (
+        Option<Res<Text3dIcons>>,
+        EventReader<AssetEvent<Image>>,
+        Option<Res<Text3dDrawers>>,
+        Option<ResMut<RasterWorker>>,
+    ),
+    (mut truncated_events, mut remeshed_events, mut commands, mut dynamic_targets): (
+        EventWriter<Text3dTruncated>,
+        EventWriter<TextRemeshed>,
+        Commands,
+        Query<(Option<&mut Mesh2d>, Option<&mut Mesh3d>), Without<Text3d>>,
+    ),
 ) {
+    let renderer = font_system.clone();
     let Ok(mut lock) = font_system.0.try_lock() else {
         return;
     };
     let mut redraw = false;
-    if font_system.is_changed() {
+    if font_system.is_changed()
+        || icons.as_ref().is_some_and(|x| x.is_changed())
+        || drawers.as_ref().is_some_and(|x| x.is_changed())
+    {
         redraw = true;
     }
+    // Redraw when an icon's image is loaded or modified.
+    if let Some(icons) = &icons {
+        for event in image_events.read() {
+            let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+            else {
+                continue;
+            };
+            if !icons.icons.values().any(|icon| icon.image.id() == *id) {
+                continue;
+            }
+            if matches!(event, AssetEvent::Modified { .. }) {
+                for (_, atlas) in atlases.iter_mut() {
+                    invalidate_icon(atlas, *id);
+                }
+            }
+            redraw = true;
+        }
+    }
     // Add asynchronously drawn text.
     for (id, atlas, image) in lock.queue.drain(..) {
         let img_id = atlas.image.id();
@@ -92,45 +198,107 @@ pub fn text_render(
         atlases.insert(id, atlas);
         redraw = true;
     }
+    // Add glyphs from `asynchronous_rasterization`, the atlas might have changed since the task started.
+    for (id, glyphs) in lock.rasterized.drain(..) {
+        rasterizing.remove(&id);
+        for glyph in glyphs {
+            glyph.blit(&mut atlases, &mut images);
+        }
+        redraw = true;
+    }
+    // Add glyphs from rasterization threads.
+    if let Some(worker) = &mut worker {
+        if worker.receive(&mut atlases, &mut images) {
+            redraw = true;
+        }
+    }
     let font_system = &mut lock.font_system;
-    let scale_factor = settings.scale_factor;
-    for (text, bounds, styling, atlas, mut mesh2d, mut mesh3d, mut output) in text_query.iter_mut()
+    if settings.share_identical_meshes {
+        shared_meshes.cleanup();
+    }
+    for (
+        entity,
+        text,
+        bounds,
+        styling,
+        atlas,
+        mut mesh2d,
+        mut mesh3d,
+        mut output,
+        mut editor,
+        mut layout_info,
+        mut shape_cache,
+        path,
+        mut font_usage,
+        scale_override,
+        mut dynamic,
+    ) in text_query.iter_mut()
     {
-        let Some(atlas) = atlases.get_mut(atlas.0.id()) else {
-            return;
+        let scale_factor = scale_override
+            .as_ref()
+            .map(|x| x.0)
+            .unwrap_or(settings.scale_factor)
+            * quality.scale_multiplier();
+        let atlas_id = atlas.0.id();
+        // Keep the old mesh until the atlas is cleared.
+        if clear_queue.pending.contains(&atlas_id) {
+            continue;
+        }
+        let Some(atlas) = atlases.get_mut(atlas_id) else {
+            continue;
         };
 
         if atlas.image.id() == AssetId::default() || !images.contains(atlas.image.id()) {
-            atlas.image = images.add(TextAtlas::empty_image(
-                settings.default_atlas_dimension.0,
-                settings.default_atlas_dimension.1,
-            ))
+            let (x, y) = settings.default_atlas_dimension;
+            atlas.image = images.add(if atlas.array {
+                TextAtlas::empty_array_image(x, y)
+            } else {
+                TextAtlas::empty_image(x, y)
+            })
         };
 
+        if let Some(icons) = &icons {
+            for (segment, _) in &text.segments {
+                if let Some(icon) = segment_icon(icons, segment) {
+                    cache_icon(atlas, &mut images, icon.image.id());
+                }
+            }
+        }
+
         let Some(image) = images.get_mut(atlas.image.id()) else {
-            return;
+            continue;
         };
 
+        let extracted_changed = text.segments.iter().any(|(segment, _)| {
+            matches!(segment, Text3dSegment::Extract(entity)
+                if segments.get(*entity).is_ok_and(|x| x.is_changed()))
+        });
+        // Shared meshes cannot be modified in place and must be redrawn.
+        let shared = mesh_handle(&mesh2d, &mesh3d)
+            .is_some_and(|handle| shared_meshes.is_shared(handle.id()));
+        let new_dimension = IVec2::new(image.width() as i32, image.height() as i32);
+
         // Change detection.
-        if !redraw && !text.is_changed() && !bounds.is_changed() && !styling.is_changed() {
-            let mut unchanged = true;
-            for segment in &text.segments {
-                if let Text3dSegment::Extract(entity) = &segment.0 {
-                    if segments.get(*entity).is_ok_and(|x| x.is_changed()) {
-                        unchanged = false;
-                        break;
-                    }
-                }
-            }
-            if unchanged {
-                let Some(image) = images.get(atlas.image.id()) else {
-                    continue;
-                };
-                let new_dimension = IVec2::new(image.width() as i32, image.height() as i32);
+        let layout_changed = redraw
+            || clear_queue.cleared.contains(&atlas_id)
+            || text.is_changed()
+            || bounds.is_changed()
+            || editor.as_ref().is_some_and(|x| x.is_changed())
+            || path.as_ref().is_some_and(|x| x.is_changed())
+            || scale_override.as_ref().is_some_and(|x| x.is_changed());
+        if !layout_changed && !extracted_changed {
+            if !styling.is_changed() {
                 if output.atlas_dimension == new_dimension {
                     continue;
                 }
-
+                // UVs in pixels are converted in the shader.
+                if settings.pixel_uvs {
+                    output.atlas_dimension = new_dimension;
+                    continue;
+                }
+            }
+            // Dynamic meshes are redrawn since they are not tracked here.
+            if !styling.is_changed() && !shared && dynamic.is_none() {
                 let Some(mesh) = get_mesh(&mut mesh2d, &mut mesh3d, &mut meshes) else {
                     continue;
                 };
@@ -140,55 +308,216 @@ pub fn text_render(
                 else {
                     continue;
                 };
-                for [x, y] in uv0 {
-                    *x *= output.atlas_dimension.x as f32 / new_dimension.x as f32;
-                    *y *= output.atlas_dimension.y as f32 / new_dimension.y as f32;
-                }
+                rescale_uvs(uv0, output.atlas_dimension, new_dimension);
                 output.atlas_dimension = new_dimension;
                 continue;
             }
+            // Styling changes that do not affect shaping update the mesh in place.
+            if !shared && dynamic.is_none() && output.atlas_dimension == new_dimension {
+                let output = &mut *output;
+                let mesh = mesh_handle(&mesh2d, &mesh3d).and_then(|x| meshes.get_mut(x.id()));
+                let mut patched = false;
+                if let (Some(state), Some(info), Some(mesh)) =
+                    (&mut output.patch, layout_info.as_deref_mut(), mesh)
+                {
+                    match state.classify(&styling) {
+                        Some(StylingPatch::Colors) => {
+                            patched = patch_colors(mesh, info, &text, &styling, &mut draw_requests);
+                            if patched {
+                                state.styling = styling.clone();
+                            }
+                        }
+                        Some(StylingPatch::Align) => {
+                            if let Some(dimension) = patch_align(mesh, info, state, &styling) {
+                                patched = true;
+                                output.dimension = dimension;
+                                remeshed_events.write(TextRemeshed { entity, dimension });
+                                if settings.update_aabb {
+                                    match mesh.compute_aabb() {
+                                        Some(aabb) => commands.entity(entity).try_insert(aabb),
+                                        None => commands.entity(entity).try_remove::<Aabb>(),
+                                    };
+                                }
+                            }
+                        }
+                        None => (),
+                    }
+                }
+                if patched {
+                    continue;
+                }
+            }
         }
 
-        let mut buffer = Buffer::new(
-            font_system,
-            Metrics::new(styling.size, styling.size * styling.line_height),
-        );
-        buffer.set_wrap(font_system, Wrap::WordOrGlyph);
+        let metrics = Metrics::new(styling.size, styling.size * styling.line_height);
+        let incremental = shape_cache.is_some();
+        let mut owned_buffer;
+        let buffer = match &mut shape_cache {
+            Some(cache) => cache.buffer(font_system, metrics),
+            None => {
+                owned_buffer = Buffer::new(font_system, metrics);
+                &mut owned_buffer
+            }
+        };
+        if styling.overflow_fade.is_some() {
+            buffer.set_wrap(font_system, Wrap::None);
+        } else {
+            buffer.set_wrap(font_system, styling.wrap.into());
+        }
         buffer.set_size(font_system, Some(bounds.width), None);
         buffer.set_tab_width(font_system, styling.tab_width);
 
-        buffer.set_rich_text(
-            font_system,
-            text.segments
-                .iter()
-                .enumerate()
-                .map(|(idx, (text, style))| {
-                    (
-                        match text {
-                            Text3dSegment::String(s) => s.as_str(),
-                            Text3dSegment::Extract(e) => segments
-                                .get(*e)
-                                .map(|x| x.into_inner().as_str())
-                                .unwrap_or(""),
-                        },
-                        style.as_attr(&styling).metadata(idx),
-                    )
-                }),
-            &Attrs::new()
-                .family(Family::Name(&styling.font))
-                .style(styling.style.into())
-                .weight(styling.weight.into()),
-            Shaping::Advanced,
-            None,
-        );
+        // Replace spaces with no-break spaces in `nowrap` segments.
+        let nowrap: Vec<_> = text
+            .segments
+            .iter()
+            .map(|(text, style)| {
+                if !style.nowrap.unwrap_or(false) {
+                    return None;
+                }
+                let s = match text {
+                    Text3dSegment::String(s) => s.as_str(),
+                    Text3dSegment::Interned(s) => s,
+                    Text3dSegment::Extract(e) => segments
+                        .get(*e)
+                        .map(|x| x.into_inner().as_str())
+                        .unwrap_or(""),
+                    Text3dSegment::Computed(c) => c.value(),
+                    Text3dSegment::Image(_) => return None,
+                };
+                let spaces: Vec<_> = s.match_indices(' ').map(|(i, _)| i).collect();
+                Some((s.replace(' ', "\u{a0}"), spaces))
+            })
+            .collect();
+        let mut offsets = BufferOffsets::default();
+
+        let spans = text
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(idx, (text, style))| {
+                let attrs = style.as_attr(&styling).metadata(idx);
+                if let Some(Some((s, _))) = nowrap.get(idx) {
+                    return (s.as_str(), attrs);
+                }
+                match text {
+                    Text3dSegment::String(s) => (s.as_str(), attrs),
+                    Text3dSegment::Interned(s) => (s.as_ref(), attrs),
+                    Text3dSegment::Extract(e) => (
+                        segments
+                            .get(*e)
+                            .map(|x| x.into_inner().as_str())
+                            .unwrap_or(""),
+                        attrs,
+                    ),
+                    Text3dSegment::Computed(c) => (c.value(), attrs),
+                    // Reserve the icon's width with letter spacing.
+                    Text3dSegment::Image(_) => {
+                        match icons.as_ref().and_then(|icons| segment_icon(icons, text)) {
+                            Some(icon) => (ICON_PLACEHOLDER, attrs.letter_spacing(icon.size.x)),
+                            None => ("", attrs),
+                        }
+                    }
+                }
+            });
+        if nowrap.iter().any(Option::is_some) {
+            let mut offset = 0;
+            for (idx, (s, _)) in spans.clone().enumerate() {
+                if let Some(Some((_, spaces))) = nowrap.get(idx) {
+                    offsets
+                        .nowrap_spaces
+                        .extend(spaces.iter().enumerate().map(|(k, i)| offset + i + k));
+                }
+                offset += s.len();
+            }
+        }
+        let default_attrs = Attrs::new()
+            .family(family(&styling.font))
+            .style(styling.style.into())
+            .weight(styling.weight.into())
+            .font_features(font_features(&styling.font_features));
+
+        if incremental {
+            set_rich_text_incremental(buffer, spans, &default_attrs);
+        } else {
+            buffer.set_rich_text(font_system, spans, &default_attrs, Shaping::Advanced, None);
+        }
 
         buffer.shape_until_scroll(font_system, true);
 
-        let Some(mesh) = get_mesh(&mut mesh2d, &mut mesh3d, &mut meshes) else {
-            continue;
+        if styling.balance_lines && styling.overflow_fade.is_none() {
+            balance_lines(buffer, font_system);
+        }
+
+        if let Some(tolerance) = styling.widow_tolerance {
+            if styling.overflow_fade.is_none() {
+                avoid_widows(buffer, font_system, tolerance);
+            }
+        }
+
+        // Static glyphs of a split text are not drawn again
+        // if only fetched segments changed and did not move them.
+        let static_key = dynamic
+            .as_ref()
+            .filter(|_| path.is_none())
+            .and_then(|_| static_layout_key(buffer, &text, &styling));
+        let reused_layout = dynamic
+            .as_mut()
+            .filter(|_| {
+                mesh_handle(&mesh2d, &mesh3d).is_some_and(|x| x.id() != AssetId::default())
+                    && !layout_changed
+                    && !styling.is_changed()
+                    && output.atlas_dimension == new_dimension
+            })
+            .and_then(|dynamic| dynamic.bypass_change_detection().static_layout.take())
+            .filter(|layout| {
+                Some(layout.key) == static_key && (layout_info.is_none() || layout.glyphs.is_some())
+            });
+        let reuse_static = reused_layout.is_some();
+        let mut reused_glyphs = reused_layout
+            .as_ref()
+            .and_then(|layout| layout.glyphs.as_deref())
+            .unwrap_or_default()
+            .iter();
+        let mut recorded_layout = static_key
+            .filter(|_| !reuse_static)
+            .map(|key| StaticLayout {
+                key,
+                extents: Vec::new(),
+                glyphs: layout_info.is_some().then(Vec::new),
+            });
+
+        // Copy on write, since other entities may be using the same mesh.
+        if !reuse_static
+            && mesh_handle(&mesh2d, &mesh3d)
+                .is_some_and(|handle| shared_meshes.is_shared(handle.id()))
+        {
+            set_mesh_handle(&mut mesh2d, &mut mesh3d, Handle::default());
+        }
+
+        let has_mesh = mesh_handle(&mesh2d, &mesh3d).is_some_and(|x| x.id() != AssetId::default());
+        let mut mesh = if dynamic.is_some() && has_mesh {
+            // Do not modify the mesh asset unless the static glyphs changed.
+            ExtractedMesh {
+                layer_offset: styling.layer_offset,
+                ..Default::default()
+            }
+        } else {
+            let Some(mesh) = get_mesh(&mut mesh2d, &mut mesh3d, &mut meshes) else {
+                continue;
+            };
+            ExtractedMesh::new(mesh, styling.layer_offset)
         };
+        mesh.tangents = styling.tangents;
+        let mut dynamic_mesh = dynamic.as_ref().map(|_| ExtractedMesh {
+            layer_offset: styling.layer_offset,
+            tangents: styling.tangents,
+            ..Default::default()
+        });
 
-        let mut mesh = ExtractedMesh::new(mesh, &mut sort_buffer, styling.layer_offset);
+        let mesh_id = mesh_handle(&mesh2d, &mesh3d)
+            .map(Handle::id)
+            .unwrap_or_default();
 
         let mut width = 0.0f32;
         let mut advance = 0.0f32;
@@ -199,23 +528,199 @@ pub fn text_render(
 
         let mut min_x = f32::MAX;
         let mut max_x = f32::MIN;
+        // Horizontal extent and width of each line, for in place alignment changes.
+        let mut line_extents = Vec::new();
+
+        let mut caret_rect = None;
+        let mut selection_rects = Vec::new();
+
+        if let Some(usage) = &mut font_usage {
+            usage.clear();
+        }
+
+        let mut offset = 0;
+        for line in &buffer.lines {
+            offsets.lines.push(offset);
+            offset += line.text().len() + line.ending().as_str().len();
+        }
+        if let Some(info) = &mut layout_info {
+            info.clear();
+            for line in &buffer.lines {
+                info.text.push_str(line.text());
+                info.text.push_str(line.ending().as_str());
+            }
+            for i in offsets.nowrap_spaces.iter().rev() {
+                info.text.replace_range(*i..*i + '\u{a0}'.len_utf8(), " ");
+            }
+        }
+
+        let path_length = path.as_ref().map(|x| x.length()).unwrap_or(0.0);
+        // Baseline of the first line is placed on the path.
+        let path_baseline = buffer
+            .layout_runs()
+            .next()
+            .map(|run| -run.line_y)
+            .unwrap_or(0.0);
+
+        let mut glyph_count = 0;
+        let mut truncated = false;
 
-        for run in buffer.layout_runs() {
-            width = width.max(run.line_w);
+        // Paragraph and indent of wrapped lines of markdown list items.
+        let mut bullet_indent = (usize::MAX, 0.0);
+
+        // Index of the first word of the current line.
+        let mut first_word = 0;
+
+        for (line_index, run) in buffer.layout_runs().enumerate() {
+            if truncated {
+                break;
+            }
+            let (indent, dx) = line_dx(&run, &styling, &mut bullet_indent);
+            width = width.max(run.line_w + indent);
             height = height.max(run.line_top + run.line_height);
             let mut underline_run = LineRun::default();
             let mut strikethrough_run = LineRun::default();
+            let extent = (min_x, max_x);
+            // Extent of static glyphs, reused from the last layout if they are not drawn.
+            let mut static_extent = reused_layout
+                .as_ref()
+                .and_then(|layout| layout.extents.get(line_index).copied())
+                .unwrap_or((f32::MAX, f32::MIN));
+            (min_x, max_x) = static_extent;
+            // Right edge and length of the fade if this line overflows.
+            let clip = styling
+                .overflow_fade
+                .filter(|_| run.line_w > bounds.width)
+                .map(|fade| (dx + bounds.width, fade * styling.size));
+            if let Some(editor) = &editor {
+                editor.collect_rects(&run, dx, &offsets, &mut caret_rect, &mut selection_rects);
+            }
+            mesh.set_line(line_index, dx, run.line_w);
+            let (glyph_words, word_spans) = words(&run);
+            let line_offset = offsets.lines.get(run.line_i).copied().unwrap_or(0);
+            let line_top = -run.line_top;
+            let line_bottom = -run.line_top - run.line_height;
+            let first_glyph = layout_info.as_ref().map(|x| x.glyphs.len()).unwrap_or(0);
+            let mut ruby_runs: Vec<RubyRun> = Vec::new();
             for glyph_index in 0..run.glyphs.len() {
                 let glyph = &run.glyphs[glyph_index];
-                let Some((_, attrs)) = text.segments.get(glyph.metadata) else {
+                let Some((segment, attrs)) = text.segments.get(glyph.metadata) else {
                     continue;
                 };
-                let dx = -run.line_w * styling.align.as_fac();
+                if clip.is_some_and(|(cut, _)| glyph.x + dx >= cut) {
+                    continue;
+                }
+                if glyph_count >= styling.max_glyphs {
+                    truncated = true;
+                    break;
+                }
+                glyph_count += 1;
+                if let Some(&Some(word)) = glyph_words.get(glyph_index) {
+                    let (min, max) = word_spans[word];
+                    mesh.set_word(first_word + word, min + dx, max - min);
+                }
+                if attrs.ruby.is_some() {
+                    match ruby_runs.last_mut() {
+                        Some(ruby) if ruby.segment == glyph.metadata => {
+                            ruby.min = ruby.min.min(glyph.x + dx);
+                            ruby.max = ruby.max.max(glyph.x + glyph.w + dx);
+                            ruby.font_size = ruby.font_size.max(glyph.font_size);
+                        }
+                        _ => ruby_runs.push(RubyRun {
+                            segment: glyph.metadata,
+                            font_id: glyph.font_id,
+                            font_size: glyph.font_size,
+                            min: glyph.x + dx,
+                            max: glyph.x + glyph.w + dx,
+                            index: real_index,
+                            advance: advance + glyph.x,
+                        }),
+                    }
+                }
+                let is_dynamic =
+                    dynamic_mesh.is_some() && matches!(segment, Text3dSegment::Extract(_));
+                if let Some(info) = &mut layout_info {
+                    let line = info.lines.len();
+                    info.glyphs.push(GlyphLayoutInfo {
+                        index: real_index,
+                        segment: glyph.metadata,
+                        line,
+                        cluster: offsets.to_source(line_offset + glyph.start)
+                            ..offsets.to_source(line_offset + glyph.end),
+                        rect: Rect::new(
+                            glyph.x + dx,
+                            line_bottom,
+                            glyph.x + glyph.w + dx,
+                            line_top,
+                        ),
+                        rtl: glyph.level.is_rtl(),
+                        quad: None,
+                        atlas_rect: None,
+                        vertices: 0..0,
+                        dynamic: is_dynamic,
+                        tag: attrs.tag.clone(),
+                    });
+                }
+                if let Some(usage) = &mut font_usage {
+                    usage.record(font_system, glyph.metadata, glyph.font_id);
+                }
+                if reuse_static && !is_dynamic {
+                    if let (Some(info), Some((quad, atlas_rect, vertices))) = (
+                        layout_info.as_mut().and_then(|x| x.glyphs.last_mut()),
+                        reused_glyphs.next(),
+                    ) {
+                        info.quad = *quad;
+                        info.atlas_rect = *atlas_rect;
+                        info.vertices = vertices.clone();
+                    }
+                    real_index += 1;
+                    continue;
+                }
+                // Draw fetched glyphs into the dynamic mesh.
+                if is_dynamic {
+                    if let Some(dynamic_mesh) = &mut dynamic_mesh {
+                        std::mem::swap(&mut mesh, dynamic_mesh);
+                    }
+                }
+                let vertex_start = mesh.positions.len();
+                let line_extent = (min_x, max_x);
+                (min_x, max_x) = (f32::MAX, f32::MIN);
 
-                styling.fill_draw_requests(attrs, &mut draw_requests);
+                styling.fill_draw_requests(attrs, drawers.as_deref(), &mut draw_requests);
 
                 let magic_number = attrs.magic_number.unwrap_or(0.);
 
+                if let Text3dSegment::Image(_) = segment {
+                    draw_requests.clear();
+                    let icon = icons
+                        .as_ref()
+                        .and_then(|icons| segment_icon(icons, segment))
+                        .and_then(|icon| {
+                            Some((icon, atlas.glyphs.get(&icon_entry(icon.image.id()))?.0))
+                        });
+                    if let Some((icon, pixel_rect)) = icon {
+                        let min =
+                            Vec2::new(glyph.x + dx, -run.line_y + icon.baseline * glyph.font_size);
+                        let rect = Rect::from_corners(min, min + icon.size * glyph.font_size);
+                        min_x = min_x.min(rect.min.x);
+                        max_x = max_x.max(rect.max.x);
+                        if let Some(info) = layout_info.as_mut().and_then(|x| x.glyphs.last_mut()) {
+                            info.quad = Some(rect);
+                            info.atlas_rect = Some(pixel_rect);
+                        }
+                        mesh.cache_rectangle2(
+                            rect,
+                            pixel_rect,
+                            attrs.fill_color.unwrap_or(Srgba::WHITE),
+                            (Layer::Higher | Layer::NoShadow).with_bias(attrs.layer.unwrap_or(0)),
+                            real_index,
+                            advance + glyph.x,
+                            magic_number,
+                            &styling,
+                        );
+                    }
+                }
+
                 for DrawRequest {
                     request,
                     color,
@@ -224,8 +729,21 @@ pub fn text_render(
                 } in draw_requests.drain(..)
                 {
                     match request {
-                        DrawType::Glyph(stroke) => {
-                            let Some((pixel_rect, base)) = get_atlas_rect(
+                        DrawType::Glyph(stroke)
+                        | DrawType::Blurred(stroke, _)
+                        | DrawType::Glow(stroke, _) => {
+                            let raster = match request {
+                                DrawType::Blurred(_, blur) => {
+                                    GlyphRaster::Blur(FloatOrd(blur), quality.blur_passes())
+                                }
+                                DrawType::Glow(_, glow) => GlyphRaster::Glow(
+                                    FloatOrd(glow.radius),
+                                    FloatOrd(glow.intensity),
+                                    quality.blur_passes(),
+                                ),
+                                _ => GlyphRaster::Coverage,
+                            };
+                            let Some((pixel_rect, base, size)) = get_atlas_rect(
                                 font_system,
                                 scale_factor,
                                 &styling,
@@ -235,6 +753,15 @@ pub fn text_render(
                                 glyph,
                                 attrs,
                                 stroke,
+                                raster,
+                                (settings.asynchronous_rasterization || worker.is_some()).then(
+                                    || {
+                                        (
+                                            pending_glyphs.entry(atlas_id).or_default(),
+                                            settings.glyph_placeholder,
+                                        )
+                                    },
+                                ),
                             ) else {
                                 continue;
                             };
@@ -243,17 +770,37 @@ pub fn text_render(
 
                             min_x = min_x.min(dw + dx);
                             max_x = max_x.max(dw + dx + glyph.w);
+                            if let Some((cut, _)) = clip {
+                                max_x = max_x.min(cut);
+                            }
 
                             let base = Vec2::new(glyph.x, glyph.y)
                                 + base
                                 + offset
                                 + Vec2::new(dx, -run.line_y);
 
-                            mesh.cache_rectangle(
-                                base,
+                            if stroke.is_none()
+                                && offset == Vec2::ZERO
+                                && raster == GlyphRaster::Coverage
+                            {
+                                if let Some(info) =
+                                    layout_info.as_mut().and_then(|x| x.glyphs.last_mut())
+                                {
+                                    info.quad = Some(Rect {
+                                        min: base,
+                                        max: base + size,
+                                    });
+                                    info.atlas_rect = Some(pixel_rect);
+                                }
+                            }
+
+                            mesh.cache_rectangle2(
+                                Rect {
+                                    min: base,
+                                    max: base + size,
+                                },
                                 pixel_rect,
                                 color,
-                                scale_factor,
                                 layer,
                                 real_index,
                                 advance + dw,
@@ -261,6 +808,44 @@ pub fn text_render(
                                 &styling,
                             );
                         }
+                        DrawType::Custom(drawer) => {
+                            let input = DrawerInput {
+                                style: attrs,
+                                font_size: glyph.font_size,
+                                rect: Rect::new(
+                                    0.0,
+                                    line_bottom + run.line_y,
+                                    glyph.w,
+                                    line_top + run.line_y,
+                                ),
+                                color,
+                                scale_factor,
+                            };
+                            let Some(rect) = drawer.quad(&input) else {
+                                continue;
+                            };
+                            let Some(pixel_rect) = drawer_texture(atlas, image, &drawer, &input)
+                            else {
+                                continue;
+                            };
+                            let origin = Vec2::new(glyph.x + dx, -run.line_y) + offset;
+                            let rect = Rect {
+                                min: rect.min + origin,
+                                max: rect.max + origin,
+                            };
+                            min_x = min_x.min(rect.min.x);
+                            max_x = max_x.max(rect.max.x);
+                            mesh.cache_rectangle2(
+                                rect,
+                                pixel_rect,
+                                drawer.color(&input),
+                                layer,
+                                real_index,
+                                advance + glyph.x,
+                                magic_number,
+                                &styling,
+                            );
+                        }
                         DrawType::Line(stroke, mode) => {
                             let line = mode.select(&mut underline_run, &mut strikethrough_run);
                             if !line.contains(glyph) {
@@ -277,12 +862,13 @@ pub fn text_render(
                             let Some(uv_rect) = mode.get_atlas_rect(
                                 font_system,
                                 glyph.font_id,
+                                glyph.font_size,
                                 scale_factor,
                                 atlas,
                                 image,
                                 &mut tess_commands,
-                                attrs,
-                                &styling,
+                                styling.stroke_join,
+                                attrs.weight.unwrap_or(styling.weight),
                                 stroke,
                             ) else {
                                 continue;
@@ -294,7 +880,7 @@ pub fn text_render(
                             {
                                 let Some(rect) = mode.get_line_rect(
                                     font_system,
-                                    styling.size,
+                                    glyph.font_size,
                                     min,
                                     max,
                                     stroke_size,
@@ -302,10 +888,21 @@ pub fn text_render(
                                 ) else {
                                     continue;
                                 };
-                                let rect = Rect {
+                                let mut rect = Rect {
                                     min: rect.min + offset + Vec2::new(dx, -run.line_y),
                                     max: rect.max + offset + Vec2::new(dx, -run.line_y),
                                 };
+                                let mut uv_max = uv_max;
+                                if let Some((cut, _)) = clip {
+                                    if rect.min.x >= cut {
+                                        continue;
+                                    }
+                                    if rect.max.x > cut {
+                                        uv_max = uv_min
+                                            + (uv_max - uv_min) * (cut - rect.min.x) / rect.width();
+                                        rect.max.x = cut;
+                                    }
+                                }
                                 let result_rect = Rect {
                                     min: Vec2::new(
                                         uv_rect.min.x + uv_rect.size().x * uv_min,
@@ -330,9 +927,186 @@ pub fn text_render(
                         }
                     };
                 }
+                if let Some((cut, fade)) = clip {
+                    mesh.fade_right(vertex_start, cut - fade, cut);
+                }
+                if let Some(path) = &path {
+                    let center = glyph.x + glyph.w / 2.0 + dx;
+                    let (position, tangent) =
+                        path.sample(center + path_length * styling.align.as_fac());
+                    mesh.place_on_path(
+                        vertex_start,
+                        Vec2::new(center, path_baseline),
+                        position,
+                        tangent,
+                    );
+                }
+                if let Some(info) = layout_info.as_mut().and_then(|x| x.glyphs.last_mut()) {
+                    info.vertices = vertex_start..mesh.positions.len();
+                    if let Some(glyphs) = recorded_layout
+                        .as_mut()
+                        .and_then(|x| x.glyphs.as_mut())
+                        .filter(|_| !is_dynamic)
+                    {
+                        glyphs.push((info.quad, info.atlas_rect, info.vertices.clone()));
+                    }
+                }
+                if !is_dynamic {
+                    static_extent = merge_extent(static_extent, (min_x, max_x));
+                }
+                (min_x, max_x) = merge_extent(line_extent, (min_x, max_x));
+                if is_dynamic {
+                    if let Some(dynamic_mesh) = &mut dynamic_mesh {
+                        std::mem::swap(&mut mesh, dynamic_mesh);
+                    }
+                }
                 real_index += 1;
             }
+            for ruby in ruby_runs {
+                let Some((segment, attrs)) = text.segments.get(ruby.segment) else {
+                    continue;
+                };
+                let Some(annotation) = &attrs.ruby else {
+                    continue;
+                };
+                let is_dynamic =
+                    dynamic_mesh.is_some() && matches!(segment, Text3dSegment::Extract(_));
+                if reuse_static && !is_dynamic {
+                    continue;
+                }
+                if clip.is_some_and(|(cut, _)| ruby.min >= cut) {
+                    continue;
+                }
+                let size = ruby.font_size * styling.ruby_size;
+                let (glyphs, ruby_width) =
+                    shape_ruby(font_system, annotation, attrs, &styling, size);
+                // Centered above the base glyphs with room for the annotation's descenders.
+                let x = (ruby.min + ruby.max - ruby_width) / 2.0;
+                let y = -run.line_y
+                    + font_ascent(font_system, ruby.font_id, ruby.font_size)
+                    + size * 0.25;
+                min_x = min_x.min(x);
+                max_x = max_x.max(x + ruby_width);
+                if !is_dynamic {
+                    static_extent = merge_extent(static_extent, (x, x + ruby_width));
+                }
+                if is_dynamic {
+                    if let Some(dynamic_mesh) = &mut dynamic_mesh {
+                        std::mem::swap(&mut mesh, dynamic_mesh);
+                    }
+                }
+                let vertex_start = mesh.positions.len();
+                let magic_number = attrs.magic_number.unwrap_or(0.);
+                // Decoration lines and custom drawers only apply to the base text.
+                styling.fill_draw_requests(attrs, None, &mut draw_requests);
+                for glyph in &glyphs {
+                    for DrawRequest {
+                        request,
+                        color,
+                        offset,
+                        sort: layer,
+                    } in &draw_requests
+                    {
+                        let (stroke, raster) = match request {
+                            DrawType::Glyph(stroke) => (*stroke, GlyphRaster::Coverage),
+                            DrawType::Blurred(stroke, blur) => (
+                                *stroke,
+                                GlyphRaster::Blur(FloatOrd(*blur), quality.blur_passes()),
+                            ),
+                            DrawType::Glow(stroke, glow) => (
+                                *stroke,
+                                GlyphRaster::Glow(
+                                    FloatOrd(glow.radius),
+                                    FloatOrd(glow.intensity),
+                                    quality.blur_passes(),
+                                ),
+                            ),
+                            DrawType::Line(..) | DrawType::Custom(_) => continue,
+                        };
+                        let Some((pixel_rect, base, quad_size)) = get_atlas_rect(
+                            font_system,
+                            scale_factor,
+                            &styling,
+                            atlas,
+                            image,
+                            &mut tess_commands,
+                            glyph,
+                            attrs,
+                            stroke,
+                            raster,
+                            (settings.asynchronous_rasterization || worker.is_some()).then(|| {
+                                (
+                                    pending_glyphs.entry(atlas_id).or_default(),
+                                    settings.glyph_placeholder,
+                                )
+                            }),
+                        ) else {
+                            continue;
+                        };
+                        let min = Vec2::new(x + glyph.x, y + glyph.y) + base + *offset;
+                        mesh.cache_rectangle2(
+                            Rect {
+                                min,
+                                max: min + quad_size,
+                            },
+                            pixel_rect,
+                            *color,
+                            *layer,
+                            ruby.index,
+                            ruby.advance,
+                            magic_number,
+                            &styling,
+                        );
+                    }
+                }
+                if let Some((cut, fade)) = clip {
+                    mesh.fade_right(vertex_start, cut - fade, cut);
+                }
+                if let Some(path) = &path {
+                    let center = (ruby.min + ruby.max) / 2.0;
+                    let (position, tangent) =
+                        path.sample(center + path_length * styling.align.as_fac());
+                    mesh.place_on_path(
+                        vertex_start,
+                        Vec2::new(center, path_baseline),
+                        position,
+                        tangent,
+                    );
+                }
+                if is_dynamic {
+                    if let Some(dynamic_mesh) = &mut dynamic_mesh {
+                        std::mem::swap(&mut mesh, dynamic_mesh);
+                    }
+                }
+            }
+            if let Some(info) = &mut layout_info {
+                let start = run.glyphs.iter().map(|g| g.start).min().unwrap_or(0);
+                let end = run.glyphs.iter().map(|g| g.end).max().unwrap_or(0);
+                let glyphs = first_glyph..info.glyphs.len();
+                info.lines.push(LineLayoutInfo {
+                    glyphs,
+                    bytes: offsets.to_source(line_offset + start)
+                        ..offsets.to_source(line_offset + end),
+                    rect: Rect::new(dx, line_bottom, dx + run.line_w, line_top),
+                    baseline: -run.line_y,
+                });
+            }
+            line_extents.push((min_x, max_x, run.line_w));
+            if let Some(layout) = &mut recorded_layout {
+                layout.extents.push(static_extent);
+            }
+            min_x = min_x.min(extent.0);
+            max_x = max_x.max(extent.1);
             advance += run.line_w;
+            first_word += word_spans.len();
+        }
+
+        if truncated {
+            truncated_events.write(Text3dTruncated {
+                entity,
+                glyphs: buffer.layout_runs().map(|run| run.glyphs.len()).sum(),
+                max_glyphs: styling.max_glyphs,
+            });
         }
 
         if max_x < min_x {
@@ -340,24 +1114,491 @@ pub fn text_render(
             max_x = 0.001;
         }
 
-        let dimension = Vec2::new(max_x - min_x, height);
-        let center = Vec2::new((max_x + min_x) / 2., -height / 2.);
+        let (bb_min, dimension) = match (&path, mesh.bounding_box()) {
+            (Some(_), Some(rect)) => (rect.min, rect.size()),
+            _ => (Vec2::new(min_x, -height), Vec2::new(max_x - min_x, height)),
+        };
+        let center = bb_min + dimension / 2.;
         let offset = *styling.anchor * dimension - center;
-        let bb_min = Vec2::new(min_x, -height);
 
-        mesh.post_process_uv1(&styling, bb_min, dimension);
+        let transform = |v: Vec2| match styling.world_scale {
+            Some(world_scale) => (v + offset) * world_scale / styling.size,
+            None => v + offset,
+        };
 
-        if let Some(world_scale) = styling.world_scale {
-            mesh.translate(|v| *v = (*v + offset) * world_scale / styling.size);
-        } else {
-            mesh.translate(|v| *v += offset);
+        if let Some(info) = &mut layout_info {
+            info.transform(transform);
+        }
+
+        if let Some(editor) = &mut editor {
+            let transform_rect =
+                |rect: Rect| Rect::from_corners(transform(rect.min), transform(rect.max));
+            let editor = editor.bypass_change_detection();
+            editor.caret_rect = caret_rect.map(transform_rect);
+            editor.selection_rects.clear();
+            editor
+                .selection_rects
+                .extend(selection_rects.into_iter().map(transform_rect));
         }
 
         output.dimension = dimension;
+        remeshed_events.write(TextRemeshed { entity, dimension });
         output.atlas_dimension = IVec2::new(image.width() as i32, image.height() as i32);
+        output.patch = (layout_info.is_some() && path.is_none()).then(|| PatchState {
+            styling: styling.clone(),
+            offset,
+            height,
+            lines: line_extents,
+        });
+
+        if let Some(dynamic) = &mut dynamic {
+            dynamic.bypass_change_detection().static_layout = reused_layout.or(recorded_layout);
+        }
+
+        pending.push((
+            entity,
+            (!reuse_static).then_some((mesh_id, mesh)),
+            dynamic_mesh
+                .zip(dynamic.as_ref())
+                .map(|(mesh, dynamic)| (dynamic.entity, mesh)),
+            MeshPostProcess {
+                uv1: styling.uv1,
+                glyph_meta: styling.glyph_meta,
+                bb_min,
+                dimension,
+                offset,
+                scale: styling.world_scale.map(|x| x / styling.size),
+                atlas_size: (!settings.pixel_uvs)
+                    .then(|| Vec2::new(image.width() as f32, image.height() as f32)),
+            },
+        ));
+    }
+
+    // Send missing glyphs to rasterization threads.
+    if let Some(worker) = &mut worker {
+        for (id, glyphs) in pending_glyphs.iter_mut() {
+            let Some(width) = atlases
+                .get(*id)
+                .and_then(|atlas| images.get(atlas.image.id()))
+                .map(|image| image.width() as usize)
+            else {
+                glyphs.clear();
+                continue;
+            };
+            for (entry, glyph) in glyphs.drain() {
+                let Some(font) = lock.font_system.get_font(glyph.glyph.font_id) else {
+                    continue;
+                };
+                worker.request(*id, entry, font, glyph, width);
+            }
+        }
+    }
+
+    // Glyphs are already cached, meshes can be finalized in parallel.
+    drop(lock);
+    clear_queue.cleared.clear();
+
+    // Rasterize missing glyphs in the background, unless a task is already in flight for this atlas.
+    for (id, glyphs) in pending_glyphs.iter_mut() {
+        if glyphs.is_empty() || rasterizing.contains(id) {
+            continue;
+        }
+        let Some(width) = atlases
+            .get(*id)
+            .and_then(|atlas| images.get(atlas.image.id()))
+            .map(|image| image.width() as usize)
+        else {
+            glyphs.clear();
+            continue;
+        };
+        rasterizing.insert(*id);
+        let task = renderer.rasterize_task(*id, width, glyphs.drain().collect());
+        AsyncComputeTaskPool::get_or_init(TaskPool::default)
+            .spawn(async move { task() })
+            .detach();
+    }
+
+    let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    pending.par_splat_map_mut(task_pool, None, |_, chunk| {
+        for (_, mesh, dynamic, post) in chunk {
+            if let Some((_, mesh)) = mesh {
+                mesh.finish(post);
+            }
+            if let Some((_, dynamic)) = dynamic {
+                dynamic.finish(post);
+            }
+        }
+    });
+
+    for (entity, extracted, dynamic, _) in pending.drain(..) {
+        if let Some((target, dynamic)) = dynamic {
+            let Ok((mut mesh2d, mut mesh3d)) = dynamic_targets.get_mut(target) else {
+                continue;
+            };
+            let Some(mesh) = get_mesh(&mut mesh2d, &mut mesh3d, &mut meshes) else {
+                continue;
+            };
+            if settings.update_aabb {
+                match dynamic.aabb() {
+                    Some(aabb) => commands.entity(target).try_insert(aabb),
+                    None => commands.entity(target).try_remove::<Aabb>(),
+                };
+            }
+            dynamic.write(mesh);
+            // Skip the static mesh if not drawn or unchanged.
+            let Some((id, extracted)) = &extracted else {
+                continue;
+            };
+            let hash = extracted.hash();
+            let Ok((.., Some(mut split))) = text_query.get_mut(entity) else {
+                continue;
+            };
+            if split.static_mesh == Some((*id, hash)) {
+                continue;
+            }
+            split.static_mesh = Some((*id, hash));
+        }
+        let Some((id, extracted)) = extracted else {
+            continue;
+        };
+        let Some(mesh) = meshes.get_mut(id) else {
+            continue;
+        };
+        // Bevy does not update bounding boxes when a mesh is modified.
+        if settings.update_aabb {
+            match extracted.aabb() {
+                Some(aabb) => commands.entity(entity).try_insert(aabb),
+                None => commands.entity(entity).try_remove::<Aabb>(),
+            };
+        }
+        extracted.write(mesh);
+        if !settings.share_identical_meshes {
+            continue;
+        }
+        let hash = hash_mesh(mesh);
+        let Ok((.., mut mesh2d, mut mesh3d, _, _, _, _, _, _, _, _)) = text_query.get_mut(entity)
+        else {
+            continue;
+        };
+        let Some(handle) = mesh_handle(&mesh2d, &mesh3d).cloned() else {
+            continue;
+        };
+        if let Some(shared) = shared_meshes.share(hash, &handle, &meshes) {
+            set_mesh_handle(&mut mesh2d, &mut mesh3d, shared);
+        }
+    }
+}
+
+/// Find the narrowest wrap width that keeps the line count of a 2 or 3 line paragraph.
+fn balance_lines(buffer: &mut Buffer, font_system: &mut FontSystem) {
+    let lines = buffer.layout_runs().count();
+    if !(2..=3).contains(&lines) {
+        return;
+    }
+    let mut max = buffer
+        .layout_runs()
+        .map(|run| run.line_w)
+        .fold(0.0f32, f32::max);
+    let mut min = max / lines as f32;
+    for _ in 0..8 {
+        let mid = (min + max) / 2.0;
+        buffer.set_size(font_system, Some(mid), None);
+        buffer.shape_until_scroll(font_system, true);
+        if buffer.layout_runs().count() > lines {
+            min = mid;
+        } else {
+            max = mid;
+        }
+    }
+    buffer.set_size(font_system, Some(max), None);
+    buffer.shape_until_scroll(font_system, true);
+}
+
+/// Indent and horizontal offset of a line,
+/// `bullet_indent` is the paragraph and indent of wrapped lines of the current list item.
+fn line_dx(
+    run: &LayoutRun,
+    styling: &Text3dStyling,
+    bullet_indent: &mut (usize, f32),
+) -> (f32, f32) {
+    let indent = if run.line_i != bullet_indent.0 {
+        *bullet_indent = (run.line_i, list_item_indent(run));
+        0.0
+    } else {
+        bullet_indent.1
+    };
+    let mut dx = indent - run.line_w * styling.align.as_fac();
+    if let Some(fac) = styling.hanging_punctuation {
+        let (left, right) = hanging_punctuation(run, fac);
+        dx += -left + (left + right) * styling.align.as_fac();
+    }
+    (indent, dx)
+}
+
+fn merge_extent(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0.min(b.0), a.1.max(b.1))
+}
+
+/// Maps byte offsets between the buffer's text and the concatenated text of the segments.
+#[derive(Debug, Default)]
+pub(crate) struct BufferOffsets {
+    /// Byte offset of each [`BufferLine`](cosmic_text::BufferLine) in the buffer's text,
+    /// including line endings of any kind.
+    lines: Vec<usize>,
+    /// Byte offsets of the no-break spaces in the buffer's text,
+    /// each is a byte longer than the space it replaces.
+    nowrap_spaces: Vec<usize>,
+}
+
+impl BufferOffsets {
+    /// Map a byte offset in the buffer's text to the concatenated text of the segments.
+    fn to_source(&self, offset: usize) -> usize {
+        offset - self.nowrap_spaces.partition_point(|i| *i < offset)
+    }
+
+    /// Map a byte offset in the concatenated text of the segments to a [`Cursor`] in the buffer.
+    pub(crate) fn to_cursor(&self, index: usize, affinity: Affinity) -> Cursor {
+        let shift = self
+            .nowrap_spaces
+            .iter()
+            .enumerate()
+            .take_while(|(k, i)| *i - k < index)
+            .count();
+        let offset = index + shift;
+        let line = self
+            .lines
+            .partition_point(|x| *x <= offset)
+            .saturating_sub(1);
+        let start = self.lines.get(line).copied().unwrap_or(0);
+        Cursor::new_with_affinity(line, offset - start, affinity)
+    }
+}
+
+/// Hash of the layout the static glyphs of a [`Text3dDynamicMesh`] are drawn from,
+/// including the position of fetched glyphs since lines and decorations may span them.
+///
+/// `None` if glyphs may be truncated.
+fn static_layout_key(buffer: &Buffer, text: &Text3d, styling: &Text3dStyling) -> Option<u64> {
+    let mut hasher = FxHasher::default();
+    let mut bullet_indent = (usize::MAX, 0.0);
+    let mut glyph_count = 0;
+    for run in buffer.layout_runs() {
+        let (indent, dx) = line_dx(&run, styling, &mut bullet_indent);
+        (run.line_i, words(&run).1.len()).hash(&mut hasher);
+        for x in [
+            indent,
+            dx,
+            run.line_y,
+            run.line_top,
+            run.line_height,
+            run.line_w,
+        ] {
+            x.to_bits().hash(&mut hasher);
+        }
+        for glyph in run.glyphs {
+            glyph_count += 1;
+            (glyph.metadata, glyph.start, glyph.end).hash(&mut hasher);
+            for x in [glyph.x, glyph.y, glyph.w, glyph.font_size] {
+                x.to_bits().hash(&mut hasher);
+            }
+            if !matches!(
+                text.segments.get(glyph.metadata),
+                Some((Text3dSegment::Extract(_), _))
+            ) {
+                (glyph.font_id, glyph.glyph_id).hash(&mut hasher);
+            }
+        }
+    }
+    (glyph_count <= styling.max_glyphs).then(|| hasher.finish())
+}
+
+fn list_item_indent(run: &LayoutRun) -> f32 {
+    if !run.text.starts_with(BULLET) {
+        return 0.0;
+    }
+    run.glyphs
+        .iter()
+        .find(|glyph| glyph.start >= BULLET.len())
+        .map(|glyph| glyph.x)
+        .unwrap_or(0.0)
+}
+
+fn is_hanging_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        '.' | ',' | ':' | ';' | '-' | '\'' | '"' | '\u{2010}'..='\u{2015}' | '\u{2018}'..='\u{201F}'
+            | '\u{00AB}' | '\u{00BB}' | '\u{2039}' | '\u{203A}' | '\u{3001}' | '\u{3002}'
+    )
+}
+
+/// Returns how much the first and last glyph in a line hangs outside of the margin.
+fn hanging_punctuation(run: &LayoutRun, fac: f32) -> (f32, f32) {
+    let hang = |glyph: &LayoutGlyph| {
+        let mut chars = run.text.get(glyph.start..glyph.end)?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if is_hanging_punctuation(c) => Some(glyph.w * fac),
+            _ => None,
+        }
+    };
+    let is_whitespace = |glyph: &&LayoutGlyph| {
+        run.text
+            .get(glyph.start..glyph.end)
+            .is_some_and(|s| s.chars().all(char::is_whitespace))
+    };
+    let left = run
+        .glyphs
+        .iter()
+        .find(|g| !is_whitespace(g))
+        .and_then(hang)
+        .unwrap_or(0.0);
+    let right = run
+        .glyphs
+        .iter()
+        .rev()
+        .find(|g| !is_whitespace(g))
+        .and_then(hang)
+        .unwrap_or(0.0);
+    (left, right)
+}
+
+/// Count lines and paragraphs that end with a single word on their own line.
+fn count_widows(buffer: &Buffer) -> (usize, usize) {
+    let mut lines = 0;
+    let mut widows = 0;
+    let mut prev_line = usize::MAX;
+    let mut runs = buffer.layout_runs().peekable();
+    while let Some(run) = runs.next() {
+        lines += 1;
+        let is_first = run.line_i != prev_line;
+        prev_line = run.line_i;
+        let is_last = runs.peek().is_none_or(|next| next.line_i != run.line_i);
+        if is_first || !is_last {
+            continue;
+        }
+        let (Some(first), Some(last)) = (run.glyphs.first(), run.glyphs.last()) else {
+            continue;
+        };
+        let (start, end) = (first.start.min(last.start), first.end.max(last.end));
+        if run
+            .text
+            .get(start..end)
+            .is_some_and(|s| !s.trim().contains(char::is_whitespace))
+        {
+            widows += 1;
+        }
+    }
+    (lines, widows)
+}
 
-        mesh.pixel_to_uv(image);
+/// Narrow the wrap width within `tolerance` to pull words down onto widowed lines.
+fn avoid_widows(buffer: &mut Buffer, font_system: &mut FontSystem, tolerance: f32) {
+    let (lines, widows) = count_widows(buffer);
+    if widows == 0 {
+        return;
+    }
+    let original = buffer.size().0;
+    let widest = buffer
+        .layout_runs()
+        .map(|run| run.line_w)
+        .fold(0.0f32, f32::max);
+    const STEPS: usize = 8;
+    for step in 1..=STEPS {
+        let width = widest * (1.0 - tolerance * step as f32 / STEPS as f32);
+        buffer.set_size(font_system, Some(width), None);
+        buffer.shape_until_scroll(font_system, true);
+        let (new_lines, new_widows) = count_widows(buffer);
+        if new_lines <= lines && new_widows < widows {
+            return;
+        }
+    }
+    buffer.set_size(font_system, original, None);
+    buffer.shape_until_scroll(font_system, true);
+}
+
+fn segment_icon<'t>(icons: &'t Text3dIcons, segment: &Text3dSegment) -> Option<&'t Text3dIcon> {
+    match segment {
+        Text3dSegment::Image(name) => icons.get(name),
+        _ => None,
+    }
+}
+
+/// Word of each glyph in a line, `None` for whitespace, and the horizontal range of each word.
+fn words(run: &LayoutRun) -> (Vec<Option<usize>>, Vec<(f32, f32)>) {
+    let mut glyph_words = Vec::with_capacity(run.glyphs.len());
+    let mut spans: Vec<(f32, f32)> = Vec::new();
+    let mut in_word = false;
+    for glyph in run.glyphs {
+        let whitespace = run
+            .text
+            .get(glyph.start..glyph.end)
+            .is_some_and(|x| x.chars().all(char::is_whitespace));
+        if whitespace {
+            in_word = false;
+            glyph_words.push(None);
+            continue;
+        }
+        match spans.last_mut() {
+            Some((min, max)) if in_word => {
+                *min = min.min(glyph.x);
+                *max = max.max(glyph.x + glyph.w);
+            }
+            _ => spans.push((glyph.x, glyph.x + glyph.w)),
+        }
+        in_word = true;
+        glyph_words.push(Some(spans.len() - 1));
+    }
+    (glyph_words, spans)
+}
+
+/// Base glyphs of a ruby annotation on a line.
+struct RubyRun {
+    segment: usize,
+    font_id: ID,
+    font_size: f32,
+    min: f32,
+    max: f32,
+    /// Glyph index and advance of the first base glyph, shared by the annotation's glyphs.
+    index: usize,
+    advance: f32,
+}
+
+/// Shape a ruby annotation on a single line, returns its glyphs and width.
+fn shape_ruby(
+    font_system: &mut FontSystem,
+    annotation: &str,
+    attrs: &SegmentStyle,
+    styling: &Text3dStyling,
+    size: f32,
+) -> (Vec<LayoutGlyph>, f32) {
+    let metrics = Metrics::new(size, size);
+    let mut buffer = Buffer::new(font_system, metrics);
+    buffer.set_wrap(font_system, Wrap::None);
+    buffer.set_text(
+        font_system,
+        annotation,
+        &attrs.as_attr(styling).metrics(metrics),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(font_system, false);
+    let mut width = 0.0f32;
+    let mut glyphs = Vec::new();
+    for run in buffer.layout_runs() {
+        width = width.max(run.line_w);
+        glyphs.extend(run.glyphs.iter().cloned());
     }
+    (glyphs, width)
+}
+
+/// Ascent of a font at `size`.
+fn font_ascent(font_system: &mut FontSystem, id: ID, size: f32) -> f32 {
+    font_system
+        .db()
+        .with_face_data(id, |file, _| {
+            let face = Face::parse(file, 0).ok()?;
+            Some(face.ascender() as f32 / face.units_per_em() as f32 * size)
+        })
+        .flatten()
+        .unwrap_or(size * 0.8)
 }
 
 fn get_atlas_rect(
@@ -370,17 +1611,49 @@ fn get_atlas_rect(
     glyph: &LayoutGlyph,
     attrs: &SegmentStyle,
     stroke: Option<NonZero<u32>>,
-) -> Option<(Rect, Vec2)> {
+    raster: GlyphRaster,
+    deferred: Option<(&mut FxHashMap<GlyphEntry, PendingGlyph>, GlyphPlaceholder)>,
+) -> Option<(Rect, Vec2, Vec2)> {
+    let padding = attrs.padding.unwrap_or(styling.glyph_padding);
+    let variations = attrs.variations(styling);
+    let raster = match raster {
+        GlyphRaster::Coverage
+            if styling.two_tone && stroke.is_some() && attrs.fill.unwrap_or(styling.fill) =>
+        {
+            GlyphRaster::TwoTone
+        }
+        raster => raster,
+    };
+    let entry = GlyphEntry {
+        font: glyph.font_id,
+        glyph_id: raster.texture_of(glyph.glyph_id),
+        size: FloatOrd(glyph.font_size),
+        weight: attrs.weight.unwrap_or(styling.weight),
+        join: styling.stroke_join,
+        stroke,
+        padding,
+        scale_factor: FloatOrd(scale_factor),
+        variations: variations_key(&variations),
+        outline: styling.outline_key(),
+    };
+    if let Some((deferred, placeholder)) = deferred {
+        if !atlas.glyphs.contains_key(&entry) {
+            deferred.entry(entry).or_insert_with(|| PendingGlyph {
+                glyph: glyph.clone(),
+                stroke,
+                outline: styling.stroke_outline(),
+                weight: attrs.weight.unwrap_or(styling.weight).into(),
+                padding,
+                scale_factor,
+                variations: variations.into_owned(),
+                raster,
+            });
+            return placeholder_rect(atlas, image, glyph, entry, placeholder);
+        }
+    }
     atlas
         .glyphs
-        .get(&GlyphEntry {
-            font: glyph.font_id,
-            glyph_id: glyph.glyph_id.into(),
-            size: FloatOrd(glyph.font_size),
-            weight: styling.weight,
-            join: styling.stroke_join,
-            stroke,
-        })
+        .get(&entry)
         .copied()
         .or_else(|| {
             font_system
@@ -396,14 +1669,76 @@ fn get_atlas_rect(
                         tess_commands,
                         glyph,
                         stroke,
-                        styling.stroke_join,
+                        &styling.stroke_outline(),
                         attrs.weight.unwrap_or(styling.weight).into(),
+                        padding,
+                        &variations,
+                        raster,
                         face,
                     )
                 })
                 .flatten()
         })
-        .map(|(rect, offset)| (rect, offset / scale_factor))
+        .map(|(rect, offset)| (rect, offset / scale_factor, rect.size() / scale_factor))
+}
+
+/// Obtain the atlas rectangle, offset and size of a glyph waiting to be rasterized.
+fn placeholder_rect(
+    atlas: &mut TextAtlas,
+    image: &mut Image,
+    glyph: &LayoutGlyph,
+    entry: GlyphEntry,
+    placeholder: GlyphPlaceholder,
+) -> Option<(Rect, Vec2, Vec2)> {
+    let scale_factor = entry.scale_factor.0;
+    match placeholder {
+        GlyphPlaceholder::Invisible => None,
+        GlyphPlaceholder::Box => {
+            let placeholder = GlyphEntry {
+                font: ID::dummy(),
+                glyph_id: GlyphTextureOf::Placeholder,
+                size: FloatOrd(0.0),
+                stroke: None,
+                padding: 0,
+                scale_factor: FloatOrd(0.0),
+                variations: 0,
+                ..entry
+            };
+            let rect = match atlas.glyphs.get(&placeholder) {
+                Some((rect, _)) => *rect,
+                None => cache_pixels(atlas, image, placeholder, 4, 4, &[[255, 255, 255, 96]; 16]),
+            };
+            // Sample the center to avoid bleeding.
+            let rect = Rect {
+                min: rect.min + 1.0,
+                max: rect.max - 1.0,
+            };
+            let size = glyph.font_size;
+            Some((
+                rect,
+                Vec2::new(glyph.w * 0.1, 0.0),
+                Vec2::new(glyph.w * 0.8, size * 0.7),
+            ))
+        }
+        GlyphPlaceholder::OtherSize => {
+            let (other, (rect, base)) = atlas
+                .glyphs
+                .iter()
+                .filter(|(other, _)| {
+                    GlyphEntry {
+                        size: entry.size,
+                        ..**other
+                    } == entry
+                })
+                .min_by_key(|(other, _)| FloatOrd((other.size.0 - entry.size.0).abs()))?;
+            let ratio = entry.size.0 / other.size.0;
+            Some((
+                *rect,
+                *base / scale_factor * ratio,
+                rect.size() / scale_factor * ratio,
+            ))
+        }
+    }
 }
 
 pub(crate) fn cache_glyph(
@@ -413,22 +1748,447 @@ pub(crate) fn cache_glyph(
     tess_commands: &mut CommandEncoder,
     glyph: &cosmic_text::LayoutGlyph,
     stroke: Option<NonZero<u32>>,
-    stroke_join: StrokeJoin,
+    outline: &StrokeOutline,
     weight: Weight,
-    face: Face,
+    padding: u16,
+    variations: &[(AxisTag, f32)],
+    raster: GlyphRaster,
+    mut face: Face,
 ) -> Option<(Rect, Vec2)> {
+    for (tag, value) in variations {
+        face.set_variation((*tag).into(), *value);
+    }
     let unit_per_em = face.units_per_em() as f32;
     let entry = GlyphEntry {
         font: glyph.font_id,
-        glyph_id: glyph.glyph_id.into(),
+        glyph_id: raster.texture_of(glyph.glyph_id),
         size: FloatOrd(glyph.font_size),
         weight: weight.into(),
         stroke,
-        join: stroke_join,
+        join: outline.join,
+        padding,
+        scale_factor: FloatOrd(scale_factor),
+        variations: variations_key(variations),
+        outline: StrokeOutline::key(outline.caps, outline.miter_limit, &outline.dash),
     };
     tess_commands.commands.clear();
     face.outline_glyph(GlyphId(glyph.glyph_id), tess_commands)?;
+    tess_commands.set_outline(outline, unit_per_em / 100.);
     let stroke = stroke.map(|x| x.get() as f32 * unit_per_em / 100.);
     let scale = glyph.font_size / unit_per_em * scale_factor;
-    tess_commands.tess_glyph(stroke, scale, atlas, image, entry)
+    match (raster, stroke) {
+        (GlyphRaster::TwoTone, Some(stroke)) => {
+            tess_commands.tess_two_tone(stroke, scale, atlas, image, entry)
+        }
+        (GlyphRaster::Blur(radius, passes), _) => tess_commands.tess_blurred(
+            stroke,
+            false,
+            radius.0 * scale_factor,
+            passes,
+            1.0,
+            scale,
+            atlas,
+            image,
+            entry,
+        ),
+        (GlyphRaster::Glow(radius, intensity, passes), _) => {
+            let radius = radius.0 * scale_factor;
+            // Grow the outline and its stroke by half the radius before blurring.
+            let dilate = stroke.unwrap_or(0.0) + radius / scale;
+            tess_commands.tess_blurred(
+                Some(dilate),
+                true,
+                radius,
+                passes,
+                intensity.0,
+                scale,
+                atlas,
+                image,
+                entry,
+            )
+        }
+        _ => tess_commands.tess_glyph(stroke, scale, atlas, image, entry),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use bevy::{
+        app::{App, Plugins},
+        asset::{AssetApp, AssetEvent, AssetPlugin, Assets},
+        ecs::{entity::Entity, event::Events},
+        image::Image,
+        math::{FloatOrd, Vec2},
+        pbr::StandardMaterial,
+        render::mesh::{Indices, Mesh, Mesh3d, VertexAttributeValues},
+        sprite::ColorMaterial,
+        MinimalPlugins,
+    };
+    use cosmic_text::fontdb::Database;
+
+    use crate::{
+        ClipShape, FetchedTextSegment, SegmentStyle, Text3d, Text3dClip, Text3dClipPlugin,
+        Text3dDynamicMesh, Text3dEditor, Text3dGlyphLayout, Text3dLayoutInfo, Text3dMarquee,
+        Text3dMarqueePlugin, Text3dPlugin, Text3dScaleFactorOverride, Text3dSegment, Text3dStyling,
+        TextAtlas, TextQuality, TextRenderer,
+    };
+
+    fn app() -> App {
+        app_with(())
+    }
+
+    fn app_with<M>(plugins: impl Plugins<M>) -> App {
+        let mut database = Database::new();
+        database.load_font_data(include_bytes!("../assets/Roboto-Regular.ttf").to_vec());
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .init_asset::<StandardMaterial>()
+            .insert_resource(TextRenderer::with_database("en-US", database))
+            .add_plugins(Text3dPlugin::default())
+            .add_plugins(plugins);
+        app.finish();
+        app.cleanup();
+        app
+    }
+
+    fn spawn_text(app: &mut App, text: Text3d) -> Entity {
+        app.world_mut()
+            .spawn((
+                text,
+                Text3dStyling {
+                    font: "Roboto".into(),
+                    // Visibility is not computed without `VisibilityPlugin`.
+                    update_when_hidden: true,
+                    max_glyphs: usize::MAX,
+                    ..Default::default()
+                },
+                Mesh3d::default(),
+            ))
+            .id()
+    }
+
+    fn render(text: String) -> (App, Entity) {
+        let mut app = app();
+        let entity = spawn_text(&mut app, Text3d::new(text));
+        app.update();
+        (app, entity)
+    }
+
+    fn mesh(app: &App, entity: Entity) -> &Mesh {
+        let handle = &app.world().get::<Mesh3d>(entity).unwrap().0;
+        app.world().resource::<Assets<Mesh>>().get(handle).unwrap()
+    }
+
+    fn attribute(
+        app: &App,
+        entity: Entity,
+        attribute: bevy::render::mesh::MeshVertexAttribute,
+    ) -> Vec<f32> {
+        match mesh(app, entity).attribute(attribute) {
+            Some(VertexAttributeValues::Float32x3(values)) => {
+                values.iter().flatten().copied().collect()
+            }
+            Some(VertexAttributeValues::Float32x2(values)) => {
+                values.iter().flatten().copied().collect()
+            }
+            _ => panic!("missing attribute"),
+        }
+    }
+
+    #[test]
+    fn large_text_uses_u32_indices() {
+        let lorem = include_str!("../examples/lorem.txt");
+        let (app, entity) = render(lorem.to_owned());
+        assert!(mesh(&app, entity).count_vertices() > 0);
+        assert!(matches!(
+            mesh(&app, entity).indices(),
+            Some(Indices::U16(_))
+        ));
+
+        let (app, entity) = render(lorem.repeat(40));
+        let vertices = mesh(&app, entity).count_vertices();
+        assert!(vertices > u16::MAX as usize + 1);
+        let Some(Indices::U32(indices)) = mesh(&app, entity).indices() else {
+            panic!("expected U32 indices for {vertices} vertices");
+        };
+        assert_eq!(indices.len(), vertices / 4 * 6);
+        assert_eq!(indices.iter().max(), Some(&(vertices as u32 - 1)));
+    }
+
+    #[test]
+    fn fetch_update_keeps_static_mesh() {
+        let mut app = app();
+        let fetched = app.world_mut().spawn(FetchedTextSegment("1".into())).id();
+        let child = app.world_mut().spawn(Mesh3d::default()).id();
+        let segment = |s: &str| (Text3dSegment::String(s.into()), SegmentStyle::default());
+        let text = Text3d {
+            segments: vec![
+                segment("Score: "),
+                (Text3dSegment::Extract(fetched), SegmentStyle::default()),
+                segment(" points\nA static paragraph below the score."),
+            ],
+        };
+        let entity = spawn_text(&mut app, text);
+        app.world_mut()
+            .entity_mut(entity)
+            .insert((Text3dDynamicMesh::new(child), Text3dLayoutInfo::default()));
+        app.update();
+        let handle = app.world().get::<Mesh3d>(entity).unwrap().0.clone();
+        let positions = attribute(&app, entity, Mesh::ATTRIBUTE_POSITION);
+        let uvs = attribute(&app, child, Mesh::ATTRIBUTE_UV_0);
+        // Asset events are flushed on the next frame.
+        app.update();
+        let mut cursor = app
+            .world()
+            .resource::<Events<AssetEvent<Mesh>>>()
+            .get_cursor_current();
+
+        app.world_mut()
+            .get_mut::<FetchedTextSegment>(fetched)
+            .unwrap()
+            .0 = "2".into();
+        app.update();
+        app.update();
+        assert_eq!(app.world().get::<Mesh3d>(entity).unwrap().0, handle);
+        assert_eq!(attribute(&app, entity, Mesh::ATTRIBUTE_POSITION), positions);
+        let modified = cursor
+            .read(app.world().resource::<Events<AssetEvent<Mesh>>>())
+            .any(|event| event == &AssetEvent::Modified { id: handle.id() });
+        assert!(!modified);
+        assert_ne!(attribute(&app, child, Mesh::ATTRIBUTE_UV_0), uvs);
+        let info = app.world().get::<Text3dLayoutInfo>(entity).unwrap();
+        let static_vertices = positions.len() / 3;
+        for glyph in &info.glyphs {
+            assert_eq!(glyph.dynamic, glyph.segment == 1);
+            if !glyph.dynamic {
+                assert!(glyph.vertices.end <= static_vertices);
+            }
+        }
+
+        // A wider value moves the static glyphs after it.
+        app.world_mut()
+            .get_mut::<FetchedTextSegment>(fetched)
+            .unwrap()
+            .0 = "1000".into();
+        app.update();
+        assert_ne!(attribute(&app, entity, Mesh::ATTRIBUTE_POSITION), positions);
+    }
+
+    #[test]
+    fn glyph_layout_matches_layout_info() {
+        let mut app = app();
+        let entity = spawn_text(&mut app, Text3d::new("Hello\nWorld"));
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(Text3dGlyphLayout::default());
+        app.update();
+        let world = app.world();
+        let info = world.get::<Text3dLayoutInfo>(entity).unwrap();
+        let glyphs = &world.get::<Text3dGlyphLayout>(entity).unwrap().glyphs;
+        assert_eq!(glyphs.len(), 10);
+        assert_eq!(glyphs.len(), info.glyphs.len());
+        for (glyph, info) in glyphs.iter().zip(&info.glyphs) {
+            assert_eq!(glyph.cluster, info.cluster);
+            assert_eq!(glyph.rect, info.rect);
+            assert_eq!(glyph.atlas_rect, info.atlas_rect);
+        }
+        assert_eq!(glyphs[6].cluster, 7..8);
+        assert_eq!(glyphs[6].line, 1);
+        assert!(glyphs[6].atlas_rect.is_some());
+    }
+
+    /// Grow the atlas by drawing large glyphs.
+    #[test]
+    fn quality_scales_without_changing_settings() {
+        let scale_factors = |app: &App| {
+            let atlases = app.world().resource::<Assets<TextAtlas>>();
+            atlases
+                .iter()
+                .flat_map(|(_, atlas)| atlas.glyphs.keys())
+                .map(|entry| entry.scale_factor)
+                .collect::<BTreeSet<_>>()
+        };
+        let mut app = app();
+        app.insert_resource(TextQuality::Low);
+        spawn_text(&mut app, Text3d::new("Low"));
+        let magnified = spawn_text(&mut app, Text3d::new("Low"));
+        app.world_mut()
+            .entity_mut(magnified)
+            .insert(Text3dScaleFactorOverride(2.0));
+        app.update();
+        app.update();
+        assert_eq!(app.world().resource::<Text3dPlugin>().scale_factor, 1.0);
+        assert_eq!(
+            scale_factors(&app),
+            BTreeSet::from([FloatOrd(0.5), FloatOrd(1.0)])
+        );
+
+        app.insert_resource(TextQuality::High);
+        app.update();
+        app.update();
+        assert_eq!(app.world().resource::<Text3dPlugin>().scale_factor, 1.0);
+        assert_eq!(
+            scale_factors(&app),
+            BTreeSet::from([FloatOrd(1.0), FloatOrd(2.0)])
+        );
+    }
+
+    fn grow_atlas(app: &mut App) {
+        let atlas = |app: &App| {
+            let images = app.world().resource::<Assets<Image>>();
+            images
+                .iter()
+                .map(|(_, image)| image.width() * image.height())
+                .max()
+        };
+        let size = atlas(app);
+        let entity = spawn_text(app, Text3d::new(include_str!("../examples/lorem.txt")));
+        app.world_mut()
+            .get_mut::<Text3dStyling>(entity)
+            .unwrap()
+            .size = 200.0;
+        app.update();
+        app.update();
+        assert_ne!(atlas(app), size);
+    }
+
+    #[test]
+    fn marquee_follows_atlas_growth() {
+        let mut app = app_with(Text3dMarqueePlugin);
+        let plain = spawn_text(&mut app, Text3d::new("Marquee"));
+        let scrolled = spawn_text(&mut app, Text3d::new("Marquee"));
+        // Does not scroll or clip any glyph.
+        app.world_mut()
+            .entity_mut(scrolled)
+            .insert(Text3dMarquee::new(100.0, 0.0));
+        app.update();
+        let uvs = attribute(&app, plain, Mesh::ATTRIBUTE_UV_0);
+        assert_eq!(attribute(&app, scrolled, Mesh::ATTRIBUTE_UV_0), uvs);
+
+        grow_atlas(&mut app);
+        assert_ne!(attribute(&app, plain, Mesh::ATTRIBUTE_UV_0), uvs);
+        assert_eq!(
+            attribute(&app, scrolled, Mesh::ATTRIBUTE_UV_0),
+            attribute(&app, plain, Mesh::ATTRIBUTE_UV_0)
+        );
+    }
+
+    #[test]
+    fn clip_follows_atlas_growth() {
+        let mut app = app_with(Text3dClipPlugin);
+        let plain = spawn_text(&mut app, Text3d::new("Clip"));
+        let clipped = spawn_text(&mut app, Text3d::new("Clip"));
+        // Does not clip any glyph.
+        let circle = |radius| ClipShape::Circle {
+            center: Vec2::ZERO,
+            radius,
+        };
+        app.world_mut()
+            .entity_mut(clipped)
+            .insert(Text3dClip::new(circle(100.0)));
+        app.update();
+        // Clipped meshes are not indexed, compare distinct uvs.
+        let uvs = |app: &App, entity| {
+            let uvs = attribute(app, entity, Mesh::ATTRIBUTE_UV_0);
+            uvs.chunks(2)
+                .map(|uv| (uv[0].to_bits(), uv[1].to_bits()))
+                .collect::<BTreeSet<_>>()
+        };
+        let before = uvs(&app, plain);
+        assert_eq!(uvs(&app, clipped), before);
+
+        grow_atlas(&mut app);
+        // Clipped again from the mesh before clipping.
+        app.world_mut()
+            .get_mut::<Text3dClip>(clipped)
+            .unwrap()
+            .shape = circle(200.0);
+        app.update();
+        assert_ne!(uvs(&app, plain), before);
+        assert_eq!(uvs(&app, clipped), uvs(&app, plain));
+    }
+
+    #[test]
+    fn nowrap_keeps_byte_offsets() {
+        let mut app = app();
+        let nowrap = SegmentStyle {
+            nowrap: Some(true),
+            ..Default::default()
+        };
+        let text = Text3d {
+            segments: vec![
+                (Text3dSegment::String("a b c".into()), nowrap),
+                (
+                    Text3dSegment::String(" d e".into()),
+                    SegmentStyle::default(),
+                ),
+            ],
+        };
+        let entity = spawn_text(&mut app, text);
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(Text3dLayoutInfo::default());
+        app.update();
+        let info = app.world().get::<Text3dLayoutInfo>(entity).unwrap();
+        assert_eq!(info.text.trim_end(), "a b c d e");
+        let clusters: String = info
+            .glyphs
+            .iter()
+            .map(|glyph| &info.text[glyph.cluster.clone()])
+            .collect();
+        assert_eq!(clusters, "a b c d e");
+        // Hit the glyph of `e`, after the no-break spaces.
+        let e = info.glyphs.last().unwrap();
+        let hit = info
+            .hit(e.rect.center() + Vec2::X * e.rect.width() / 4.0)
+            .unwrap();
+        assert_eq!(hit.glyph, Some(info.glyphs.len() - 1));
+        assert_eq!(hit.byte, 9);
+        assert_eq!(info.glyph_for_byte(8), Some(e));
+    }
+
+    #[test]
+    fn editor_follows_buffer_lines() {
+        let mut app = app();
+        let mut carets = Vec::new();
+        // `\r\n` is laid out as two line breaks.
+        let texts = [
+            ("ab\ncd", 4),
+            ("ab\rcd", 4),
+            ("ab\n\ncd", 5),
+            ("ab\r\ncd", 5),
+        ];
+        for (text, cursor) in texts {
+            let entity = spawn_text(&mut app, Text3d::default());
+            let mut editor = Text3dEditor::new(text);
+            editor.set_cursor(cursor);
+            editor.move_right(true);
+            app.world_mut().entity_mut(entity).insert(editor);
+            carets.push(entity);
+        }
+        app.update();
+        let rects = |entity| {
+            let editor = app.world().get::<Text3dEditor>(entity).unwrap();
+            (editor.caret_rect(), editor.selection_rects().to_vec())
+        };
+        for pair in carets.chunks(2) {
+            let lf = rects(pair[0]);
+            assert!(lf.0.is_some());
+            assert_eq!(lf.1.len(), 1);
+            assert_eq!(rects(pair[1]), lf);
+        }
+
+        let mut editor = Text3dEditor::new("ab\r\ncd");
+        editor.set_cursor(0);
+        editor.move_end(false);
+        assert_eq!(editor.cursor(), 2);
+        editor.set_cursor(6);
+        editor.move_home(false);
+        assert_eq!(editor.cursor(), 4);
+    }
 }
@@ -0,0 +1,244 @@
+use std::string::FromUtf8Error;
+
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::{
+        io::Reader, Asset, AssetApp, AssetEvent, AssetId, AssetLoader, Assets, Handle, LoadContext,
+    },
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res},
+        world::Ref,
+    },
+    reflect::TypePath,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    fetch::text_fetch_system, parse::parse_style_list, FetchedTextSegment, ParseError, ParseSpan,
+    SegmentStyle, SpannedParseError, Text3d, Text3dSegment, Text3dSet,
+};
+
+/// A rich text file parsed with [`Text3d::parse`], loaded from `.rt3d` files.
+///
+/// The file may start with style definitions between `---` lines,
+/// each definition is a name followed by a list of styles, styles can refer to previous definitions.
+///
+/// ```text
+/// ---
+/// title: bold, #ffcc00
+/// warning: red, title
+/// ---
+/// {title:Chapter 1}
+///
+/// Hello {player_name}, {warning:watch out!}
+/// ```
+#[derive(Debug, Clone, Default, Asset, TypePath)]
+pub struct RichTextAsset {
+    pub segments: Vec<(RichTextSegment, SegmentStyle)>,
+    /// Style definitions of the file.
+    pub styles: FxHashMap<String, SegmentStyle>,
+}
+
+/// A segment of [`RichTextAsset`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RichTextSegment {
+    String(String),
+    /// A dynamic value like `{player_name}`, instantiated as an entity with [`RichTextValue`].
+    Dynamic(String),
+    /// An inline image like `{image:gold_coin}`.
+    Image(String),
+}
+
+impl RichTextAsset {
+    /// Parse a rich text file.
+    pub fn parse(text: &str) -> Result<Self, SpannedParseError> {
+        // Byte offset of a substring of `text`.
+        let offset = |s: &str| s.as_ptr() as usize - text.as_ptr() as usize;
+        let spanned = |error, offset| SpannedParseError::new(error, ParseSpan::new(text, offset));
+        let mut styles = FxHashMap::default();
+        let mut body = text;
+        if let Some(rest) = text
+            .strip_prefix("---")
+            .and_then(|x| x.strip_prefix('\n').or_else(|| x.strip_prefix("\r\n")))
+        {
+            let (header, rest) = match rest.strip_prefix("---") {
+                Some(rest) => ("", rest),
+                None => rest
+                    .split_once("\n---")
+                    .ok_or_else(|| spanned(ParseError::BadCommand("---".into()), 0))?,
+            };
+            body = rest.split_once('\n').map(|(_, x)| x).unwrap_or("");
+            for line in header.lines().map(str::trim).filter(|x| !x.is_empty()) {
+                let Some((name, value)) = line.split_once(':') else {
+                    return Err(spanned(ParseError::BadCommand(line.into()), offset(line)));
+                };
+                let style = parse_style_list(value, |s| {
+                    styles
+                        .get(s)
+                        .cloned()
+                        .ok_or_else(|| ParseError::MissingStyle(s.into()))
+                })
+                .map_err(|error| spanned(error, offset(line)))?;
+                styles.insert(name.trim().to_owned(), style);
+            }
+        }
+        let mut keys = Vec::new();
+        let text = Text3d::parse(
+            body,
+            |key| {
+                keys.push(key.to_owned());
+                Ok(Text3dSegment::Extract(Entity::PLACEHOLDER))
+            },
+            |s| {
+                styles
+                    .get(s)
+                    .cloned()
+                    .ok_or_else(|| ParseError::MissingStyle(s.into()))
+            },
+        )
+        .map_err(|error| spanned(error.error().clone(), offset(body) + error.span().offset))?;
+        let mut keys = keys.into_iter();
+        let segments = text
+            .segments
+            .into_iter()
+            .map(|(segment, style)| match segment {
+                Text3dSegment::String(s) => (RichTextSegment::String(s), style),
+                Text3dSegment::Interned(s) => (RichTextSegment::String(s.to_string()), style),
+                Text3dSegment::Image(s) => (RichTextSegment::Image(s), style),
+                Text3dSegment::Computed(c) => (RichTextSegment::String(c.value), style),
+                Text3dSegment::Extract(_) => (
+                    RichTextSegment::Dynamic(keys.next().unwrap_or_default()),
+                    style,
+                ),
+            })
+            .collect();
+        Ok(RichTextAsset { segments, styles })
+    }
+}
+
+/// Error when loading a [`RichTextAsset`].
+#[derive(Debug, thiserror::Error)]
+pub enum RichTextLoadError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Utf8(#[from] FromUtf8Error),
+    #[error("{0}")]
+    Parse(#[from] SpannedParseError),
+}
+
+/// [`AssetLoader`] of [`RichTextAsset`] for `.rt3d` files.
+#[derive(Debug, Default, TypePath)]
+pub struct RichTextAssetLoader;
+
+impl AssetLoader for RichTextAssetLoader {
+    type Asset = RichTextAsset;
+    type Settings = ();
+    type Error = RichTextLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &(),
+        _: &mut LoadContext<'_>,
+    ) -> Result<RichTextAsset, RichTextLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(RichTextAsset::parse(&String::from_utf8(bytes)?)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rt3d"]
+    }
+}
+
+/// Instantiates [`Text3d`] from a [`RichTextAsset`], updated when the asset is reloaded.
+///
+/// Dynamic values are spawned as entities with [`RichTextValue`] and [`FetchedTextSegment`].
+///
+/// Requires [`Text3dRichTextPlugin`].
+#[derive(Debug, Clone, Component, Default)]
+#[require(Text3d)]
+pub struct RichText3d(pub Handle<RichTextAsset>);
+
+/// Key of a dynamic value in a [`RichTextAsset`], write the value to [`FetchedTextSegment`] on the same entity.
+#[derive(Debug, Clone, Component, Default, PartialEq, Eq)]
+#[require(FetchedTextSegment)]
+pub struct RichTextValue(pub String);
+
+pub(crate) fn spawn_value(commands: &mut Commands, key: &str) -> Entity {
+    #[cfg(feature = "fluent")]
+    if let Some(key) = key.strip_prefix("loc:") {
+        return commands
+            .spawn((
+                crate::LocalizedText::new(key.trim()),
+                RichTextValue(key.to_owned()),
+            ))
+            .id();
+    }
+    commands.spawn(RichTextValue(key.to_owned())).id()
+}
+
+/// Loads `.rt3d` files as [`RichTextAsset`]s and instantiates [`RichText3d`]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dRichTextPlugin;
+
+impl Plugin for Text3dRichTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<RichTextAsset>();
+        app.init_asset_loader::<RichTextAssetLoader>();
+        app.add_systems(
+            PostUpdate,
+            rich_text_system.in_set(Text3dSet).before(text_fetch_system),
+        );
+    }
+}
+
+/// Instantiates [`RichText3d`] as [`Text3d`].
+pub fn rich_text_system(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<RichTextAsset>>,
+    assets: Res<Assets<RichTextAsset>>,
+    mut query: Query<(Ref<RichText3d>, &mut Text3d)>,
+    mut changed: Local<FxHashSet<AssetId<RichTextAsset>>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event {
+            changed.insert(*id);
+        }
+    }
+    for (rich_text, mut text) in query.iter_mut() {
+        if !rich_text.is_changed() && !changed.contains(&rich_text.0.id()) {
+            continue;
+        }
+        let Some(asset) = assets.get(rich_text.0.id()) else {
+            continue;
+        };
+        // Replacing segments does not trigger `Text3d`'s `on_remove` hook.
+        for (segment, _) in &text.segments {
+            if let Text3dSegment::Extract(entity) = segment {
+                commands.entity(*entity).try_despawn();
+            }
+        }
+        text.segments = asset
+            .segments
+            .iter()
+            .map(|(segment, style)| {
+                let segment = match segment {
+                    RichTextSegment::String(s) => Text3dSegment::String(s.clone()),
+                    RichTextSegment::Image(s) => Text3dSegment::Image(s.clone()),
+                    RichTextSegment::Dynamic(key) => {
+                        Text3dSegment::Extract(spawn_value(&mut commands, key))
+                    }
+                };
+                (segment, style.clone())
+            })
+            .collect();
+    }
+    changed.clear();
+}
@@ -0,0 +1,157 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{ParamSet, Query},
+    },
+    math::Vec3,
+    render::camera::{Camera, CameraProjection, Projection},
+    transform::{components::Transform, helper::TransformHelper},
+};
+
+#[cfg(feature = "reflect")]
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::{Reflect, ReflectDefault},
+};
+
+use crate::{Text3dSet, Text3dStyling};
+
+/// Scales a [`Text3d`](crate::Text3d) entity every frame so that its `em` occupies
+/// a constant height on screen regardless of its distance to the camera.
+///
+/// The entity's [`Transform::scale`] is overwritten.
+///
+/// Requires [`Text3dConstantScreenSizePlugin`].
+#[derive(Debug, Component, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dConstantScreenSize {
+    /// Camera to measure against, if `None`, uses the first active [`Camera`].
+    pub camera: Option<Entity>,
+    /// Height of [`Text3dStyling::size`] on screen in logical pixels.
+    pub pixels: f32,
+}
+
+impl Default for Text3dConstantScreenSize {
+    fn default() -> Self {
+        Self {
+            camera: None,
+            pixels: 16.0,
+        }
+    }
+}
+
+impl Text3dConstantScreenSize {
+    /// Occupy `pixels` logical pixels per `em` on the first active camera.
+    pub const fn new(pixels: f32) -> Self {
+        Self {
+            camera: None,
+            pixels,
+        }
+    }
+
+    /// Measure against a specific camera.
+    pub const fn with_camera(mut self, camera: Entity) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+}
+
+/// Rescales [`Text3dConstantScreenSize`]s every frame.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dConstantScreenSizePlugin;
+
+impl Plugin for Text3dConstantScreenSizePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, text_screen_size_system.in_set(Text3dSet));
+        #[cfg(feature = "reflect")]
+        app.register_type::<Text3dConstantScreenSize>();
+    }
+}
+
+/// Rescales [`Text3dConstantScreenSize`] entities.
+///
+/// Runs before transform propagation, so global transforms of the camera and the text
+/// are computed from this frame's [`Transform`]s instead of last frame's
+/// [`GlobalTransform`](bevy::transform::components::GlobalTransform).
+pub fn text_screen_size_system(
+    cameras: Query<(Entity, &Camera, &Projection)>,
+    query: Query<(
+        Entity,
+        &Text3dConstantScreenSize,
+        &Text3dStyling,
+        Option<&ChildOf>,
+    )>,
+    mut transforms: ParamSet<(
+        TransformHelper,
+        Query<&mut Transform, With<Text3dConstantScreenSize>>,
+    )>,
+) {
+    let default_camera = cameras
+        .iter()
+        .find(|(_, camera, ..)| camera.is_active)
+        .map(|(entity, ..)| entity);
+    let mut scales = Vec::new();
+    let helper = transforms.p0();
+    for (entity, screen_size, styling, parent) in query.iter() {
+        let Some((camera_entity, camera, projection)) = screen_size
+            .camera
+            .or(default_camera)
+            .and_then(|entity| cameras.get(entity).ok())
+        else {
+            continue;
+        };
+        let Some(viewport) = camera.logical_viewport_size() else {
+            continue;
+        };
+        let clip_from_view = projection.get_clip_from_view();
+        if clip_from_view.y_axis.y == 0.0 || viewport.y == 0.0 {
+            continue;
+        }
+        // World units covered by the viewport's height at the entity's depth.
+        let mut world_height = 2.0 / clip_from_view.y_axis.y;
+        // Perspective projections divide by view space depth.
+        if clip_from_view.z_axis.w != 0.0 {
+            let (Ok(camera_transform), Ok(global)) = (
+                helper.compute_global_transform(camera_entity),
+                helper.compute_global_transform(entity),
+            ) else {
+                continue;
+            };
+            let depth = (global.translation() - camera_transform.translation())
+                .dot(camera_transform.forward().as_vec3());
+            if depth <= 0.0 {
+                continue;
+            }
+            world_height *= depth;
+        }
+        let em = match styling.world_scale {
+            Some(world_scale) => world_scale.y,
+            None => styling.size,
+        };
+        if em == 0.0 {
+            continue;
+        }
+        let scale = screen_size.pixels / viewport.y * world_height / em;
+        // Convert to the parent's space.
+        let parent_scale = parent
+            .and_then(|parent| helper.compute_global_transform(parent.parent()).ok())
+            .map(|x| x.scale())
+            .unwrap_or(Vec3::ONE);
+        scales.push((entity, Vec3::splat(scale) / parent_scale));
+    }
+    let mut query = transforms.p1();
+    for (entity, scale) in scales {
+        let Ok(mut transform) = query.get_mut(entity) else {
+            continue;
+        };
+        if !transform.scale.abs_diff_eq(scale, f32::EPSILON) {
+            transform.scale = scale;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::ecs::component::Component;
+use cosmic_text::{
+    Attrs, AttrsList, BidiParagraphs, Buffer, BufferLine, FontSystem, LineEnding, Metrics, Shaping,
+};
+
+/// Caches the shaped text of a [`Text3d`](crate::Text3d) between redraws.
+///
+/// Add this component to reuse shaping results of lines that did not change,
+/// useful for large and frequently updated text like logs
+/// where only a few [`FetchedTextSegment`](crate::FetchedTextSegment)s change at a time.
+///
+/// The mesh is still rebuilt entirely. The `incremental` bench updates one segment
+/// of a 2000 line log per frame, which runs about 25 times faster with this component.
+#[derive(Debug, Component, Default)]
+pub struct Text3dShapeCache {
+    pub(crate) buffer: Option<Buffer>,
+}
+
+impl Text3dShapeCache {
+    /// Obtain the cached [`Buffer`] with updated metrics.
+    pub(crate) fn buffer(&mut self, font_system: &mut FontSystem, metrics: Metrics) -> &mut Buffer {
+        let buffer = self
+            .buffer
+            .get_or_insert_with(|| Buffer::new(font_system, metrics));
+        buffer.set_metrics(font_system, metrics);
+        buffer
+    }
+}
+
+/// Like [`Buffer::set_rich_text`] but only resets lines whose text or attributes changed.
+pub(crate) fn set_rich_text_incremental<'r, 's>(
+    buffer: &mut Buffer,
+    spans: impl IntoIterator<Item = (&'s str, Attrs<'r>)>,
+    default_attrs: &Attrs,
+) {
+    let mut string = String::new();
+    let mut spans_data = Vec::new();
+    for (s, attrs) in spans {
+        let start = string.len();
+        string.push_str(s);
+        spans_data.push((attrs, start..string.len()));
+    }
+
+    let mut line_count = 0;
+    let mut changed = false;
+    let string_start = string.as_ptr() as usize;
+    for line in BidiParagraphs::new(&string) {
+        let start = line.as_ptr() as usize - string_start;
+        let end = start + line.len();
+        let mut attrs_list = AttrsList::new(default_attrs);
+        for (attrs, range) in &spans_data {
+            let span_start = range.start.max(start);
+            let span_end = range.end.min(end);
+            if span_start < span_end && attrs != default_attrs {
+                attrs_list.add_span(span_start - start..span_end - start, attrs);
+            }
+        }
+        if let Some(buffer_line) = buffer.lines.get_mut(line_count) {
+            changed |= buffer_line.set_text(line, LineEnding::default(), attrs_list);
+        } else {
+            buffer.lines.push(BufferLine::new(
+                line,
+                LineEnding::default(),
+                attrs_list,
+                Shaping::Advanced,
+            ));
+            changed = true;
+        }
+        line_count += 1;
+    }
+    if line_count == 0 {
+        if let Some(buffer_line) = buffer.lines.first_mut() {
+            changed |=
+                buffer_line.set_text("", LineEnding::default(), AttrsList::new(default_attrs));
+        } else {
+            buffer.lines.push(BufferLine::new(
+                "",
+                LineEnding::default(),
+                AttrsList::new(default_attrs),
+                Shaping::Advanced,
+            ));
+            changed = true;
+        }
+        line_count = 1;
+    }
+    if buffer.lines.len() > line_count {
+        buffer.lines.truncate(line_count);
+        changed = true;
+    }
+    if changed {
+        buffer.set_redraw(true);
+    }
+}
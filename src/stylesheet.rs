@@ -0,0 +1,272 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    asset::{Asset, AssetApp, AssetEvent, AssetId, Assets, Handle},
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res},
+        world::Ref,
+    },
+    log::warn,
+    reflect::TypePath,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    fetch::text_fetch_system, rich_text::spawn_value, ParseError, SegmentStyle, SpannedParseError,
+    Text3d, Text3dSegment, Text3dSet,
+};
+
+/// Named styles used by [`Text3d::parse_with_stylesheet`] and [`StyledText3d`].
+///
+/// Can be inserted as a [`Resource`] for game-wide styles or loaded as an asset.
+/// With the `serde` feature, `.stylesheet.ron` and `.stylesheet.toml` files
+/// can be loaded as a map of names to [`SegmentStyle`]s:
+///
+/// ```ron
+/// {
+///     "title": (font: Some("Roboto"), size: Some(32.0)),
+///     "warning": (stroke: Some(4), magic_number: Some(1.0)),
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Asset, Resource, TypePath)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct TextStyleSheet {
+    pub styles: FxHashMap<String, SegmentStyle>,
+}
+
+impl TextStyleSheet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a named style.
+    pub fn with_style(mut self, name: impl Into<String>, style: SegmentStyle) -> Self {
+        self.styles.insert(name.into(), style);
+        self
+    }
+
+    /// Add or replace a named style.
+    pub fn insert(&mut self, name: impl Into<String>, style: SegmentStyle) {
+        self.styles.insert(name.into(), style);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SegmentStyle> {
+        self.styles.get(name)
+    }
+
+    /// Obtain a style by name as the `stylesheet` function of [`Text3d::parse`].
+    pub fn style(&self, name: &str) -> Result<SegmentStyle, ParseError> {
+        self.styles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ParseError::MissingStyle(name.into()))
+    }
+}
+
+#[cfg(feature = "serde")]
+mod loader {
+    use std::string::FromUtf8Error;
+
+    use bevy::{
+        asset::{io::Reader, AssetLoader, LoadContext},
+        reflect::TypePath,
+    };
+
+    use super::TextStyleSheet;
+
+    /// Error when loading a [`TextStyleSheet`].
+    #[derive(Debug, thiserror::Error)]
+    pub enum TextStyleSheetLoadError {
+        #[error("{0}")]
+        Io(#[from] std::io::Error),
+        #[error("{0}")]
+        Utf8(#[from] FromUtf8Error),
+        #[error("{0}")]
+        Ron(#[from] ron::error::SpannedError),
+        #[error("{0}")]
+        Toml(#[from] toml::de::Error),
+    }
+
+    /// [`AssetLoader`] of [`TextStyleSheet`] for `.stylesheet.ron` and `.stylesheet.toml` files.
+    #[derive(Debug, Default, TypePath)]
+    pub struct TextStyleSheetLoader;
+
+    impl AssetLoader for TextStyleSheetLoader {
+        type Asset = TextStyleSheet;
+        type Settings = ();
+        type Error = TextStyleSheetLoadError;
+
+        async fn load(
+            &self,
+            reader: &mut dyn Reader,
+            _: &(),
+            load_context: &mut LoadContext<'_>,
+        ) -> Result<TextStyleSheet, TextStyleSheetLoadError> {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let text = String::from_utf8(bytes)?;
+            if load_context
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == "toml")
+            {
+                Ok(toml::from_str(&text)?)
+            } else {
+                Ok(ron::from_str(&text)?)
+            }
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["stylesheet.ron", "stylesheet.toml"]
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+pub use loader::{TextStyleSheetLoadError, TextStyleSheetLoader};
+
+impl Text3d {
+    /// Call [`Text3d::parse`] with styles from a [`TextStyleSheet`].
+    ///
+    /// Dynamic values are not supported, use [`StyledText3d`] instead.
+    pub fn parse_with_stylesheet(
+        text: &str,
+        stylesheet: &TextStyleSheet,
+    ) -> Result<Self, SpannedParseError> {
+        Text3d::parse(
+            text,
+            |command| Err(ParseError::BadCommand(command.into())),
+            |style| stylesheet.style(style),
+        )
+    }
+}
+
+/// Parses a string into [`Text3d`] with a [`TextStyleSheet`], parsed again when the sheet changes.
+///
+/// Dynamic values are spawned as entities with [`RichTextValue`](crate::RichTextValue),
+/// same as [`RichText3d`](crate::RichText3d).
+///
+/// Requires [`Text3dStyleSheetPlugin`].
+#[derive(Debug, Clone, Component, Default)]
+#[require(Text3d)]
+pub struct StyledText3d {
+    /// Text in the format of [`Text3d::parse`].
+    pub text: String,
+    /// If `None`, uses the [`TextStyleSheet`] resource.
+    pub stylesheet: Option<Handle<TextStyleSheet>>,
+}
+
+impl StyledText3d {
+    /// Parse with the [`TextStyleSheet`] resource.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            stylesheet: None,
+        }
+    }
+
+    /// Parse with a [`TextStyleSheet`] asset.
+    pub fn with_stylesheet(mut self, stylesheet: Handle<TextStyleSheet>) -> Self {
+        self.stylesheet = Some(stylesheet);
+        self
+    }
+}
+
+/// Adds [`TextStyleSheet`] assets and parses [`StyledText3d`]s.
+///
+/// Not required to use [`TextStyleSheet`] as a [`Resource`] with [`Text3d::parse_with_stylesheet`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dStyleSheetPlugin;
+
+impl Plugin for Text3dStyleSheetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TextStyleSheet>();
+        #[cfg(feature = "serde")]
+        app.init_asset_loader::<TextStyleSheetLoader>();
+        app.add_systems(
+            PostUpdate,
+            styled_text_system
+                .in_set(Text3dSet)
+                .before(text_fetch_system),
+        );
+    }
+}
+
+/// Parses [`StyledText3d`] into [`Text3d`].
+pub fn styled_text_system(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<TextStyleSheet>>,
+    assets: Res<Assets<TextStyleSheet>>,
+    resource: Option<Res<TextStyleSheet>>,
+    mut query: Query<(Ref<StyledText3d>, &mut Text3d)>,
+    mut changed: Local<FxHashSet<AssetId<TextStyleSheet>>>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event {
+            changed.insert(*id);
+        }
+    }
+    let resource_changed = resource.as_ref().is_some_and(|x| x.is_changed());
+    for (styled, mut text) in query.iter_mut() {
+        let stylesheet = match &styled.stylesheet {
+            Some(handle) => {
+                if !styled.is_changed() && !changed.contains(&handle.id()) {
+                    continue;
+                }
+                assets.get(handle.id())
+            }
+            None => {
+                if !styled.is_changed() && !resource_changed {
+                    continue;
+                }
+                resource.as_deref()
+            }
+        };
+        let Some(stylesheet) = stylesheet else {
+            continue;
+        };
+        let mut keys = Vec::new();
+        let parsed = match Text3d::parse(
+            &styled.text,
+            |key| {
+                keys.push(key.to_owned());
+                Ok(Text3dSegment::Extract(Entity::PLACEHOLDER))
+            },
+            |style| stylesheet.style(style),
+        ) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                warn!("Error parsing styled text {:?}: {err}.", styled.text);
+                continue;
+            }
+        };
+        // Replacing segments does not trigger `Text3d`'s `on_remove` hook.
+        for (segment, _) in &text.segments {
+            if let Text3dSegment::Extract(entity) = segment {
+                commands.entity(*entity).try_despawn();
+            }
+        }
+        let mut keys = keys.into_iter();
+        text.segments = parsed
+            .segments
+            .into_iter()
+            .map(|(segment, style)| match segment {
+                Text3dSegment::Extract(_) => (
+                    Text3dSegment::Extract(spawn_value(
+                        &mut commands,
+                        &keys.next().unwrap_or_default(),
+                    )),
+                    style,
+                ),
+                segment => (segment, style),
+            })
+            .collect();
+    }
+    changed.clear();
+}
@@ -1,20 +1,36 @@
 use bevy::{
+    asset::AssetId,
     color::Srgba,
     ecs::component::Component,
+    image::Image,
     math::{FloatOrd, Vec2},
 };
-use cosmic_text::{fontdb::ID, Attrs};
-use std::{num::NonZeroU32, sync::Arc};
+use cosmic_text::{fontdb::ID, Attrs, FontFeatures, Metrics};
+use rustc_hash::FxHasher;
+use std::{
+    borrow::Cow,
+    hash::{Hash, Hasher},
+    num::NonZeroU32,
+    sync::Arc,
+};
 
-use crate::{prepare::family, GlyphMeta, StrokeJoin, Style, TextAlign, TextAnchor, Weight};
+use crate::{
+    prepare::family, AxisTag, FeatureTag, GlowStyle, GlyphMeta, StrokeCap, StrokeJoin, Style,
+    TextAlign, TextAnchor, TextWrap, Weight,
+};
 
 #[cfg(feature = "reflect")]
 use bevy::prelude::{Reflect, ReflectComponent, ReflectDefault};
 
 /// Default text style of a rich text component.
-#[derive(Debug, Component, Clone)]
+///
+/// If the entity has a [`Text3dLayoutInfo`](crate::Text3dLayoutInfo), changing only colors
+/// or [`Text3dStyling::align`] updates the existing mesh in place without shaping the text again.
+#[derive(Debug, Component, Clone, PartialEq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component, Default))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Text3dStyling {
     /// Size of the font, corresponding to world space units.
     ///
@@ -35,6 +51,8 @@ pub struct Text3dStyling {
     pub anchor: TextAnchor,
     /// Height of a line multiplied by font size, by default `1.0`.
     pub line_height: f32,
+    /// Size of ruby annotations multiplied by the size of their base text, by default `0.5`.
+    pub ruby_size: f32,
     /// Color of fill.
     pub color: Srgba,
     /// Color of stroke.
@@ -50,8 +68,25 @@ pub struct Text3dStyling {
     pub stroke: Option<NonZeroU32>,
     /// If true, render stroke in front.
     pub stroke_in_front: bool,
+    /// If true and both fill and stroke are drawn, draws a single quad per glyph
+    /// with fill coverage in the alpha channel and stroke coverage in the red channel.
+    ///
+    /// Halves the number of quads and removes z-fighting between fill and stroke,
+    /// but requires a material that understands this format, like [`TextTwoToneExtension`](crate::TextTwoToneExtension).
+    /// The stroke color is set on the material instead of per segment.
+    pub two_tone: bool,
     /// The shape of the stroke line joins, usually [`StrokeJoin::Round`].
     pub stroke_join: StrokeJoin,
+    /// Caps at the start and end of each dash in [`Text3dStyling::stroke_dash`], by default [`StrokeCap::Round`].
+    pub stroke_caps: (StrokeCap, StrokeCap),
+    /// Limit of the ratio between the length of a miter join and the stroke width, by default `4`.
+    ///
+    /// Joins exceeding the limit are beveled, only used with [`StrokeJoin::Miter`].
+    pub stroke_miter_limit: f32,
+    /// If not empty, draws dashed strokes with alternating on and off lengths in the same unit as [`Text3dStyling::stroke`].
+    ///
+    /// Dashes are cached per unique pattern so be sure not to use too many of them.
+    pub stroke_dash: Vec<f32>,
     /// Sets the distance between different layers, i.e. stroke and fill.
     ///
     /// `0.0` likely works for transparent render modes and opaque2d, but
@@ -59,6 +94,14 @@ pub struct Text3dStyling {
     pub layer_offset: f32,
     /// Determines what to extract as uv1.
     pub uv1: (GlyphMeta, GlyphMeta),
+    /// If set, emits [`ATTRIBUTE_GLYPH_META`](crate::ATTRIBUTE_GLYPH_META) with these four values
+    /// in addition to `uv1`, by default `None`.
+    ///
+    /// Requires a material that declares the attribute in its vertex layout.
+    pub glyph_meta: Option<[GlyphMeta; 4]>,
+    /// If true, emits [`Mesh::ATTRIBUTE_TANGENT`](bevy::render::mesh::Mesh::ATTRIBUTE_TANGENT)
+    /// for materials with normal maps, by default `false`.
+    pub tangents: bool,
     /// Tab in terms of spaces, default 4.
     pub tab_width: u16,
     /// If set, overwrite the size of `em` in the generated mesh.
@@ -68,6 +111,81 @@ pub struct Text3dStyling {
 
     /// If `Some`, render a text shadow.
     pub text_shadow: Option<(Srgba, Vec2)>,
+    /// Additional text shadows as color, offset and blur radius, like css's `text-shadow`,
+    /// drawn behind [`Text3dStyling::text_shadow`] with the first shadow on top.
+    ///
+    /// Blurred glyphs are cached per unique radius so be sure not to use too many of them.
+    pub text_shadows: Vec<(Srgba, Vec2, f32)>,
+    /// If `Some`, render a glow behind each glyph and in front of text shadows,
+    /// for neon or magic effects without post-processing.
+    ///
+    /// Cached per unique radius and intensity so be sure not to use too many of them.
+    pub glow: Option<GlowStyle>,
+    /// If set, lines will not be wrapped, glyphs that exceed [`Text3dBounds::width`](crate::Text3dBounds)
+    /// will be clipped and the last visible glyphs will fade out over this length in `em`.
+    ///
+    /// This is an alternative to ellipsis for truncating single line text.
+    pub overflow_fade: Option<f32>,
+    /// If true, for text that wraps into 2 or 3 lines, narrow the wrap width
+    /// so that lines have similar lengths, like css's `text-wrap: balance`.
+    ///
+    /// This is intended for headings and requires reshaping the text a few times.
+    pub balance_lines: bool,
+    /// If set, avoid paragraphs ending with a single word on the last line
+    /// by narrowing the wrap width by at most this fraction of the widest line,
+    /// pulling a word down from the previous line.
+    ///
+    /// A value like `0.1` is usually enough.
+    pub widow_tolerance: Option<f32>,
+    /// If set, punctuation like quotes, hyphens and periods at the start or end of a line
+    /// hang outside of the text block by this fraction of their advance,
+    /// making margins look optically straight.
+    ///
+    /// `1.0` hangs the entire punctuation mark.
+    pub hanging_punctuation: Option<f32>,
+    /// Transparent margin around each glyph in atlas pixels, included in the glyph's quad.
+    ///
+    /// Reserves room for vertex shader animations like wiggle, so glyph edges are not clipped at quad borders.
+    pub glyph_padding: u16,
+    /// OpenType features applied during shaping, i.e. `(FeatureTag::TABULAR_NUMBERS, 1)`
+    /// for numbers that do not jitter, or `(FeatureTag::STANDARD_LIGATURES, 0)` to disable ligatures.
+    pub font_features: Vec<(FeatureTag, u32)>,
+    /// Axis values of variable fonts, i.e. `(AxisTag::WEIGHT, 650.0)`.
+    ///
+    /// Applied when rasterizing glyphs, glyphs are cached separately for each configuration.
+    /// Shaping uses the advances of the font's default instance,
+    /// since variations are not supported by the shaper.
+    pub font_variations: Vec<(AxisTag, f32)>,
+    /// Maximum number of glyphs drawn, glyphs past this limit are not drawn
+    /// and a [`Text3dTruncated`](crate::Text3dTruncated) event is sent.
+    ///
+    /// Guards against runaway dynamic strings producing huge meshes, by default `16384`.
+    pub max_glyphs: usize,
+    /// Names of [`Text3dDrawer`](crate::Text3dDrawer)s in [`Text3dDrawers`](crate::Text3dDrawers)
+    /// that draw a custom quad for every glyph.
+    pub drawers: Vec<String>,
+    /// If false, layout of text that is hidden or was culled last frame
+    /// is deferred until it is visible again, see [`Text3dHidden`](crate::Text3dHidden).
+    ///
+    /// Set to true for text that must stay up to date, i.e. when reading [`Text3dDimensionOut`](crate::Text3dDimensionOut).
+    pub update_when_hidden: bool,
+    /// Where lines can be broken, ignored if `overflow_fade` is set.
+    pub wrap: TextWrap,
+}
+
+impl Text3dStyling {
+    pub(crate) fn stroke_outline(&self) -> StrokeOutline {
+        StrokeOutline {
+            join: self.stroke_join,
+            caps: self.stroke_caps,
+            miter_limit: self.stroke_miter_limit,
+            dash: self.stroke_dash.clone(),
+        }
+    }
+
+    pub(crate) fn outline_key(&self) -> u64 {
+        StrokeOutline::key(self.stroke_caps, self.stroke_miter_limit, &self.stroke_dash)
+    }
 }
 
 impl Default for Text3dStyling {
@@ -84,22 +202,48 @@ impl Default for Text3dStyling {
             fill: true,
             stroke: Default::default(),
             line_height: 1.0,
+            ruby_size: 0.5,
             layer_offset: 0.01,
             stroke_in_front: false,
+            two_tone: false,
             stroke_join: StrokeJoin::Round,
+            stroke_caps: (StrokeCap::Round, StrokeCap::Round),
+            stroke_miter_limit: 4.0,
+            stroke_dash: Vec::new(),
             uv1: (GlyphMeta::Index, GlyphMeta::PerGlyphAdvance),
+            glyph_meta: None,
+            tangents: false,
             tab_width: 4,
             world_scale: None,
             text_shadow: None,
+            text_shadows: Vec::new(),
+            glow: None,
+            overflow_fade: None,
+            balance_lines: false,
+            widow_tolerance: None,
+            hanging_punctuation: None,
+            glyph_padding: 0,
+            font_features: Vec::new(),
+            font_variations: Vec::new(),
+            max_glyphs: 16384,
+            drawers: Vec::new(),
+            update_when_hidden: false,
+            wrap: TextWrap::WordOrGlyph,
         }
     }
 }
 
 /// Text style of a segment.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct SegmentStyle {
     pub font: Option<Arc<str>>,
+    /// Overrides [`Text3dStyling::size`], glyphs of different sizes share the same baseline.
+    ///
+    /// Ths is cached per unique value so be sure not to use too many of them.
+    pub size: Option<f32>,
     pub fill_color: Option<Srgba>,
     pub stroke_color: Option<Srgba>,
     pub fill: Option<bool>,
@@ -110,21 +254,63 @@ pub struct SegmentStyle {
     pub strikethrough: Option<bool>,
     /// Can be referenced by [`GlyphMeta::MagicNumber`].
     pub magic_number: Option<f32>,
+    /// Overrides [`Text3dStyling::glyph_padding`], for shader effects like glow that sample beyond the glyph.
+    ///
+    /// Glyphs are cached per padding value, so padded and unpadded variants coexist in the atlas.
+    pub padding: Option<u16>,
+    /// Draws this segment above segments with a lower value and below segments with a higher value,
+    /// including their strokes and shadows, by default `0`.
+    pub layer: Option<i8>,
+    /// Names of [`Text3dDrawer`](crate::Text3dDrawer)s drawn in addition to [`Text3dStyling::drawers`].
+    pub drawers: Vec<String>,
+    /// If true, prevents line breaks at spaces in this segment, i.e. keeps `{value} HP` on the same line.
+    ///
+    /// Spaces are replaced by no-break spaces before layout.
+    pub nowrap: Option<bool>,
+    /// OpenType features applied after [`Text3dStyling::font_features`].
+    pub font_features: Vec<(FeatureTag, u32)>,
+    /// Overrides axes of [`Text3dStyling::font_variations`].
+    pub font_variations: Vec<(AxisTag, f32)>,
+    /// An identifier for gameplay code, reported by [`GlyphLayoutInfo::tag`](crate::GlyphLayoutInfo::tag)
+    /// and [`Text3dHit::tag`](crate::Text3dHit::tag) regardless of the segment's content.
+    pub tag: Option<Arc<str>>,
+    /// Ruby annotation, i.e. furigana, drawn centered above this segment
+    /// at [`Text3dStyling::ruby_size`].
+    ///
+    /// Lines containing the segment are made taller to fit the annotation.
+    pub ruby: Option<Arc<str>>,
 }
 
 impl SegmentStyle {
     pub fn as_attr<'t>(&'t self, base: &'t Text3dStyling) -> Attrs<'t> {
         let family_name = self.font.as_ref().map(Arc::as_ref).unwrap_or(&base.font);
         let family = family(family_name);
-        Attrs::new()
+        let attrs = Attrs::new()
             .weight(self.weight.unwrap_or(base.weight).into())
             .style(self.style.unwrap_or(base.style).into())
             .family(family)
+            .font_features(font_features(
+                base.font_features.iter().chain(&self.font_features),
+            ));
+        // Glyphs are centered vertically in a line,
+        // so the line grows by twice the annotation to make room above the glyphs.
+        match (self.size, &self.ruby) {
+            (size, Some(_)) => {
+                let size = size.unwrap_or(base.size);
+                attrs.metrics(Metrics::new(
+                    size,
+                    size * (base.line_height + 2.0 * base.ruby_size),
+                ))
+            }
+            (Some(size), None) => attrs.metrics(Metrics::new(size, size * base.line_height)),
+            (None, None) => attrs,
+        }
     }
 
     pub fn join(&self, other: Self) -> Self {
         SegmentStyle {
             font: other.font.or_else(|| self.font.clone()),
+            size: other.size.or(self.size),
             fill_color: other.fill_color.or(self.fill_color),
             stroke_color: other.stroke_color.or(self.stroke_color),
             fill: other.fill.or(self.fill),
@@ -134,8 +320,88 @@ impl SegmentStyle {
             strikethrough: other.strikethrough.or(self.strikethrough),
             style: other.style.or(self.style),
             magic_number: other.magic_number.or(self.magic_number),
+            padding: other.padding.or(self.padding),
+            layer: other.layer.or(self.layer),
+            drawers: self.drawers.iter().chain(&other.drawers).cloned().collect(),
+            nowrap: other.nowrap.or(self.nowrap),
+            font_features: self
+                .font_features
+                .iter()
+                .chain(&other.font_features)
+                .copied()
+                .collect(),
+            font_variations: merge_variations(&self.font_variations, &other.font_variations)
+                .into_owned(),
+            tag: other.tag.or_else(|| self.tag.clone()),
+            ruby: other.ruby.or_else(|| self.ruby.clone()),
         }
     }
+
+    pub fn with_font(mut self, font: impl Into<Arc<str>>) -> Self {
+        self.font = Some(font.into());
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_fill_color(mut self, color: impl Into<Srgba>) -> Self {
+        self.fill_color = Some(color.into());
+        self
+    }
+
+    pub fn with_stroke_color(mut self, color: impl Into<Srgba>) -> Self {
+        self.stroke_color = Some(color.into());
+        self
+    }
+
+    /// Draw a stroke of `width`, or no stroke if `0`.
+    pub fn with_stroke(mut self, width: u32) -> Self {
+        self.stroke = NonZeroU32::new(width);
+        self
+    }
+
+    pub fn with_weight(mut self, weight: Weight) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn with_underline(mut self) -> Self {
+        self.underline = Some(true);
+        self
+    }
+
+    pub fn with_strikethrough(mut self) -> Self {
+        self.strikethrough = Some(true);
+        self
+    }
+
+    pub fn with_layer(mut self, layer: i8) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<Arc<str>>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    pub fn with_ruby(mut self, ruby: impl Into<Arc<str>>) -> Self {
+        self.ruby = Some(ruby.into());
+        self
+    }
+
+    /// Variable font axes of this segment.
+    pub(crate) fn variations<'t>(&'t self, base: &'t Text3dStyling) -> Cow<'t, [(AxisTag, f32)]> {
+        merge_variations(&base.font_variations, &self.font_variations)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -146,6 +412,95 @@ pub struct GlyphEntry {
     pub size: FloatOrd,
     pub weight: Weight,
     pub stroke: Option<NonZeroU32>,
+    pub padding: u16,
+    pub scale_factor: FloatOrd,
+    /// See [`variations_key`].
+    pub variations: u64,
+    /// See [`StrokeOutline::key`].
+    pub outline: u64,
+}
+
+/// Stroke settings other than the width, see [`Text3dStyling::stroke_outline`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StrokeOutline {
+    pub join: StrokeJoin,
+    pub caps: (StrokeCap, StrokeCap),
+    pub miter_limit: f32,
+    pub dash: Vec<f32>,
+}
+
+impl Default for StrokeOutline {
+    fn default() -> Self {
+        StrokeOutline::from_join(StrokeJoin::Round)
+    }
+}
+
+impl StrokeOutline {
+    pub fn from_join(join: StrokeJoin) -> Self {
+        StrokeOutline {
+            join,
+            caps: (StrokeCap::Round, StrokeCap::Round),
+            miter_limit: 4.0,
+            dash: Vec::new(),
+        }
+    }
+
+    /// Hash of caps, miter limit and dashes used as part of [`GlyphEntry`], `0` if default.
+    pub fn key(caps: (StrokeCap, StrokeCap), miter_limit: f32, dash: &[f32]) -> u64 {
+        if caps == (StrokeCap::Round, StrokeCap::Round) && miter_limit == 4.0 && dash.is_empty() {
+            return 0;
+        }
+        let mut hasher = FxHasher::default();
+        caps.hash(&mut hasher);
+        miter_limit.to_bits().hash(&mut hasher);
+        for length in dash {
+            length.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// Merge variable font axes, axes in `other` take priority.
+pub(crate) fn merge_variations<'t>(
+    base: &'t [(AxisTag, f32)],
+    other: &'t [(AxisTag, f32)],
+) -> Cow<'t, [(AxisTag, f32)]> {
+    if other.is_empty() {
+        return Cow::Borrowed(base);
+    }
+    if base.is_empty() {
+        return Cow::Borrowed(other);
+    }
+    let mut result: Vec<_> = base
+        .iter()
+        .filter(|(tag, _)| !other.iter().any(|(t, _)| t == tag))
+        .copied()
+        .collect();
+    result.extend_from_slice(other);
+    Cow::Owned(result)
+}
+
+/// Hash of variable font axes used as part of [`GlyphEntry`], `0` if empty.
+pub(crate) fn variations_key(variations: &[(AxisTag, f32)]) -> u64 {
+    if variations.is_empty() {
+        return 0;
+    }
+    let mut hasher = FxHasher::default();
+    for (tag, value) in variations {
+        tag.hash(&mut hasher);
+        value.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+pub(crate) fn font_features<'t>(
+    features: impl IntoIterator<Item = &'t (FeatureTag, u32)>,
+) -> FontFeatures {
+    let mut result = FontFeatures::new();
+    for (tag, value) in features {
+        result.set((*tag).into(), *value);
+    }
+    result
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -153,6 +508,45 @@ pub enum GlyphTextureOf {
     Id(u16),
     UnderlineTexture,
     StrikethroughTexture,
+    Icon(AssetId<Image>),
+    /// Texture of a [`Text3dDrawer`](crate::Text3dDrawer).
+    Custom(u64),
+    /// Texture of [`GlyphPlaceholder::Box`](crate::GlyphPlaceholder::Box).
+    Placeholder,
+    /// Fill and stroke of a glyph in different channels, see [`Text3dStyling::two_tone`].
+    TwoTone(u16),
+    /// A glyph blurred by a radius, see [`Text3dStyling::text_shadows`].
+    Blurred(u16, FloatOrd),
+    /// A glyph dilated and blurred by a radius with an intensity, see [`Text3dStyling::glow`].
+    Glow(u16, FloatOrd, FloatOrd),
+}
+
+/// How a glyph is rasterized into the atlas.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) enum GlyphRaster {
+    /// Coverage in the alpha channel.
+    #[default]
+    Coverage,
+    /// See [`Text3dStyling::two_tone`].
+    TwoTone,
+    /// Coverage blurred by a radius in the same unit as [`Text3dStyling::size`],
+    /// with a number of passes, see [`TextQuality::blur_passes`](crate::TextQuality::blur_passes).
+    Blur(FloatOrd, u8),
+    /// Coverage dilated and blurred by a radius, multiplied by an intensity, with a number of passes.
+    Glow(FloatOrd, FloatOrd, u8),
+}
+
+impl GlyphRaster {
+    pub fn texture_of(self, glyph_id: u16) -> GlyphTextureOf {
+        match self {
+            GlyphRaster::Coverage => GlyphTextureOf::Id(glyph_id),
+            GlyphRaster::TwoTone => GlyphTextureOf::TwoTone(glyph_id),
+            GlyphRaster::Blur(radius, _) => GlyphTextureOf::Blurred(glyph_id, radius),
+            GlyphRaster::Glow(radius, intensity, _) => {
+                GlyphTextureOf::Glow(glyph_id, radius, intensity)
+            }
+        }
+    }
 }
 
 impl From<u16> for GlyphTextureOf {
@@ -3,13 +3,18 @@ use bevy::{
     math::{IVec2, Rect, Vec2},
 };
 use cosmic_text::ttf_parser::OutlineBuilder;
-use zeno::{Cap, Command, Format, Mask, Stroke, Style, Transform, Vector};
+use zeno::{Command, Format, Mask, Stroke, Style, Transform, Vector};
 
-use crate::{styling::GlyphEntry, TextAtlas};
+use crate::{
+    styling::{GlyphEntry, StrokeOutline},
+    TextAtlas,
+};
 
 #[derive(Debug, Default)]
 pub(crate) struct CommandEncoder {
     pub commands: Vec<Command>,
+    /// Stroke settings of the current glyph, see [`CommandEncoder::set_outline`].
+    outline: StrokeOutline,
 }
 
 impl OutlineBuilder for CommandEncoder {
@@ -40,6 +45,29 @@ impl OutlineBuilder for CommandEncoder {
 }
 
 impl CommandEncoder {
+    /// Set stroke settings for the next glyph, dashes are multiplied by `unit`.
+    pub fn set_outline(&mut self, outline: &StrokeOutline, unit: f32) {
+        self.outline.join = outline.join;
+        self.outline.caps = outline.caps;
+        self.outline.miter_limit = outline.miter_limit;
+        self.outline.dash.clear();
+        self.outline
+            .dash
+            .extend(outline.dash.iter().map(|x| x * unit));
+    }
+
+    fn stroke(&self, width: f32) -> Style<'_> {
+        Style::Stroke(Stroke {
+            width,
+            join: self.outline.join.into(),
+            miter_limit: self.outline.miter_limit,
+            start_cap: self.outline.caps.0.into(),
+            end_cap: self.outline.caps.1.into(),
+            dashes: &self.outline.dash,
+            ..Default::default()
+        })
+    }
+
     /// Returns a rectangle and an additional offset, keep in mind both has to be applied scale factor before usage.
     pub fn tess_glyph(
         &self,
@@ -51,13 +79,7 @@ impl CommandEncoder {
     ) -> Option<(Rect, Vec2)> {
         let (alpha_map, bb) = if let Some(stroke) = stroke {
             Mask::new(&self.commands)
-                .style(Style::Stroke(Stroke {
-                    width: stroke,
-                    start_cap: Cap::Round,
-                    end_cap: Cap::Round,
-                    join: entry.join.into(),
-                    ..Default::default()
-                }))
+                .style(self.stroke(stroke))
                 .transform(Some(Transform::scale(scale, scale)))
                 .format(Format::Alpha)
                 .render()
@@ -68,15 +90,177 @@ impl CommandEncoder {
                 .render()
         };
         let (w, h) = (bb.width as usize, bb.height as usize);
-        let base = Vec2::new(bb.left as f32, bb.top as f32);
-        let pixel_rect = atlas.cache(image, entry, base, w, h, |buffer, pitch| {
+        // Transparent margin around the glyph, the atlas is already transparent.
+        let padding = entry.padding as usize;
+        let base = Vec2::new(bb.left as f32, bb.top as f32) - padding as f32;
+        let (pw, ph) = (w + padding * 2, h + padding * 2);
+        let pixel_rect = atlas.cache(image, entry, base, pw, ph, |buffer, pitch| {
             for x in 0..w {
                 for y in 0..h {
-                    buffer[y * pitch + x * 4 + 3] = alpha_map[y * w + x]
+                    buffer[(y + padding) * pitch + (x + padding) * 4 + 3] = alpha_map[y * w + x]
+                }
+            }
+            IVec2::new(pw as i32, ph as i32)
+        });
+        Some((pixel_rect, base))
+    }
+
+    /// Draws fill coverage to alpha and stroke coverage to red, see [`Text3dStyling::two_tone`](crate::Text3dStyling::two_tone).
+    pub fn tess_two_tone(
+        &self,
+        stroke: f32,
+        scale: f32,
+        atlas: &mut TextAtlas,
+        image: &mut Image,
+        entry: GlyphEntry,
+    ) -> Option<(Rect, Vec2)> {
+        let (fill_map, fill_bb) = Mask::new(&self.commands)
+            .transform(Some(Transform::scale(scale, scale)))
+            .format(Format::Alpha)
+            .render();
+        let (stroke_map, stroke_bb) = Mask::new(&self.commands)
+            .style(self.stroke(stroke))
+            .transform(Some(Transform::scale(scale, scale)))
+            .format(Format::Alpha)
+            .render();
+        let left = fill_bb.left.min(stroke_bb.left);
+        let top = fill_bb.top.min(stroke_bb.top);
+        let right =
+            (fill_bb.left + fill_bb.width as i32).max(stroke_bb.left + stroke_bb.width as i32);
+        let bottom =
+            (fill_bb.top + fill_bb.height as i32).max(stroke_bb.top + stroke_bb.height as i32);
+        let padding = entry.padding as usize;
+        let base = Vec2::new(left as f32, top as f32) - padding as f32;
+        let (w, h) = ((right - left) as usize, (bottom - top) as usize);
+        let (pw, ph) = (w + padding * 2, h + padding * 2);
+        let sample = |map: &[u8], bb: &zeno::Placement, x: i32, y: i32| {
+            let (x, y) = (x - bb.left, y - bb.top);
+            if x < 0 || y < 0 || x >= bb.width as i32 || y >= bb.height as i32 {
+                0
+            } else {
+                map[y as usize * bb.width as usize + x as usize]
+            }
+        };
+        let pixel_rect = atlas.cache(image, entry, base, pw, ph, |buffer, pitch| {
+            // Overwrite the padding as well, the atlas background is white.
+            for y in 0..ph {
+                for x in 0..pw {
+                    let (gx, gy) = (
+                        x as i32 - padding as i32 + left,
+                        y as i32 - padding as i32 + top,
+                    );
+                    let i = y * pitch + x * 4;
+                    buffer[i] = sample(&stroke_map, &stroke_bb, gx, gy);
+                    buffer[i + 1] = 0;
+                    buffer[i + 2] = 0;
+                    buffer[i + 3] = sample(&fill_map, &fill_bb, gx, gy);
+                }
+            }
+            IVec2::new(pw as i32, ph as i32)
+        });
+        Some((pixel_rect, base))
+    }
+
+    /// Draws coverage blurred by `radius` pixels and multiplied by `gain`, used by soft text shadows and glows.
+    ///
+    /// If `fill` is true and `stroke` is set, draws the union of the fill and the stroke.
+    /// `passes` box blurs approximate a gaussian blur, see [`TextQuality::blur_passes`](crate::TextQuality::blur_passes).
+    pub fn tess_blurred(
+        &self,
+        stroke: Option<f32>,
+        fill: bool,
+        radius: f32,
+        passes: u8,
+        gain: f32,
+        scale: f32,
+        atlas: &mut TextAtlas,
+        image: &mut Image,
+        entry: GlyphEntry,
+    ) -> Option<(Rect, Vec2)> {
+        let mut mask = Mask::new(&self.commands);
+        if let Some(stroke) = stroke {
+            mask.style(self.stroke(stroke));
+        }
+        let (alpha_map, bb) = mask
+            .transform(Some(Transform::scale(scale, scale)))
+            .format(Format::Alpha)
+            .render();
+        let (w, h) = (bb.width as usize, bb.height as usize);
+        // Each box blur spreads coverage by `box_radius`.
+        let passes = passes.max(1) as usize;
+        let box_radius = (radius / passes as f32).round().max(1.0) as usize;
+        let margin = box_radius * passes;
+        let (bw, bh) = (w + margin * 2, h + margin * 2);
+        let mut blurred = vec![0.0f32; bw * bh];
+        for y in 0..h {
+            for x in 0..w {
+                blurred[(y + margin) * bw + x + margin] = alpha_map[y * w + x] as f32;
+            }
+        }
+        if fill && stroke.is_some() {
+            let (fill_map, fill_bb) = Mask::new(&self.commands)
+                .transform(Some(Transform::scale(scale, scale)))
+                .format(Format::Alpha)
+                .render();
+            // The fill is inside of the stroke's bounds.
+            for y in 0..fill_bb.height as usize {
+                for x in 0..fill_bb.width as usize {
+                    let bx = x as i32 + fill_bb.left - bb.left + margin as i32;
+                    let by = y as i32 + fill_bb.top - bb.top + margin as i32;
+                    if bx < 0 || by < 0 || bx >= bw as i32 || by >= bh as i32 {
+                        continue;
+                    }
+                    let i = by as usize * bw + bx as usize;
+                    blurred[i] = blurred[i].max(fill_map[y * fill_bb.width as usize + x] as f32);
+                }
+            }
+        }
+        let mut scratch = Vec::new();
+        for _ in 0..passes {
+            box_blur(&mut blurred, &mut scratch, bw, bh, box_radius, 1, bw);
+            box_blur(&mut blurred, &mut scratch, bh, bw, box_radius, bw, 1);
+        }
+        let padding = entry.padding as usize;
+        let base = Vec2::new(bb.left as f32, bb.top as f32) - (margin + padding) as f32;
+        let (pw, ph) = (bw + padding * 2, bh + padding * 2);
+        let pixel_rect = atlas.cache(image, entry, base, pw, ph, |buffer, pitch| {
+            for y in 0..bh {
+                for x in 0..bw {
+                    buffer[(y + padding) * pitch + (x + padding) * 4 + 3] =
+                        (blurred[y * bw + x] * gain).round().clamp(0.0, 255.0) as u8;
                 }
             }
-            IVec2::new(w as i32, h as i32)
+            IVec2::new(pw as i32, ph as i32)
         });
         Some((pixel_rect, base))
     }
 }
+
+/// Box blur the rows of a 2d buffer in place,
+/// each row has `len` values `step` apart and rows start `stride` apart.
+fn box_blur(
+    data: &mut [f32],
+    scratch: &mut Vec<f32>,
+    len: usize,
+    rows: usize,
+    radius: usize,
+    step: usize,
+    stride: usize,
+) {
+    let window = (radius * 2 + 1) as f32;
+    for row in 0..rows {
+        let start = row * stride;
+        scratch.clear();
+        scratch.extend((0..len).map(|i| data[start + i * step]));
+        let mut sum: f32 = scratch.iter().take(radius + 1).sum();
+        for i in 0..len {
+            data[start + i * step] = sum / window;
+            if let Some(next) = scratch.get(i + radius + 1) {
+                sum += next;
+            }
+            if i >= radius {
+                sum -= scratch[i - radius];
+            }
+        }
+    }
+}
@@ -1,41 +1,191 @@
+use std::{
+    fmt::Debug,
+    ops::{Range, RangeBounds},
+    sync::Arc,
+};
+
 use bevy::ecs::{
     component::{Component, HookContext},
     entity::Entity,
-    world::{DeferredWorld, Mut},
+    system::{Commands, Query},
+    world::{DeferredWorld, Mut, World},
 };
 #[cfg(feature = "reflect")]
-use bevy::{ecs::reflect::ReflectComponent, reflect::Reflect};
+use bevy::{ecs::reflect::ReflectComponent, prelude::ReflectDefault, reflect::Reflect};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    styling::SegmentStyle, Text3dBounds, Text3dDimensionOut, Text3dStyling, TextAtlasHandle,
+    styling::SegmentStyle, FetchedTextSegment, SharedTextSegment, Text3dBounds, Text3dDimensionOut,
+    Text3dPlugin, Text3dStyling, TextAtlasHandle,
 };
 
 /// A rich text component.
 ///
 /// Requires [`Text3dStyling`], [`Text3dBounds`], [`TextAtlasHandle`], [`Text3dDimensionOut`].
-#[derive(Debug, Component)]
+///
+/// # Cloning
+///
+/// [`Text3dSegment::Extract`] refers to an entity that is despawned when the [`Text3d`] is removed,
+/// copying the segments to another entity, i.e. via entity cloning with the `reflect` feature,
+/// shares these entities and despawns them when either copy is removed.
+/// Use [`Text3d::deep_clone`] to copy them instead.
+///
+/// To keep these entities, mark them as [`SharedTextSegment`](crate::SharedTextSegment),
+/// add [`Text3dKeepSegments`] to this entity or move them out with [`Text3d::take_segments`].
+#[derive(Debug, Component, Default)]
 #[require(Text3dDimensionOut, Text3dBounds, TextAtlasHandle, Text3dStyling)]
 #[component(on_remove = text_3d_on_remove)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text3d {
     pub segments: Vec<(Text3dSegment, SegmentStyle)>,
 }
 
+/// Prevents [`Text3d`] on this entity from despawning its [`Text3dSegment::Extract`] entities on remove,
+/// for pooled or reused segment entities.
+#[derive(Debug, Component, Default, Clone, Copy)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub struct Text3dKeepSegments;
+
+/// What happens to the [`Text3dSegment::Extract`] entities of a [`Text3d`] when it is removed.
+///
+/// Set for all entities with [`Text3dPlugin::segment_removal`](crate::Text3dPlugin::segment_removal),
+/// or add this component to the [`Text3d`] entity to override it.
+/// Entities marked [`SharedTextSegment`](crate::SharedTextSegment) are always kept,
+/// add [`Text3dKeepSegments`] to keep all of them.
+#[derive(Debug, Component, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component, Default))]
+pub enum SegmentRemoval {
+    /// Despawn them immediately.
+    #[default]
+    Immediate,
+    /// Despawn them after this many frames, so fading out text can still read their values.
+    Deferred(u32),
+}
+
+/// Despawns a segment entity after this many frames, see [`SegmentRemoval::Deferred`].
+#[derive(Debug, Component)]
+pub(crate) struct DeferredSegmentDespawn(u32);
+
 /// A string segment in [`Text3d`].
 ///
 /// `Extract` reads data from an entity's [`FetchedTextSegment`](crate::FetchedTextSegment) component.
-#[derive(Debug)]
+///
+/// `Image` is the name of an icon in [`Text3dIcons`](crate::Text3dIcons), drawn inline as a single glyph.
+///
+/// `Interned` is a shared string, see [`Text3dInterner`](crate::Text3dInterner),
+/// for labels repeated across many texts.
+///
+/// `Computed` is a function of the [`World`] evaluated every frame before layout,
+/// for simple values like elapsed time or counts without spawning a [`TextFetch`](crate::TextFetch).
+///
+/// With the `serde` feature, `Extract` is serialized as a placeholder without the entity,
+/// and deserialized as an empty `String`. `Computed` is serialized as its current value.
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 pub enum Text3dSegment {
     String(String),
     Extract(Entity),
+    Image(String),
+    Computed(ComputedSegment),
+    Interned(Arc<str>),
+}
+
+/// A function of the [`World`] and its last value, see [`Text3dSegment::Computed`].
+///
+/// The text is only redrawn if the value changes.
+#[derive(Clone)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(opaque))]
+pub struct ComputedSegment {
+    compute: Arc<dyn Fn(&World) -> String + Send + Sync>,
+    pub(crate) value: String,
+}
+
+impl ComputedSegment {
+    pub fn new(compute: impl Fn(&World) -> String + Send + Sync + 'static) -> Self {
+        ComputedSegment {
+            compute: Arc::new(compute),
+            value: String::new(),
+        }
+    }
+
+    /// The last computed value.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub(crate) fn compute(&self, world: &World) -> String {
+        (self.compute)(world)
+    }
+}
+
+impl Debug for ComputedSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ComputedSegment").field(&self.value).finish()
+    }
+}
+
+impl PartialEq for ComputedSegment {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.compute, &other.compute) && self.value == other.value
+    }
+}
+
+impl Eq for ComputedSegment {}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Text3dSegment")]
+enum SerdeSegment<'t> {
+    String(std::borrow::Cow<'t, str>),
+    Extract,
+    Image(std::borrow::Cow<'t, str>),
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Text3dSegment {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Text3dSegment::String(s) => SerdeSegment::String(s.into()),
+            Text3dSegment::Interned(s) => SerdeSegment::String(s.as_ref().into()),
+            Text3dSegment::Extract(_) => SerdeSegment::Extract,
+            Text3dSegment::Image(s) => SerdeSegment::Image(s.into()),
+            Text3dSegment::Computed(c) => SerdeSegment::String(c.value().into()),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Text3dSegment {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match SerdeSegment::deserialize(deserializer)? {
+            SerdeSegment::String(s) => Text3dSegment::String(s.into_owned()),
+            SerdeSegment::Extract => Text3dSegment::String(String::new()),
+            SerdeSegment::Image(s) => Text3dSegment::Image(s.into_owned()),
+        })
+    }
 }
 
 fn text_3d_on_remove(mut world: DeferredWorld, cx: HookContext) {
     let Ok(entity) = world.get_entity(cx.entity) else {
         return;
     };
+    if entity.contains::<Text3dKeepSegments>() {
+        return;
+    }
+    let removal = entity
+        .get::<SegmentRemoval>()
+        .copied()
+        .or_else(|| {
+            world
+                .get_resource::<Text3dPlugin>()
+                .map(|x| x.segment_removal)
+        })
+        .unwrap_or_default();
     let Some(text) = entity.get::<Text3d>() else {
         return;
     };
@@ -43,13 +193,41 @@ fn text_3d_on_remove(mut world: DeferredWorld, cx: HookContext) {
         .segments
         .iter()
         .filter_map(|x| match &x.0 {
-            Text3dSegment::String(_) => None,
             Text3dSegment::Extract(entity) => Some(*entity),
+            _ => None,
+        })
+        .filter(|entity| {
+            world
+                .get_entity(*entity)
+                .is_ok_and(|x| !x.contains::<SharedTextSegment>())
         })
         .collect();
     let mut commands = world.commands();
     for entity in to_be_dropped {
-        commands.entity(entity).try_despawn();
+        match removal {
+            SegmentRemoval::Deferred(frames) => {
+                commands
+                    .entity(entity)
+                    .try_insert(DeferredSegmentDespawn(frames));
+            }
+            SegmentRemoval::Immediate => {
+                commands.entity(entity).try_despawn();
+            }
+        }
+    }
+}
+
+/// Despawns segment entities of removed [`Text3d`]s after [`SegmentRemoval::Deferred`] frames.
+pub(crate) fn deferred_segment_despawn_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut DeferredSegmentDespawn)>,
+) {
+    for (entity, mut frames) in query.iter_mut() {
+        if frames.0 == 0 {
+            commands.entity(entity).try_despawn();
+        } else {
+            frames.0 -= 1;
+        }
     }
 }
 
@@ -70,14 +248,243 @@ impl Text3d {
         }
     }
 
-    /// If only contains an owned segment, return that segment as a `&str`.
+    /// Replace all segments, only triggering change detection if they differ,
+    /// so re-parsing templated text does not re-mesh when the result is the same.
+    ///
+    /// Returns true if changed. Add [`Text3dShapeCache`](crate::Text3dShapeCache)
+    /// to only reshape lines that changed otherwise.
+    pub fn set_segments(this: Mut<Self>, segments: Vec<(Text3dSegment, SegmentStyle)>) -> bool {
+        Self::splice(this, .., segments)
+    }
+
+    /// Replace a range of segments, only triggering change detection if they differ,
+    /// see [`Text3d::set_segments`].
+    ///
+    /// # Panics
+    ///
+    /// If `range` is out of bounds.
+    pub fn splice(
+        mut this: Mut<Self>,
+        range: impl RangeBounds<usize>,
+        segments: impl IntoIterator<Item = (Text3dSegment, SegmentStyle)>,
+    ) -> bool {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        let segments: Vec<_> = segments.into_iter().collect();
+        if this.segments[range] == segments[..] {
+            return false;
+        }
+        this.segments.splice(range, segments);
+        true
+    }
+
+    /// Remove all segments, so their [`Text3dSegment::Extract`] entities are not despawned
+    /// when this [`Text3d`] is removed, i.e. to move them to another [`Text3d`].
+    pub fn take_segments(&mut self) -> Vec<(Text3dSegment, SegmentStyle)> {
+        std::mem::take(&mut self.segments)
+    }
+
+    /// Clone the segments, spawning a copy of each [`Text3dSegment::Extract`] entity.
+    ///
+    /// Components that implement [`Clone`] are copied, including [`FetchedTextSegment`](crate::FetchedTextSegment)
+    /// and [`TextFetch`](crate::TextFetch), whose clone shares the same fetch function.
+    /// Entities marked [`SharedTextSegment`](crate::SharedTextSegment) are copied as well.
+    /// If an entity no longer exists, its copy is left empty.
+    pub fn deep_clone(&self, commands: &mut Commands) -> Text3d {
+        let segments = self
+            .segments
+            .iter()
+            .map(|(segment, style)| {
+                let segment = match segment {
+                    Text3dSegment::String(s) => Text3dSegment::String(s.clone()),
+                    Text3dSegment::Interned(s) => Text3dSegment::Interned(s.clone()),
+                    Text3dSegment::Image(s) => Text3dSegment::Image(s.clone()),
+                    Text3dSegment::Computed(c) => Text3dSegment::Computed(c.clone()),
+                    Text3dSegment::Extract(source) => {
+                        let source = *source;
+                        let target = commands.spawn_empty().id();
+                        commands.queue(move |world: &mut World| {
+                            if let Ok(mut entity) = world.get_entity_mut(source) {
+                                entity.clone_with(target, |_| ());
+                            }
+                        });
+                        Text3dSegment::Extract(target)
+                    }
+                };
+                (segment, style.clone())
+            })
+            .collect();
+        Text3d { segments }
+    }
+
+    /// Concatenate all segments as plain text, with [`Text3dSegment::Extract`] resolved to their current values,
+    /// for logging, accessibility or copying to the clipboard.
+    ///
+    /// Images and missing entities are skipped.
+    pub fn resolve_plain(&self, segments: &Query<&FetchedTextSegment>) -> String {
+        self.to_plain_string(|entity| segments.get(entity).ok().map(FetchedTextSegment::as_str))
+    }
+
+    /// Concatenate all segments as plain text, see [`Text3d::resolve_plain`].
+    pub fn resolve_plain_world(&self, world: &World) -> String {
+        self.to_plain_string(|entity| {
+            world
+                .get::<FetchedTextSegment>(entity)
+                .map(FetchedTextSegment::as_str)
+        })
+    }
+
+    /// Concatenate all segments as plain text, with [`Text3dSegment::Extract`] resolved by `resolve`.
+    ///
+    /// Images and unresolved entities are skipped.
+    pub fn to_plain_string<'t>(
+        &'t self,
+        resolve: impl Fn(Entity) -> Option<&'t str> + 't,
+    ) -> String {
+        self.resolved_segments(resolve).map(|(_, _, s)| s).collect()
+    }
+
+    /// Iterate over segments, their styles and their text, with [`Text3dSegment::Extract`] resolved by `resolve`.
+    ///
+    /// Images and unresolved entities yield an empty string.
+    pub fn resolved_segments<'t>(
+        &'t self,
+        resolve: impl Fn(Entity) -> Option<&'t str> + 't,
+    ) -> impl Iterator<Item = (&'t Text3dSegment, &'t SegmentStyle, &'t str)> + 't {
+        self.segments.iter().map(move |(segment, style)| {
+            let s = match segment {
+                Text3dSegment::String(s) => s.as_str(),
+                Text3dSegment::Interned(s) => s,
+                Text3dSegment::Extract(entity) => resolve(*entity).unwrap_or_default(),
+                Text3dSegment::Computed(c) => c.value(),
+                Text3dSegment::Image(_) => "",
+            };
+            (segment, style, s)
+        })
+    }
+
+    /// Number of `char`s in the plain text, see [`Text3d::to_plain_string`].
+    pub fn char_len<'t>(&'t self, resolve: impl Fn(Entity) -> Option<&'t str> + 't) -> usize {
+        self.resolved_segments(resolve)
+            .map(|(_, _, s)| s.chars().count())
+            .sum()
+    }
+
+    /// Number of words in the plain text by unicode word boundaries, see [`Text3d::to_plain_string`].
+    ///
+    /// Words spanning multiple segments are counted once.
+    pub fn word_count<'t>(&'t self, resolve: impl Fn(Entity) -> Option<&'t str> + 't) -> usize {
+        self.to_plain_string(resolve).unicode_words().count()
+    }
+
+    /// Apply a style to a byte range of the plain text, splitting segments as needed,
+    /// for search highlighting or emphasis without re-authoring the source string.
+    ///
+    /// Byte offsets match [`Text3d::resolve_plain`] and are moved to the previous character boundary.
+    /// `style` takes priority over the segments' existing styles.
+    /// [`Text3dSegment::Extract`] counts as empty, see [`Text3d::apply_style_resolved`].
+    pub fn apply_style(&mut self, range: Range<usize>, style: SegmentStyle) {
+        self.apply_style_with(range, style, |_| 0)
+    }
+
+    /// Apply a style to a byte range of the plain text, see [`Text3d::apply_style`].
+    ///
+    /// [`Text3dSegment::Extract`] counts as its current value like [`Text3dSegment::Computed`]
+    /// and is styled as a whole if it overlaps the range.
+    pub fn apply_style_resolved(
+        &mut self,
+        range: Range<usize>,
+        style: SegmentStyle,
+        segments: &Query<&FetchedTextSegment>,
+    ) {
+        self.apply_style_with(range, style, |entity| {
+            segments.get(entity).map(|x| x.as_str().len()).unwrap_or(0)
+        })
+    }
+
+    fn apply_style_with(
+        &mut self,
+        range: Range<usize>,
+        style: SegmentStyle,
+        len: impl Fn(Entity) -> usize,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let mut offset = 0;
+        let mut result = Vec::with_capacity(self.segments.len() + 2);
+        for (segment, segment_style) in self.segments.drain(..) {
+            let start = offset;
+            match segment {
+                Text3dSegment::String(_) | Text3dSegment::Interned(_) => {
+                    let s: &str = match &segment {
+                        Text3dSegment::String(s) => s,
+                        Text3dSegment::Interned(s) => s,
+                        _ => "",
+                    };
+                    offset += s.len();
+                    if range.end <= start || range.start >= offset {
+                        result.push((segment, segment_style));
+                        continue;
+                    }
+                    let boundary = |i: usize| {
+                        let mut i = i.saturating_sub(start).min(s.len());
+                        while !s.is_char_boundary(i) {
+                            i -= 1;
+                        }
+                        i
+                    };
+                    let (a, b) = (boundary(range.start), boundary(range.end));
+                    let joined = segment_style.join(style.clone());
+                    for (part, part_style) in [
+                        (&s[..a], &segment_style),
+                        (&s[a..b], &joined),
+                        (&s[b..], &segment_style),
+                    ] {
+                        if !part.is_empty() {
+                            result.push((Text3dSegment::String(part.into()), part_style.clone()));
+                        }
+                    }
+                }
+                Text3dSegment::Extract(_) | Text3dSegment::Computed(_) => {
+                    offset += match &segment {
+                        Text3dSegment::Extract(entity) => len(*entity),
+                        Text3dSegment::Computed(c) => c.value().len(),
+                        _ => 0,
+                    };
+                    let overlaps = if offset == start {
+                        range.contains(&start)
+                    } else {
+                        range.start < offset && range.end > start
+                    };
+                    let segment_style = if overlaps {
+                        segment_style.join(style.clone())
+                    } else {
+                        segment_style
+                    };
+                    result.push((segment, segment_style));
+                }
+                Text3dSegment::Image(name) => {
+                    let segment_style = if range.contains(&start) {
+                        segment_style.join(style.clone())
+                    } else {
+                        segment_style
+                    };
+                    result.push((Text3dSegment::Image(name), segment_style));
+                }
+            }
+        }
+        self.segments = result;
+    }
+
+    /// If only contains an owned or interned segment, return that segment as a `&str`.
     pub fn get_single(&self) -> Option<&str> {
         if self.segments.len() != 1 {
-            None
-        } else if let Some((Text3dSegment::String(s), _)) = self.segments.first() {
-            Some(s)
-        } else {
-            None
+            return None;
+        }
+        match self.segments.first() {
+            Some((Text3dSegment::String(s), _)) => Some(s),
+            Some((Text3dSegment::Interned(s), _)) => Some(s),
+            _ => None,
         }
     }
 
@@ -0,0 +1,48 @@
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+@group(2) @binding(103) var<uniform> stroke_color: vec4<f32>;
+@group(2) @binding(104) var atlas_texture: texture_2d<f32>;
+@group(2) @binding(105) var atlas_sampler: sampler;
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+#ifdef VERTEX_UVS_A
+    // Fill coverage is in alpha, stroke coverage is in red.
+    let coverage = textureSample(atlas_texture, atlas_sampler, in.uv);
+    let fill_color = pbr_input.material.base_color;
+    let fill = coverage.a * fill_color.a;
+    let stroke = coverage.r * stroke_color.a * (1.0 - fill);
+    let alpha = fill + stroke;
+    let rgb = (fill_color.rgb * fill + stroke_color.rgb * stroke) / max(alpha, 0.0001);
+    pbr_input.material.base_color = vec4(rgb, alpha);
+#endif
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+    return out;
+}
@@ -0,0 +1,151 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        schedule::IntoScheduleConfigs,
+        system::Query,
+    },
+    math::{Vec2, Vec3},
+    render::{
+        camera::Camera,
+        view::{InheritedVisibility, Visibility},
+    },
+    transform::{
+        components::{GlobalTransform, Transform},
+        TransformSystem,
+    },
+    ui::{ComputedNode, ComputedNodeTarget, Node},
+};
+
+use crate::{Text3d, Text3dBounds, Text3dDimensionOut};
+
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+/// Places a [`Text3d`] over a UI [`Node`], for mesh based text effects in menus.
+///
+/// The text should be a root entity rendered by a 2d camera, usually the camera the UI is rendered to.
+/// Its translation is set to the center of the node, its [`Text3dBounds`] to the width of the node,
+/// and its [`Visibility`] follows the node. Set [`TextAnchor::CENTER`](crate::TextAnchor::CENTER) to center the text.
+///
+/// With `scale_to_fit`, the text is not wrapped and is instead scaled down to fit the node.
+///
+/// Requires [`Text3dUiPlugin`].
+#[derive(Debug, Clone, Copy, Component)]
+#[require(Text3d)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct Text3dUiNode {
+    /// The UI [`Node`] to follow.
+    pub node: Entity,
+    /// If true, scales the text down uniformly so its [`Text3dDimensionOut`] fits inside the node,
+    /// instead of wrapping it at the node's width.
+    pub scale_to_fit: bool,
+}
+
+impl Text3dUiNode {
+    pub fn new(node: Entity) -> Self {
+        Self {
+            node,
+            scale_to_fit: false,
+        }
+    }
+
+    pub fn scale_to_fit(mut self) -> Self {
+        self.scale_to_fit = true;
+        self
+    }
+}
+
+/// Synchronizes [`Text3dUiNode`]s with UI layout.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Text3dUiPlugin;
+
+impl Plugin for Text3dUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            text_ui_node_system.after(TransformSystem::TransformPropagate),
+        );
+        #[cfg(feature = "reflect")]
+        app.register_type::<Text3dUiNode>();
+    }
+}
+
+/// Places [`Text3dUiNode`]s over their UI nodes.
+///
+/// Runs after transform propagation so the text's [`GlobalTransform`] is written directly.
+pub fn text_ui_node_system(
+    nodes: Query<
+        (
+            &ComputedNode,
+            &ComputedNodeTarget,
+            &GlobalTransform,
+            &InheritedVisibility,
+        ),
+        (With<Node>, Without<Text3dUiNode>),
+    >,
+    cameras: Query<(&Camera, &GlobalTransform), Without<Text3dUiNode>>,
+    mut texts: Query<(
+        &Text3dUiNode,
+        &mut Transform,
+        &mut GlobalTransform,
+        &mut Text3dBounds,
+        &mut Visibility,
+        &Text3dDimensionOut,
+    )>,
+) {
+    for (ui, mut transform, mut global, mut bounds, mut visibility, output) in texts.iter_mut() {
+        let Ok((node, target, node_transform, node_visibility)) = nodes.get(ui.node) else {
+            continue;
+        };
+        let Some((camera, camera_transform)) =
+            target.camera().and_then(|camera| cameras.get(camera).ok())
+        else {
+            continue;
+        };
+        let expected = if node_visibility.get() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(expected);
+        // UI transforms and sizes are in physical pixels, viewports are in logical pixels.
+        let scale = node.inverse_scale_factor();
+        let center = node_transform.translation().truncate() * scale;
+        let half = node.size() * scale / 2.0;
+        let to_world = |position: Vec2| camera.viewport_to_world_2d(camera_transform, position);
+        let (Ok(world_center), Ok(min), Ok(max)) = (
+            to_world(center),
+            to_world(center - half),
+            to_world(center + half),
+        ) else {
+            continue;
+        };
+        let size = (max - min).abs();
+        // Wrapping would change the dimension used for scaling.
+        let (width, text_scale) = if ui.scale_to_fit {
+            let fac = (size / output.dimension.max(Vec2::splat(f32::EPSILON)))
+                .min_element()
+                .min(1.0);
+            (f32::INFINITY, Vec3::new(fac, fac, 1.0))
+        } else {
+            (
+                size.x / transform.scale.x.max(f32::EPSILON),
+                transform.scale,
+            )
+        };
+        if bounds.width != width {
+            bounds.width = width;
+        }
+        let translation = world_center.extend(transform.translation.z);
+        if transform.translation != translation || transform.scale != text_scale {
+            transform.translation = translation;
+            transform.scale = text_scale;
+            *global = GlobalTransform::from(*transform);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::{
+    ecs::{
+        change_detection::DetectChanges,
+        entity::Entity,
+        event::{Event, EventWriter},
+        system::{Local, Query, Res},
+        world::Ref,
+    },
+    log::warn,
+};
+use cosmic_text::{fontdb::Database, Family};
+
+use crate::{prepare::family, Text3d, Text3dStyling, TextRenderer};
+
+/// Sent when a [`Text3d`] uses a font family that is not loaded.
+///
+/// The text is still rendered with a fallback font.
+/// Only sent when [`Text3dPlugin::validate_fonts`](crate::Text3dPlugin::validate_fonts) is enabled.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct Text3dMissingFont {
+    /// Entity of the [`Text3d`].
+    pub entity: Entity,
+    /// Index of the segment using the font, `None` if used by [`Text3dStyling`].
+    pub segment: Option<usize>,
+    /// Name of the missing family.
+    pub family: String,
+}
+
+fn has_family(db: &Database, name: &str) -> bool {
+    match family(name) {
+        Family::Name(name) => db.faces().any(|face| {
+            face.families
+                .iter()
+                .any(|(family, _)| family.eq_ignore_ascii_case(name))
+        }),
+        // Generic families always resolve to some font.
+        _ => true,
+    }
+}
+
+/// Sends [`Text3dMissingFont`] when a [`Text3d`] or its [`Text3dStyling`] changes,
+/// and for all text when fonts are added or removed.
+pub fn validate_fonts_system(
+    renderer: Res<TextRenderer>,
+    query: Query<(Entity, Ref<Text3d>, Ref<Text3dStyling>)>,
+    mut events: EventWriter<Text3dMissingFont>,
+    mut missed: Local<bool>,
+) {
+    let Ok(lock) = renderer.0.try_lock() else {
+        *missed = true;
+        return;
+    };
+    let db = lock.font_system.db();
+    let all = std::mem::take(&mut *missed) || renderer.is_changed();
+    for (entity, text, styling) in query.iter() {
+        if !all && !text.is_changed() && !styling.is_changed() {
+            continue;
+        }
+        let mut report = |segment: Option<usize>, name: &str| {
+            if has_family(db, name) {
+                return;
+            }
+            warn!("Font family {name:?} used by {entity} is not loaded.");
+            events.write(Text3dMissingFont {
+                entity,
+                segment,
+                family: name.to_owned(),
+            });
+        };
+        report(None, &styling.font);
+        for (index, (_, style)) in text.segments.iter().enumerate() {
+            if let Some(font) = &style.font {
+                report(Some(index), font);
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::sync::{
+    mpsc::{channel, Receiver, Sender},
+    Arc, Mutex,
+};
+
+use bevy::{
+    asset::{AssetId, Assets},
+    ecs::resource::Resource,
+    image::Image,
+    math::{IVec2, Vec2},
+};
+use cosmic_text::{ttf_parser::Face, Font};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    prepare::PendingGlyph, render::cache_glyph, styling::GlyphEntry, tess::CommandEncoder,
+    TextAtlas,
+};
+
+/// A glyph sent to a rasterization thread.
+struct RasterJob {
+    atlas: AssetId<TextAtlas>,
+    entry: GlyphEntry,
+    font: Arc<Font>,
+    glyph: PendingGlyph,
+    /// Width of the target atlas.
+    width: usize,
+}
+
+/// A glyph rasterized in the background, waiting to be copied into its atlas.
+#[derive(Debug)]
+pub(crate) struct RasterizedGlyph {
+    atlas: AssetId<TextAtlas>,
+    entry: GlyphEntry,
+    base: Vec2,
+    width: usize,
+    height: usize,
+    /// Empty if the glyph has no outline.
+    pixels: Vec<[u8; 4]>,
+}
+
+/// Threads that rasterize glyphs missing at runtime,
+/// see [`Text3dPlugin::rasterization_threads`](crate::Text3dPlugin::rasterization_threads).
+///
+/// Unlike [`Text3dPlugin::asynchronous_rasterization`](crate::Text3dPlugin::asynchronous_rasterization),
+/// the font system is not locked and text rendering is not paused, each glyph is drawn on a scratch image and copied into its atlas on the main thread.
+#[derive(Resource)]
+pub(crate) struct RasterWorker {
+    jobs: Sender<RasterJob>,
+    results: Mutex<Receiver<RasterizedGlyph>>,
+    /// Glyphs in flight, and glyphs without outlines so they are not requested again.
+    requested: FxHashSet<(AssetId<TextAtlas>, GlyphEntry)>,
+}
+
+impl RasterWorker {
+    /// Spawn `threads` threads that take glyphs from a shared queue.
+    pub fn new(threads: usize) -> Self {
+        let (jobs, job_receiver) = channel::<RasterJob>();
+        let (result_sender, results) = channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        for i in 0..threads {
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+            std::thread::Builder::new()
+                .name(format!("text3d-rasterizer-{i}"))
+                .spawn(move || {
+                    let mut tess_commands = CommandEncoder::default();
+                    loop {
+                        // Idle threads wait on the lock, whichever thread is free takes the next glyph.
+                        let Ok(job) = job_receiver.lock().unwrap().recv() else {
+                            return;
+                        };
+                        let result = rasterize(job, &mut tess_commands);
+                        if result_sender.send(result).is_err() {
+                            return;
+                        }
+                    }
+                })
+                .expect("Failed to spawn rasterization thread.");
+        }
+        RasterWorker {
+            jobs,
+            results: Mutex::new(results),
+            requested: FxHashSet::default(),
+        }
+    }
+
+    /// Queue a glyph if not already requested.
+    pub fn request(
+        &mut self,
+        atlas: AssetId<TextAtlas>,
+        entry: GlyphEntry,
+        font: Arc<Font>,
+        glyph: PendingGlyph,
+        width: usize,
+    ) {
+        if !self.requested.insert((atlas, entry)) {
+            return;
+        }
+        let _ = self.jobs.send(RasterJob {
+            atlas,
+            entry,
+            font,
+            glyph,
+            width,
+        });
+    }
+
+    /// Copy finished glyphs into their atlases, returns true if any glyph is added.
+    pub fn receive(&mut self, atlases: &mut Assets<TextAtlas>, images: &mut Assets<Image>) -> bool {
+        let mut added = false;
+        for glyph in self.results.get_mut().unwrap().try_iter() {
+            if glyph.pixels.is_empty() {
+                continue;
+            }
+            self.requested.remove(&(glyph.atlas, glyph.entry));
+            added |= glyph.blit(atlases, images);
+        }
+        added
+    }
+}
+
+impl RasterizedGlyph {
+    /// A glyph without outline, which is not added to the atlas.
+    fn empty(atlas: AssetId<TextAtlas>, entry: GlyphEntry) -> Self {
+        RasterizedGlyph {
+            atlas,
+            entry,
+            base: Vec2::ZERO,
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+        }
+    }
+
+    /// Rasterize a glyph on a scratch image as wide as its target atlas.
+    pub fn new(
+        atlas: AssetId<TextAtlas>,
+        entry: GlyphEntry,
+        face: Face,
+        glyph: &PendingGlyph,
+        width: usize,
+        tess_commands: &mut CommandEncoder,
+    ) -> Self {
+        let mut result = RasterizedGlyph::empty(atlas, entry);
+        let mut scratch = TextAtlas::default();
+        let mut image = TextAtlas::empty_image(width, 64);
+        let Some((rect, base)) = cache_glyph(
+            glyph.scale_factor,
+            &mut scratch,
+            &mut image,
+            tess_commands,
+            &glyph.glyph,
+            glyph.stroke,
+            &glyph.outline,
+            glyph.weight,
+            glyph.padding,
+            &glyph.variations,
+            glyph.raster,
+            face,
+        ) else {
+            return result;
+        };
+        let (Some(data), w) = (&image.data, image.width() as usize) else {
+            return result;
+        };
+        let min = rect.min.as_uvec2();
+        let max = rect.max.as_uvec2();
+        result.base = base;
+        result.width = (max.x - min.x) as usize;
+        result.height = (max.y - min.y) as usize;
+        for y in min.y as usize..max.y as usize {
+            let row = &data[(y * w + min.x as usize) * 4..(y * w + max.x as usize) * 4];
+            result
+                .pixels
+                .extend(row.chunks_exact(4).map(|x| [x[0], x[1], x[2], x[3]]));
+        }
+        result
+    }
+
+    /// Copy the glyph into the current state of its atlas, returns true if added.
+    pub fn blit(self, atlases: &mut Assets<TextAtlas>, images: &mut Assets<Image>) -> bool {
+        if self.pixels.is_empty() {
+            return false;
+        }
+        let Some(atlas) = atlases.get_mut(self.atlas) else {
+            return false;
+        };
+        let Some(image) = images.get_mut(atlas.image.id()) else {
+            return false;
+        };
+        let RasterizedGlyph {
+            entry,
+            base,
+            width,
+            height,
+            pixels,
+            ..
+        } = self;
+        atlas.cache(image, entry, base, width, height, |data, stride| {
+            for (y, row) in pixels.chunks(width).enumerate() {
+                data[y * stride..y * stride + row.len() * 4].copy_from_slice(row.as_flattened());
+            }
+            IVec2::new(width as i32, height as i32)
+        });
+        true
+    }
+}
+
+fn rasterize(job: RasterJob, tess_commands: &mut CommandEncoder) -> RasterizedGlyph {
+    let RasterJob {
+        atlas,
+        entry,
+        font,
+        glyph,
+        width,
+    } = job;
+    match Face::parse(font.data(), 0) {
+        Ok(face) => RasterizedGlyph::new(atlas, entry, face, &glyph, width, tess_commands),
+        Err(_) => RasterizedGlyph::empty(atlas, entry),
+    }
+}